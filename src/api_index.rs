@@ -0,0 +1,248 @@
+//! The `Special/ApiIndex` page, listing every function documented in a function table
+//! across the wiki
+
+use wikitext_simplified::WikitextSimplifiedNode as WSN;
+
+use crate::{config::ApiIndexColumns, site::Site, wikitext};
+
+/// The title of the page listing the documented functions
+pub const API_INDEX: &str = "Special/ApiIndex";
+
+/// A function found in a function table
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiEntry {
+    /// The function's name, e.g. `Execute`
+    pub name: String,
+    /// The function's signature, e.g. `Execute()`
+    pub signature: String,
+    /// What the function returns, if the table says
+    pub returns: String,
+    /// The title of the page documenting the function
+    pub page: String,
+    /// The heading of the section of `page` the table is in, if it's in one
+    pub section: Option<String>,
+}
+
+/// The functions listed in the function tables of the page `title`. A function table is
+/// one with a header cell reading `columns.signature`; pages without any give nothing.
+pub fn extract(title: &str, nodes: &[WSN], columns: &ApiIndexColumns) -> Vec<ApiEntry> {
+    let mut entries = vec![];
+    extract_from_nodes(title, nodes, columns, &mut None, &mut entries);
+    entries
+}
+
+fn extract_from_nodes(
+    title: &str,
+    nodes: &[WSN],
+    columns: &ApiIndexColumns,
+    section: &mut Option<String>,
+    entries: &mut Vec<ApiEntry>,
+) {
+    for node in nodes {
+        match node {
+            node if wikitext::is_code(node) => {}
+            WSN::Heading { children, .. } => {
+                let text = wikitext::plain_text(children);
+                *section = Some(text.trim().to_string()).filter(|text| !text.is_empty());
+            }
+            WSN::Table { rows, .. } => {
+                let cell_text = |content: &[WSN]| wikitext::plain_text(content).trim().to_string();
+                let Some(header) = rows.iter().position(|row| {
+                    row.cells
+                        .iter()
+                        .any(|cell| cell_text(&cell.content) == columns.signature)
+                }) else {
+                    continue;
+                };
+                let column = |name: &str| {
+                    rows[header]
+                        .cells
+                        .iter()
+                        .position(|cell| cell_text(&cell.content) == name)
+                };
+                let signature_column = column(&columns.signature);
+                let returns_column = column(&columns.returns);
+
+                for row in &rows[header + 1..] {
+                    let Some(cell) = signature_column.and_then(|i| row.cells.get(i)) else {
+                        continue;
+                    };
+                    let signature = cell_text(&cell.content);
+                    if signature.is_empty() {
+                        continue;
+                    }
+                    // The function's name is the text of the link to its page, or else
+                    // everything before its parameters
+                    let name = cell
+                        .content
+                        .iter()
+                        .find_map(|node| match node {
+                            WSN::Link { text, .. } => Some(text.trim().to_string()),
+                            _ => None,
+                        })
+                        .unwrap_or_else(|| {
+                            signature
+                                .split('(')
+                                .next()
+                                .unwrap_or_default()
+                                .trim()
+                                .to_string()
+                        });
+                    let returns = returns_column
+                        .and_then(|i| row.cells.get(i))
+                        .map(|cell| cell_text(&cell.content))
+                        .unwrap_or_default();
+                    entries.push(ApiEntry {
+                        name,
+                        signature,
+                        returns,
+                        page: title.to_string(),
+                        section: section.clone(),
+                    });
+                }
+            }
+            node => {
+                for children in wikitext::children(node) {
+                    extract_from_nodes(title, children, columns, section, entries);
+                }
+            }
+        }
+    }
+}
+
+/// The URL of the section documenting `entry`
+pub fn entry_url(site: &Site, entry: &ApiEntry) -> String {
+    let url = crate::page_url(&site.config, &entry.page);
+    match &entry.section {
+        Some(section) => {
+            let anchor = crate::toc::heading_id(
+                section,
+                site.config.heading_ids,
+                &site.config.anchor_prefix,
+                &[],
+            );
+            format!("{url}#{anchor}")
+        }
+        None => url,
+    }
+}
+
+/// The content of the API index page: the functions ordered by name, with a search box
+/// that filters them if scripts are enabled
+pub fn render(site: &Site, entries: &[ApiEntry]) -> paxhtml::Element {
+    use paxhtml::html;
+
+    let mut entries = entries.iter().collect::<Vec<_>>();
+    entries.sort_by(|a, b| {
+        a.name
+            .to_lowercase()
+            .cmp(&b.name.to_lowercase())
+            .then_with(|| a.page.cmp(&b.page))
+            .then_with(|| a.signature.cmp(&b.signature))
+    });
+
+    let search = site.config.scripts.then(|| {
+        html! {
+            <>
+                <input id="api-index-search" type="search" placeholder="Search functions..." class="w-full mb-4 px-3 py-2 border border-gray-300 rounded" />
+                <script>
+                    {paxhtml::Element::Raw { html: SEARCH_SCRIPT.to_string() }}
+                </script>
+            </>
+        }
+    });
+
+    html! {
+        <>
+            #{search}
+            <table id="api-index" class="min-w-full divide-y divide-gray-200 border border-gray-300">
+                <thead class="bg-gray-800 text-white">
+                    <tr>
+                        <th class="px-4 py-2 text-left">"Function"</th>
+                        <th class="px-4 py-2 text-left">"Returns"</th>
+                        <th class="px-4 py-2 text-left">"Page"</th>
+                    </tr>
+                </thead>
+                <tbody class="divide-y divide-gray-200">
+                    #{entries.into_iter().map(|entry| html! {
+                        <tr data-search={format!("{} {}", entry.signature, entry.page).to_lowercase()}>
+                            <td class="px-4 py-2 font-mono">
+                                <a class={site.config.link_class.clone()} href={entry_url(site, entry)}>
+                                    {entry.signature.clone()}
+                                </a>
+                            </td>
+                            <td class="px-4 py-2 font-mono">{entry.returns.clone()}</td>
+                            <td class="px-4 py-2">{entry.page.clone()}</td>
+                        </tr>
+                    })}
+                </tbody>
+            </table>
+        </>
+    }
+}
+
+/// Hides the rows that don't contain every word typed into the search box
+const SEARCH_SCRIPT: &str = r##"
+document.getElementById("api-index-search").addEventListener("input", function (event) {
+    var words = event.target.value.toLowerCase().split(/\s+/).filter(Boolean);
+    document.querySelectorAll("#api-index tbody tr").forEach(function (row) {
+        var text = row.dataset.search;
+        row.hidden = !words.every(function (word) { return text.includes(word); });
+    });
+});
+"##;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_function_table_entries_are_extracted() {
+        let source = r#"==Functions==
+{| class="wikitable"
+!Returns
+!Prototype
+|-
+|object
+|[[Lua/Server/SQLQuery/Functions/Execute|Execute]]()
+|}
+==Examples==
+{| class="wikitable"
+!Name
+!Value
+|-
+|Example
+|1
+|}"#;
+        let pwt_configuration = wikitext_simplified::wikitext_util::wikipedia_pwt_configuration();
+        let nodes =
+            wikitext_simplified::parse_and_simplify_wikitext(source, &pwt_configuration).unwrap();
+        let entries = extract("Lua/Server/SQLQuery", &nodes, &ApiIndexColumns::default());
+
+        assert_eq!(
+            entries,
+            [ApiEntry {
+                name: "Execute".to_string(),
+                signature: "Execute()".to_string(),
+                returns: "object".to_string(),
+                page: "Lua/Server/SQLQuery".to_string(),
+                section: Some("Functions".to_string()),
+            }]
+        );
+
+        let site = Site::new(
+            crate::config::Config::default(),
+            ["Lua/Server/SQLQuery".to_string()].into(),
+            Default::default(),
+        );
+        assert_eq!(
+            entry_url(&site, &entries[0]),
+            "/wiki/Lua/Server/SQLQuery.html#functions"
+        );
+        let html = crate::test_util::render_to_string(render(&site, &entries));
+        assert!(
+            html.contains(r#"href="/wiki/Lua/Server/SQLQuery.html#functions""#),
+            "{html}"
+        );
+    }
+}
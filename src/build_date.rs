@@ -0,0 +1,180 @@
+//! The date of the build, used by the date magic words (e.g. `{{CURRENTYEAR}}`)
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const MONTH_NAMES: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+const DAY_NAMES: [&str; 7] = [
+    "Sunday",
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+];
+
+/// A calendar date (UTC), which can be given in the configuration as `YYYY-MM-DD`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(try_from = "String")]
+pub struct BuildDate {
+    pub year: i64,
+    /// 1-12
+    pub month: u32,
+    /// 1-31
+    pub day: u32,
+}
+impl BuildDate {
+    /// The current date, or the date given by `SOURCE_DATE_EPOCH` if it's set, so that
+    /// builds can be reproduced
+    pub fn now() -> Self {
+        let seconds = std::env::var("SOURCE_DATE_EPOCH")
+            .ok()
+            .and_then(|s| s.trim().parse::<i64>().ok())
+            .unwrap_or_else(|| {
+                SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |d| d.as_secs() as i64)
+            });
+        Self::from_unix_seconds(seconds)
+    }
+
+    pub fn from_unix_seconds(seconds: i64) -> Self {
+        // Howard Hinnant's `civil_from_days`
+        let days = seconds.div_euclid(86_400) + 719_468;
+        let era = days.div_euclid(146_097);
+        let day_of_era = days.rem_euclid(146_097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let shifted_month = (5 * day_of_year + 2) / 153;
+        let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+        let month = (if shifted_month < 10 {
+            shifted_month + 3
+        } else {
+            shifted_month - 9
+        }) as u32;
+        let year = year_of_era + era * 400 + i64::from(month <= 2);
+        Self { year, month, day }
+    }
+
+    /// The number of days since 1970-01-01
+    fn days_since_epoch(&self) -> i64 {
+        // Howard Hinnant's `days_from_civil`
+        let year = self.year - i64::from(self.month <= 2);
+        let era = year.div_euclid(400);
+        let year_of_era = year.rem_euclid(400);
+        let shifted_month = i64::from((self.month + 9) % 12);
+        let day_of_year = (153 * shifted_month + 2) / 5 + i64::from(self.day) - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        era * 146_097 + day_of_era - 719_468
+    }
+
+    /// The day of the week, where 0 is Sunday
+    pub fn day_of_week(&self) -> usize {
+        // 1970-01-01 was a Thursday
+        (self.days_since_epoch() + 4).rem_euclid(7) as usize
+    }
+
+    /// The value of the date magic word `name` (e.g. `CURRENTYEAR`), if it is one
+    pub fn magic_word(&self, name: &str) -> Option<String> {
+        let word = name
+            .strip_prefix("CURRENT")
+            .or_else(|| name.strip_prefix("LOCAL"))?;
+        let month_name = MONTH_NAMES[self.month as usize - 1];
+        let day_name = DAY_NAMES[self.day_of_week()];
+        Some(match word {
+            "YEAR" => self.year.to_string(),
+            "MONTH" | "MONTH2" => format!("{:02}", self.month),
+            "MONTH1" => self.month.to_string(),
+            "MONTHNAME" => month_name.to_string(),
+            "MONTHABBREV" => month_name[..3].to_string(),
+            "DAY" => self.day.to_string(),
+            "DAY2" => format!("{:02}", self.day),
+            "DOW" => self.day_of_week().to_string(),
+            "DAYNAME" => day_name.to_string(),
+            _ => return None,
+        })
+    }
+}
+impl std::fmt::Display for BuildDate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+impl TryFrom<String> for BuildDate {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> anyhow::Result<Self> {
+        let invalid = || anyhow::anyhow!("Invalid date {value:?}, expected YYYY-MM-DD");
+
+        let mut parts = value.splitn(3, '-');
+        let mut next = || parts.next().and_then(|part| part.parse::<i64>().ok());
+        let (Some(year), Some(month), Some(day)) = (next(), next(), next()) else {
+            return Err(invalid());
+        };
+        if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+            return Err(invalid());
+        }
+
+        Ok(Self {
+            year,
+            month: month as u32,
+            day: day as u32,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_unix_seconds() {
+        assert_eq!(
+            BuildDate::from_unix_seconds(0),
+            BuildDate::try_from("1970-01-01".to_string()).unwrap()
+        );
+        // 2014-03-09T12:00:00Z
+        assert_eq!(
+            BuildDate::from_unix_seconds(1_394_366_400),
+            BuildDate::try_from("2014-03-09".to_string()).unwrap()
+        );
+        assert!(BuildDate::try_from("2014-13-01".to_string()).is_err());
+        assert_eq!(
+            BuildDate::from_unix_seconds(1_394_366_400).to_string(),
+            "2014-03-09"
+        );
+    }
+
+    #[test]
+    fn test_magic_words() {
+        let date = BuildDate::try_from("2014-03-09".to_string()).unwrap();
+        let word = |name: &str| date.magic_word(name).unwrap();
+
+        assert_eq!(word("CURRENTYEAR"), "2014");
+        assert_eq!(word("CURRENTMONTH"), "03");
+        assert_eq!(word("CURRENTMONTH1"), "3");
+        assert_eq!(word("CURRENTMONTHNAME"), "March");
+        assert_eq!(word("CURRENTMONTHABBREV"), "Mar");
+        assert_eq!(word("CURRENTDAY"), "9");
+        assert_eq!(word("CURRENTDAY2"), "09");
+        assert_eq!(word("CURRENTDOW"), "0");
+        assert_eq!(word("CURRENTDAYNAME"), "Sunday");
+        assert_eq!(word("LOCALYEAR"), "2014");
+        assert_eq!(date.magic_word("CURRENTWEEKEND"), None);
+        assert_eq!(date.magic_word("Lua/Server"), None);
+    }
+}
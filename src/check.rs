@@ -0,0 +1,259 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use wikitext_simplified::WikitextSimplifiedNode as WSN;
+
+use crate::{
+    color::Color,
+    config::Config,
+    site::{Site, normalize_title},
+    warnings::Warnings,
+    wikitext,
+};
+
+/// The minimum contrast ratio between text and its background (WCAG AA, normal text)
+const MIN_CONTRAST_RATIO: f64 = 4.5;
+
+/// Run the `--check` passes over the generated site, printing every problem found.
+///
+/// Poor color contrast is reported as a warning rather than a problem.
+pub fn run(output_dir: &Path, warnings: &Warnings) -> anyhow::Result<()> {
+    let mut problems = vec![];
+    for path in html_files(output_dir)? {
+        let html = fs::read_to_string(&path)?;
+        let page = path.strip_prefix(output_dir).unwrap_or(&path).display();
+        for error in validate_html(&html) {
+            problems.push(format!("{page}: {error}"));
+        }
+        for issue in audit_contrast(&html) {
+            warnings.warn(format!("{page}: {issue}"));
+        }
+    }
+
+    for problem in &problems {
+        eprintln!("{problem}");
+    }
+    if !problems.is_empty() {
+        anyhow::bail!("Check found {} problem(s)", problems.len());
+    }
+
+    Ok(())
+}
+
+/// The internal links whose casing differs from the title of the page or redirect they
+/// lead to, with the canonical form to use instead. Links still resolve regardless of
+/// casing; this only reports them. As in MediaWiki, a lowercase first letter is allowed.
+pub fn link_casing(site: &Site) -> Vec<String> {
+    let canonical_titles = site
+        .page_titles
+        .iter()
+        .chain(site.redirects.keys())
+        .map(|title| (normalize_title(title), title.as_str()))
+        .collect::<HashMap<_, _>>();
+
+    let links = site.links.borrow();
+    let links = links.iter().collect::<BTreeSet<_>>();
+    let mut issues = vec![];
+    for (source, target) in links {
+        let title = target.split('#').next().unwrap_or_default();
+        let Some(&canonical) = canonical_titles.get(&normalize_title(title)) else {
+            continue;
+        };
+        let written = title.trim().replace('_', " ");
+        let mut chars = written.chars();
+        let capitalized = chars
+            .next()
+            .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+            .unwrap_or_default();
+        if capitalized != canonical {
+            issues.push(format!(
+                "{source}: link to [[{title}]] should be [[{canonical}]]"
+            ));
+        }
+    }
+    issues
+}
+
+/// The lines of a page's text (`nodes`) containing one of the configured TODO markers,
+/// each as the marker and the line it's on. Code is skipped unless configured otherwise.
+pub fn todo_markers(config: &Config, nodes: &[WSN]) -> Vec<(String, String)> {
+    fn without_code(nodes: &[WSN]) -> Vec<WSN> {
+        nodes
+            .iter()
+            .filter(|node| !wikitext::is_code(node))
+            .cloned()
+            .map(|mut node| {
+                for children in wikitext::children_mut(&mut node) {
+                    *children = without_code(children);
+                }
+                node
+            })
+            .collect()
+    }
+
+    let text = if config.todo_markers_in_code {
+        wikitext::plain_text(nodes)
+    } else {
+        wikitext::plain_text(&without_code(nodes))
+    };
+    text.lines()
+        .filter_map(|line| {
+            let marker = config
+                .todo_markers
+                .iter()
+                .find(|marker| !marker.is_empty() && line.contains(marker.as_str()))?;
+            Some((marker.clone(), line.trim().to_string()))
+        })
+        .collect()
+}
+
+/// Parse `html` as an HTML5 document, returning the distinct parse errors encountered
+/// (unbalanced tags, invalid nesting, and so on)
+pub fn validate_html(html: &str) -> BTreeSet<String> {
+    scraper::Html::parse_document(html)
+        .errors
+        .into_iter()
+        .map(|error| error.to_string())
+        .collect()
+}
+
+/// Find the elements in `html` whose explicit inline text color doesn't contrast enough
+/// with their background: the nearest explicit inline background color, or white.
+///
+/// Colors set by classes or stylesheets aren't considered.
+pub fn audit_contrast(html: &str) -> Vec<String> {
+    let document = scraper::Html::parse_document(html);
+    let mut issues = vec![];
+    for element in document.root_element().descendants() {
+        let Some(element) = scraper::ElementRef::wrap(element) else {
+            continue;
+        };
+        let Some(foreground) = style_color(&element, &["color"]) else {
+            continue;
+        };
+        let background = std::iter::once(element)
+            .chain(element.ancestors().filter_map(scraper::ElementRef::wrap))
+            .find_map(|element| style_color(&element, &["background-color", "background"]))
+            .unwrap_or(Color::WHITE);
+
+        let ratio = foreground.contrast_ratio(&background);
+        if ratio < MIN_CONTRAST_RATIO {
+            issues.push(format!(
+                "low color contrast ({ratio:.2}:1, needs {MIN_CONTRAST_RATIO}:1) in <{} style=\"{}\">",
+                element.value().name(),
+                element.value().attr("style").unwrap_or_default()
+            ));
+        }
+    }
+    issues
+}
+
+/// The color given by the first of `properties` in the element's inline style
+fn style_color(element: &scraper::ElementRef, properties: &[&str]) -> Option<Color> {
+    let style = element.value().attr("style")?;
+    style
+        .split(';')
+        .filter_map(|declaration| declaration.split_once(':'))
+        .filter(|(property, _)| properties.contains(&property.trim().to_lowercase().as_str()))
+        .find_map(|(_, value)| {
+            let value = value.trim().trim_end_matches("!important").trim();
+            // `background` can be a shorthand with the color among other values
+            Color::parse(value).or_else(|| value.split_whitespace().find_map(Color::parse))
+        })
+}
+
+fn html_files(dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut files = vec![];
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            files.extend(html_files(&path)?);
+        } else if path.extension().is_some_and(|e| e == "html") {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid_html_has_no_errors() {
+        let errors = validate_html(
+            "<!DOCTYPE html><html><head><title>Test</title></head><body><p>Hello</p></body></html>",
+        );
+        assert!(errors.is_empty(), "{errors:?}");
+    }
+
+    #[test]
+    fn test_misnested_table_content_is_flagged() {
+        let errors = validate_html(
+            "<!DOCTYPE html><html><head><title>Test</title></head><body>\
+             <table><tr><td>Cell</td></tr><div>Stray</div></table>\
+             </body></html>",
+        );
+        assert!(!errors.is_empty());
+    }
+
+    #[test]
+    fn test_miscased_link_is_flagged_with_canonical_title() {
+        let site = Site::new(
+            crate::config::Config::default(),
+            ["Lua/Server/Vehicle".to_string()].into(),
+            Default::default(),
+        );
+        site.record_link("Vehicles", "lua/server/vehicle");
+        site.record_link("Vehicles", "Lua/Server/Vehicle#Functions");
+        site.record_link("Vehicles", "lua/Server/Vehicle");
+        site.record_link("Vehicles", "Missing page");
+
+        assert_eq!(
+            link_casing(&site),
+            ["Vehicles: link to [[lua/server/vehicle]] should be [[Lua/Server/Vehicle]]"]
+        );
+    }
+
+    #[test]
+    fn test_todo_markers_are_found_outside_of_code() {
+        let pwt_configuration = wikitext_simplified::wikitext_util::wikipedia_pwt_configuration();
+        let nodes = wikitext_simplified::parse_and_simplify_wikitext(
+            "Spawning is covered below.\n\nTODO: document despawning.\n\n<code>-- FIXME: example</code>",
+            &pwt_configuration,
+        )
+        .unwrap();
+
+        let config = Config::default();
+        assert_eq!(
+            todo_markers(&config, &nodes),
+            [("TODO".to_string(), "TODO: document despawning.".to_string())]
+        );
+
+        let config = Config {
+            todo_markers_in_code: true,
+            ..Config::default()
+        };
+        assert_eq!(todo_markers(&config, &nodes).len(), 2);
+    }
+
+    #[test]
+    fn test_low_contrast_inline_style_is_flagged() {
+        let issues = audit_contrast(
+            "<!DOCTYPE html><html><head><title>Test</title></head><body>\
+             <div style=\"background-color: #777\"><span style=\"color: #888\">Faint</span></div>\
+             <span style=\"color: black; background: white\">Fine</span>\
+             <span class=\"text-gray-300\">Not inspected</span>\
+             </body></html>",
+        );
+        assert_eq!(issues.len(), 1, "{issues:?}");
+        assert!(
+            issues[0].contains(r#"<span style="color: #888">"#),
+            "{issues:?}"
+        );
+    }
+}
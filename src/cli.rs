@@ -0,0 +1,197 @@
+use std::path::{Path, PathBuf};
+
+use crate::link_report::ReportFormat;
+
+/// The directory the site is built in, unless other targets are given
+pub const OUTPUT_DIRECTORY: &str = "output";
+
+/// How the JSON written alongside each page is formatted
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum JsonFormat {
+    /// Indented, for reading while debugging
+    #[default]
+    Pretty,
+    /// Without any whitespace, for size
+    Compact,
+}
+impl JsonFormat {
+    pub fn to_string(self, value: &impl serde::Serialize) -> serde_json::Result<String> {
+        match self {
+            Self::Pretty => serde_json::to_string_pretty(value),
+            Self::Compact => serde_json::to_string(value),
+        }
+    }
+}
+impl std::str::FromStr for JsonFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "pretty" => Ok(Self::Pretty),
+            "compact" => Ok(Self::Compact),
+            _ => anyhow::bail!("Unknown JSON format: {s} (expected `pretty` or `compact`)"),
+        }
+    }
+}
+
+/// What an output target builds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TargetKind {
+    /// The full site, with the wiki in its `wiki` directory
+    Site,
+    /// The simplified wikitext of every page as JSON, like `--output-json-only`
+    Json,
+}
+
+/// Something to build and the directory it's written to, given as `kind:directory`
+/// (e.g. `json:json-out`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Target {
+    pub kind: TargetKind,
+    pub dir: PathBuf,
+}
+impl std::str::FromStr for Target {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let Some((kind, dir)) = s.split_once(':').filter(|(_, dir)| !dir.is_empty()) else {
+            anyhow::bail!(
+                "Invalid target: {s} (expected `site:<directory>` or `json:<directory>`)"
+            );
+        };
+        let kind = match kind {
+            "site" => TargetKind::Site,
+            "json" => TargetKind::Json,
+            _ => anyhow::bail!("Unknown target kind: {kind} (expected `site` or `json`)"),
+        };
+        Ok(Self {
+            kind,
+            dir: dir.into(),
+        })
+    }
+}
+
+/// Options passed on the command line
+#[derive(Debug, Default)]
+pub struct Args {
+    /// Run additional checks over the generated site and report any problems found
+    pub check: bool,
+    /// Fail the build if any warnings were emitted
+    pub deny_warnings: bool,
+    /// Fail the build on problems that would otherwise only be warnings, such as
+    /// redirects to missing pages
+    pub strict: bool,
+    /// Fail the build if any page links to a page that doesn't exist, without failing on
+    /// other warnings
+    pub deny_broken_links: bool,
+    /// How the link report is presented
+    pub report_format: ReportFormat,
+    /// Only build the page with this title
+    pub page: Option<String>,
+    /// Time each page and template, and print the slowest at the end of the build
+    pub profile: bool,
+    /// Only write each page's simplified wikitext as JSON, without rendering any HTML
+    pub output_json_only: bool,
+    /// How each page's simplified wikitext is formatted as JSON
+    pub json_format: JsonFormat,
+    /// Don't print the summary at the end of the build
+    pub quiet: bool,
+    /// The title of the home page, overriding the configured one
+    pub main_page: Option<String>,
+    /// Leave every client-side script out of the pages
+    pub no_js: bool,
+    /// The directory the Tailwind binary is kept in between builds
+    pub tailwind_cache: Option<PathBuf>,
+    /// The outputs to build from the same parse of the wiki. Without any, the site is
+    /// built in `output`.
+    pub targets: Vec<Target>,
+    /// Print the files that the build added, changed or removed compared to the
+    /// previous build in the same directory
+    pub diff: bool,
+}
+impl Args {
+    pub fn parse(args: impl IntoIterator<Item = String>) -> anyhow::Result<Self> {
+        let mut result = Self::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--check" => result.check = true,
+                "--deny-warnings" => result.deny_warnings = true,
+                "--strict" => result.strict = true,
+                "--deny-broken-links" => result.deny_broken_links = true,
+                "--profile" => result.profile = true,
+                "--output-json-only" => result.output_json_only = true,
+                "--quiet" => result.quiet = true,
+                "--no-js" => result.no_js = true,
+                "--diff" => result.diff = true,
+                "--report-format" => {
+                    let Some(format) = args.next() else {
+                        anyhow::bail!("--report-format requires a value");
+                    };
+                    result.report_format = format.parse()?;
+                }
+                "--json-format" => {
+                    let Some(format) = args.next() else {
+                        anyhow::bail!("--json-format requires a value");
+                    };
+                    result.json_format = format.parse()?;
+                }
+                "--main-page" => {
+                    let Some(title) = args.next() else {
+                        anyhow::bail!("--main-page requires a page title");
+                    };
+                    result.main_page = Some(title);
+                }
+                "--tailwind-cache" => {
+                    let Some(dir) = args.next() else {
+                        anyhow::bail!("--tailwind-cache requires a directory");
+                    };
+                    result.tailwind_cache = Some(dir.into());
+                }
+                "--target" => {
+                    let Some(target) = args.next() else {
+                        anyhow::bail!("--target requires a value");
+                    };
+                    result.targets.push(target.parse()?);
+                }
+                "--page" => {
+                    let Some(title) = args.next() else {
+                        anyhow::bail!("--page requires a page title");
+                    };
+                    result.page = Some(title);
+                }
+                _ => anyhow::bail!("Unknown argument: {arg}"),
+            }
+        }
+        if result.output_json_only && !result.targets.is_empty() {
+            anyhow::bail!("--output-json-only can't be combined with --target");
+        }
+        let sites = result
+            .targets
+            .iter()
+            .filter(|target| target.kind == TargetKind::Site)
+            .count();
+        if sites > 1 {
+            anyhow::bail!("Only one site target can be built at a time");
+        }
+        Ok(result)
+    }
+
+    /// The outputs to build, relative to the working directory
+    pub fn targets(&self) -> Vec<Target> {
+        if !self.targets.is_empty() {
+            return self.targets.clone();
+        }
+        vec![if self.output_json_only {
+            Target {
+                kind: TargetKind::Json,
+                dir: Path::new(OUTPUT_DIRECTORY).join(crate::WIKI_DIRECTORY),
+            }
+        } else {
+            Target {
+                kind: TargetKind::Site,
+                dir: OUTPUT_DIRECTORY.into(),
+            }
+        }]
+    }
+}
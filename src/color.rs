@@ -0,0 +1,115 @@
+//! Parsing CSS colors and measuring their contrast
+
+/// An opaque sRGB color
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+impl Color {
+    pub const WHITE: Self = Self::rgb(255, 255, 255);
+
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b }
+    }
+
+    /// Parse a CSS color given as `#rgb`, `#rrggbb`, `rgb(r, g, b)` or a common named
+    /// color. Any other color is not understood.
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim().to_lowercase();
+        if let Some(hex) = value.strip_prefix('#') {
+            let digit = |i: usize| u8::from_str_radix(hex.get(i..i + 1)?, 16).ok();
+            let pair = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+            return match hex.len() {
+                3 => Some(Self::rgb(digit(0)? * 17, digit(1)? * 17, digit(2)? * 17)),
+                6 => Some(Self::rgb(pair(0)?, pair(2)?, pair(4)?)),
+                _ => None,
+            };
+        }
+        if let Some(arguments) = value
+            .strip_prefix("rgb(")
+            .or_else(|| value.strip_prefix("rgba("))
+            .and_then(|rest| rest.strip_suffix(')'))
+        {
+            let mut channels = arguments
+                .split([',', ' ', '/'])
+                .filter(|s| !s.is_empty())
+                .map(|s| s.parse::<u8>().ok());
+            return Some(Self::rgb(
+                channels.next()??,
+                channels.next()??,
+                channels.next()??,
+            ));
+        }
+
+        Some(match value.as_str() {
+            "black" => Self::rgb(0, 0, 0),
+            "white" => Self::WHITE,
+            "gray" | "grey" => Self::rgb(128, 128, 128),
+            "silver" => Self::rgb(192, 192, 192),
+            "lightgray" | "lightgrey" => Self::rgb(211, 211, 211),
+            "darkgray" | "darkgrey" => Self::rgb(169, 169, 169),
+            "red" => Self::rgb(255, 0, 0),
+            "darkred" => Self::rgb(139, 0, 0),
+            "maroon" => Self::rgb(128, 0, 0),
+            "orange" => Self::rgb(255, 165, 0),
+            "yellow" => Self::rgb(255, 255, 0),
+            "gold" => Self::rgb(255, 215, 0),
+            "lime" => Self::rgb(0, 255, 0),
+            "green" => Self::rgb(0, 128, 0),
+            "darkgreen" => Self::rgb(0, 100, 0),
+            "olive" => Self::rgb(128, 128, 0),
+            "teal" => Self::rgb(0, 128, 128),
+            "cyan" | "aqua" => Self::rgb(0, 255, 255),
+            "blue" => Self::rgb(0, 0, 255),
+            "darkblue" => Self::rgb(0, 0, 139),
+            "navy" => Self::rgb(0, 0, 128),
+            "purple" => Self::rgb(128, 0, 128),
+            "magenta" | "fuchsia" => Self::rgb(255, 0, 255),
+            "pink" => Self::rgb(255, 192, 203),
+            "brown" => Self::rgb(165, 42, 42),
+            _ => return None,
+        })
+    }
+
+    /// The WCAG relative luminance, from 0 (black) to 1 (white)
+    pub fn relative_luminance(&self) -> f64 {
+        let channel = |c: u8| {
+            let c = f64::from(c) / 255.0;
+            if c <= 0.039_28 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        0.2126 * channel(self.r) + 0.7152 * channel(self.g) + 0.0722 * channel(self.b)
+    }
+
+    /// The WCAG contrast ratio between two colors, from 1 to 21
+    pub fn contrast_ratio(&self, other: &Self) -> f64 {
+        let (a, b) = (self.relative_luminance(), other.relative_luminance());
+        (a.max(b) + 0.05) / (a.min(b) + 0.05)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_contrast() {
+        assert_eq!(Color::parse("#fff"), Some(Color::WHITE));
+        assert_eq!(Color::parse("#1E90FF"), Some(Color::rgb(30, 144, 255)));
+        assert_eq!(
+            Color::parse("rgb(10, 20, 30)"),
+            Some(Color::rgb(10, 20, 30))
+        );
+        assert_eq!(Color::parse("Navy"), Some(Color::rgb(0, 0, 128)));
+        assert_eq!(Color::parse("var(--accent)"), None);
+
+        let black = Color::parse("black").unwrap();
+        assert!((black.contrast_ratio(&Color::WHITE) - 21.0).abs() < 1e-9);
+        assert!((Color::WHITE.contrast_ratio(&Color::WHITE) - 1.0).abs() < 1e-9);
+    }
+}
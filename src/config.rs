@@ -0,0 +1,545 @@
+use std::{collections::BTreeMap, path::Path};
+
+use serde::Deserialize;
+
+use crate::build_date::BuildDate;
+
+/// The optional configuration file, relative to the working directory
+pub const CONFIG_FILE: &str = "config.json";
+
+/// The classes of a heading whose level isn't in `heading_classes`
+pub const DEFAULT_HEADING_CLASS: &str = "font-semibold mt-4 mb-2";
+
+/// What to do with a code block whose language isn't recognized
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UnknownCodeLanguage {
+    /// Render the code without highlighting
+    #[default]
+    Plain,
+    /// Render the code without highlighting and emit a warning naming the page and
+    /// language
+    Error,
+    /// Guess the language from the code, rendering it without highlighting if that fails
+    Autodetect,
+}
+
+/// How `<math>` formulae are rendered
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MathBackend {
+    /// Render the formulae with KaTeX, which pages with formulae load from a CDN
+    Katex,
+    /// Convert the formulae to MathML at build time, without any scripts. Formulae that
+    /// use TeX the converter doesn't support are shown as with `Plain`.
+    #[default]
+    MathMl,
+    /// Show the TeX source of the formulae as code, without any scripts
+    Plain,
+}
+
+/// Whether and how the wikitext source of each page is shown
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SourceView {
+    /// The source isn't shown
+    #[default]
+    None,
+    /// In a collapsed section at the bottom of the page
+    Details,
+    /// On a page of its own next to the page (`Page.source.html`), linked from the bottom
+    /// of the page
+    Page,
+}
+
+/// How the `id`s of headings are derived from their text
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HeadingIds {
+    /// As on the original wiki: the words joined with underscores (`Example_code`)
+    MediaWiki,
+    /// Lowercase, with words joined with hyphens and punctuation removed
+    /// (`example-code`)
+    #[default]
+    Slug,
+}
+
+/// The header cells that identify the columns of a function table, for the API index
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ApiIndexColumns {
+    /// The column holding each function's signature. Tables with this column are
+    /// function tables.
+    pub signature: String,
+    /// The column holding what each function returns
+    pub returns: String,
+}
+impl Default for ApiIndexColumns {
+    fn default() -> Self {
+        Self {
+            signature: "Prototype".to_string(),
+            returns: "Returns".to_string(),
+        }
+    }
+}
+
+/// Settings for the generator.
+///
+/// Every field has a default, so the configuration file only needs to contain
+/// the settings that differ from them.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// How many levels of the sidebar tree are expanded by default. Deeper branches
+    /// are collapsed unless they contain the current page.
+    pub sidebar_max_depth: usize,
+    /// The `syntaxhighlight` language used for code blocks without an explicit `lang`
+    pub default_code_language: String,
+    /// Overrides of `default_code_language` for pages under a top-level wiki directory,
+    /// keyed by directory name (e.g. `Lua`)
+    pub code_languages: BTreeMap<String, String>,
+    /// What to do with code blocks in languages that can't be highlighted
+    pub unknown_code_language: UnknownCodeLanguage,
+    /// How `<math>` formulae are rendered
+    pub math_backend: MathBackend,
+    /// Whether and how the wikitext source of each page is shown
+    pub source_view: SourceView,
+    /// Whether pages may include client-side scripts. Without them, the features that
+    /// need scripts are left out or fall back to static HTML.
+    pub scripts: bool,
+    /// The title of the home page, which the site's root redirects to
+    pub main_page: String,
+    /// Whether pages are written as `Page/index.html` and linked as `/wiki/Page/`,
+    /// rather than as `Page.html`
+    pub clean_urls: bool,
+    /// Whether clean URLs end with a slash (`/wiki/Page/`) or not (`/wiki/Page`)
+    pub trailing_slash: bool,
+    /// Whether the links between pages (and to the site's assets) are relative to the
+    /// page they're on, so that the site works without a web server
+    pub relative_links: bool,
+    /// The scheme and host the site is served from (e.g. `https://jc-mp.com`), used
+    /// for canonical URLs
+    pub base_url: Option<String>,
+    /// Whether `:shortcode:`s in page text (outside of code) are replaced with emoji
+    pub emoji_shortcodes: bool,
+    /// A JSON file of terms and their definitions. The first use of each term on a
+    /// page (outside of code, links and headings) gets its definition as a tooltip.
+    pub glossary_file: Option<String>,
+    /// Pages that have been renamed, mapped from their old title to their new one. Links
+    /// and redirects to an old title lead to the new one.
+    pub moves: BTreeMap<String, String>,
+    /// Whether links to the old title of a moved page are warned about
+    pub warn_moved_links: bool,
+    /// A file of HTML added to the end of every content page, such as a comments widget
+    pub after_content_file: Option<String>,
+    /// The URL of an analytics script to load on every content page, if any
+    pub analytics_script: Option<String>,
+    /// The attributes given to the analytics script's `<script>` (e.g. `data-domain`)
+    pub analytics_attributes: BTreeMap<String, String>,
+    /// The classes applied to links. Broken and external links use variants of these.
+    pub link_class: String,
+    /// The `rel` attribute applied to external links
+    pub external_link_rel: String,
+    /// If set, `nofollow` is added to the `rel` of external links to hosts other than
+    /// these domains and their subdomains
+    pub follow_domains: Option<Vec<String>>,
+    /// Query parameters removed from external links, such as tracking parameters. A
+    /// trailing `*` matches any parameter starting with the rest (e.g. `utm_*`).
+    pub strip_query_parameters: Vec<String>,
+    /// The number of footnotes at which a `<references responsive />` list is split
+    /// into columns
+    pub references_columns_threshold: usize,
+    /// If set, tables with more rows than this only show that many until a button is
+    /// clicked to show the rest. The rows are all in the HTML regardless.
+    pub collapse_table_rows: Option<usize>,
+    /// The number of levels added to every wiki heading. Rendered headings are also
+    /// clamped to `<h2>`-`<h6>`, as the page title is the only `<h1>`.
+    pub heading_level_offset: u8,
+    /// Whether headings are prefixed with their section number (1, 1.1, 1.2, 2)
+    pub number_headings: bool,
+    /// The classes of rendered headings, by HTML level (`2` for `<h2>`). Levels that
+    /// aren't listed get [`DEFAULT_HEADING_CLASS`].
+    pub heading_classes: BTreeMap<u8, String>,
+    /// Whether long words in body text and inline code are broken (and hyphenated) to
+    /// fit narrow screens, instead of overflowing the page
+    pub break_long_words: bool,
+    /// Whether pages with enough headings get a sticky "On this page" list of them
+    pub on_this_page: bool,
+    /// The number of headings a page needs for its headings to be listed
+    pub toc_min_headings: usize,
+    /// Prepended to the `id`s derived from a page's content, such as those of its
+    /// headings, so that they can't collide with the `id`s of the layout
+    pub anchor_prefix: String,
+    /// How the `id`s of headings are derived from their text
+    pub heading_ids: HeadingIds,
+    /// Whether headings get a "#" link to themselves, shown when they're hovered
+    pub heading_permalinks: bool,
+    /// The language of the wiki's pages, used for `<html lang>`
+    pub language: String,
+    /// The language codes that mark a subpage as a translation of its parent
+    /// (e.g. `Page/de` is the German translation of `Page`)
+    pub translation_languages: Vec<String>,
+    /// The date used by the date magic words (e.g. `{{CURRENTYEAR}}`), for reproducible
+    /// builds. Defaults to the current date.
+    pub build_date: Option<BuildDate>,
+    /// The variant of the site being built (e.g. `developer` or `player`), which pages
+    /// can branch on with `{{#ifeq:{{BUILDPROFILE}}|...}}`
+    pub build_profile: String,
+    /// The file extensions, without the dot, of the pages and templates in the wiki
+    /// directory. Other files are ignored.
+    pub source_extensions: Vec<String>,
+    /// The namespaces that titles can be prefixed with (e.g. `Template:Infobox`), mapped
+    /// to the directory of the wiki and output their pages are in
+    pub namespaces: BTreeMap<String, String>,
+    /// The directory, relative to the working directory, that `[[File:...]]` links
+    /// refer to. Its contents are copied into the output.
+    pub media_directory: String,
+    /// If set, images wider than this (and at least `thumbnail_min_bytes` large) get a
+    /// thumbnail of this width, which is shown in place of the full image
+    pub thumbnail_width: Option<u32>,
+    /// The width framed images (`thumb`) are shown at if they don't give a size
+    pub image_default_width: Option<u32>,
+    /// The widest images are shown at; larger sizes are clamped to it
+    pub image_max_width: Option<u32>,
+    /// Whether images without an alignment are centered on their own line
+    pub center_images: bool,
+    /// The file size below which images are used as-is rather than thumbnailed
+    pub thumbnail_min_bytes: u64,
+    /// Media files larger than this are warned about (and fail the build with
+    /// `--strict`), as they should probably be optimized
+    pub max_media_bytes: Option<u64>,
+    /// Pages whose rendered HTML is larger than this are warned about (and fail the
+    /// build with `--strict`), as they're likely to be slow to load
+    pub max_page_bytes: Option<u64>,
+    /// Whether each page carries a `<meta name="content-hash">` with a hash of its
+    /// rendered content, for caches to detect changes with
+    pub content_hash: bool,
+    /// Whether pages in categories carry a `<meta name="keywords">` listing them
+    pub keywords_meta: bool,
+    /// The headings of the sections whose definition lists are questions and answers,
+    /// given to search engines as `FAQPage` structured data
+    pub faq_sections: Vec<String>,
+    /// The color browsers tint their interface with (`<meta name="theme-color">`)
+    pub theme_color: Option<String>,
+    /// The theme color used in dark mode, if it differs from `theme_color`
+    pub theme_color_dark: Option<String>,
+    /// Whether pages carry the metadata for being added to a phone's home screen
+    pub mobile_web_app: bool,
+    /// How many pages are listed on the recent changes page
+    pub recent_changes_count: usize,
+    /// How many of the most recently modified pages are in `feed.xml`. No feed is
+    /// written if this is 0.
+    pub feed_count: usize,
+    /// The markers (e.g. `TODO`) that `--check` reports in page text
+    pub todo_markers: Vec<String>,
+    /// Whether `--check` also reports the markers in code
+    pub todo_markers_in_code: bool,
+    /// The layout of the function tables that the API index is gathered from
+    pub api_index_columns: ApiIndexColumns,
+    /// Pages with less text than this many characters are marked as stubs, as are pages
+    /// with a `{{Stub}}` marker
+    pub stub_threshold: Option<usize>,
+    /// Whether a `robots.txt` is written to the output root
+    pub robots_txt: bool,
+    /// The paths `robots.txt` allows crawlers to visit
+    pub robots_allow: Vec<String>,
+    /// The paths `robots.txt` asks crawlers not to visit
+    pub robots_disallow: Vec<String>,
+    /// Whether `llms.txt` and `llms-full.txt`, an index and digest of the text of every
+    /// page, are written to the output root
+    pub llms_txt: bool,
+    /// Whether every page is also written to one printable document, `print.html`
+    pub print_document: bool,
+    /// A command converting the print document to `wiki.pdf`, with `{input}` and
+    /// `{output}` replaced by their paths. Nothing is converted if it's empty.
+    pub pdf_command: Vec<String>,
+}
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            sidebar_max_depth: 2,
+            default_code_language: "lua".to_string(),
+            code_languages: BTreeMap::new(),
+            unknown_code_language: UnknownCodeLanguage::Plain,
+            math_backend: MathBackend::MathMl,
+            source_view: SourceView::None,
+            scripts: true,
+            main_page: "Main Page".to_string(),
+            clean_urls: false,
+            trailing_slash: true,
+            relative_links: false,
+            base_url: None,
+            emoji_shortcodes: false,
+            glossary_file: None,
+            moves: BTreeMap::new(),
+            warn_moved_links: true,
+            after_content_file: None,
+            analytics_script: None,
+            analytics_attributes: BTreeMap::new(),
+            link_class: "text-blue-600 hover:text-blue-800 hover:underline".to_string(),
+            external_link_rel: "noopener noreferrer".to_string(),
+            follow_domains: None,
+            strip_query_parameters: vec![],
+            references_columns_threshold: 10,
+            collapse_table_rows: None,
+            heading_level_offset: 0,
+            number_headings: false,
+            break_long_words: false,
+            heading_classes: [
+                (2, "text-2xl font-bold mt-8 mb-4"),
+                (3, "text-xl font-bold mt-6 mb-3"),
+                (4, "text-lg font-semibold mt-4 mb-2"),
+                (5, "font-semibold mt-4 mb-2"),
+                (6, "text-sm font-semibold uppercase tracking-wide mt-4 mb-2"),
+            ]
+            .into_iter()
+            .map(|(level, class)| (level, class.to_string()))
+            .collect(),
+            on_this_page: true,
+            toc_min_headings: 4,
+            anchor_prefix: String::new(),
+            heading_ids: HeadingIds::Slug,
+            heading_permalinks: true,
+            language: "en".to_string(),
+            translation_languages: vec![],
+            build_date: None,
+            build_profile: String::new(),
+            source_extensions: vec!["wikitext".to_string()],
+            namespaces: ["Category", "Help", "Template"]
+                .into_iter()
+                .map(|namespace| (namespace.to_string(), namespace.to_string()))
+                .collect(),
+            media_directory: "media".to_string(),
+            thumbnail_width: None,
+            image_default_width: Some(300),
+            image_max_width: Some(1000),
+            center_images: false,
+            thumbnail_min_bytes: 100_000,
+            max_media_bytes: Some(2_000_000),
+            max_page_bytes: None,
+            content_hash: false,
+            keywords_meta: false,
+            faq_sections: vec![],
+            theme_color: Some("#111827".to_string()),
+            theme_color_dark: None,
+            mobile_web_app: true,
+            recent_changes_count: 25,
+            feed_count: 20,
+            todo_markers: vec!["TODO".to_string(), "FIXME".to_string()],
+            todo_markers_in_code: false,
+            api_index_columns: ApiIndexColumns::default(),
+            stub_threshold: None,
+            robots_txt: true,
+            robots_allow: vec![],
+            robots_disallow: vec!["/wiki/Special/".to_string()],
+            llms_txt: false,
+            print_document: false,
+            pdf_command: vec![],
+        }
+    }
+}
+impl Config {
+    /// Load the configuration from `path`, or use the defaults if it doesn't exist
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(path)?;
+        serde_json::from_str(&content)
+            .map_err(|e| anyhow::anyhow!("Failed to parse config {}: {e}", path.display()))
+    }
+
+    /// The language to highlight a code block in when the page with the given title
+    /// doesn't specify one
+    pub fn default_code_language_for(&self, title: &str) -> &str {
+        title
+            .split_once('/')
+            .and_then(|(directory, _)| self.code_languages.get(directory))
+            .unwrap_or(&self.default_code_language)
+    }
+
+    /// How `<math>` formulae are rendered, given that KaTeX needs scripts: without
+    /// them, the formulae are rendered to MathML instead
+    pub fn effective_math_backend(&self) -> MathBackend {
+        match self.math_backend {
+            MathBackend::Katex if !self.scripts => MathBackend::MathMl,
+            backend => backend,
+        }
+    }
+
+    /// The scheme and host the site is served from: `base_url`, or [`crate::BASE_URL`] if
+    /// it isn't set, for the files that need absolute URLs
+    pub fn site_url(&self) -> &str {
+        self.base_url
+            .as_deref()
+            .filter(|base_url| !base_url.is_empty())
+            .unwrap_or(crate::BASE_URL)
+    }
+
+    /// The HTML heading level to render a wiki heading of `level` as
+    pub fn heading_level(&self, level: u8) -> u8 {
+        level.saturating_add(self.heading_level_offset).clamp(2, 6)
+    }
+
+    /// The classes of a rendered `<h{level}>`
+    pub fn heading_class(&self, level: u8) -> &str {
+        self.heading_classes
+            .get(&level)
+            .map_or(DEFAULT_HEADING_CLASS, String::as_str)
+    }
+
+    /// The classes of a link to a page that doesn't exist: the link classes with their
+    /// text colors replaced with red
+    pub fn broken_link_class(&self) -> String {
+        let is_text_color = |class: &&str| {
+            class
+                .rsplit(':')
+                .next()
+                .and_then(|utility| utility.strip_prefix("text-"))
+                .is_some_and(|rest| {
+                    rest.contains('-') || matches!(rest, "black" | "white" | "current" | "inherit")
+                })
+        };
+        self.link_class
+            .split_whitespace()
+            .filter(|class| !is_text_color(class))
+            .chain(["text-red-600", "hover:text-red-800"])
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// The classes of a link to another site: the link classes with an arrow after
+    /// the link
+    pub fn external_link_class(&self) -> String {
+        format!(
+            "{} after:content-['↗'] after:ml-0.5 after:text-xs",
+            self.link_class
+        )
+    }
+
+    /// The `rel` attribute for an external link to `url`
+    pub fn external_link_rel(&self, url: &str) -> String {
+        let Some(follow_domains) = &self.follow_domains else {
+            return self.external_link_rel.clone();
+        };
+
+        let host = url
+            .split_once("://")
+            .map_or(url, |(_, rest)| rest)
+            .split(['/', '?', '#', ':'])
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+        let followed = follow_domains.iter().any(|domain| {
+            let domain = domain.to_lowercase();
+            host == domain || host.ends_with(&format!(".{domain}"))
+        });
+
+        if followed {
+            self.external_link_rel.clone()
+        } else {
+            format!("{} nofollow", self.external_link_rel)
+        }
+    }
+
+    /// `url` without the query parameters in `strip_query_parameters`
+    pub fn strip_query_parameters(&self, url: &str) -> String {
+        let (url_without_fragment, fragment) = match url.split_once('#') {
+            Some((url, fragment)) => (url, Some(fragment)),
+            None => (url, None),
+        };
+        let Some((base, query)) = url_without_fragment.split_once('?') else {
+            return url.to_string();
+        };
+
+        let is_stripped = |parameter: &str| {
+            let key = parameter.split('=').next().unwrap_or_default();
+            self.strip_query_parameters
+                .iter()
+                .any(|pattern| match pattern.strip_suffix('*') {
+                    Some(prefix) => key.starts_with(prefix),
+                    None => key == pattern,
+                })
+        };
+        let parameters = query.split('&').collect::<Vec<_>>();
+        if !parameters.iter().any(|parameter| is_stripped(parameter)) {
+            return url.to_string();
+        }
+
+        let kept = parameters
+            .into_iter()
+            .filter(|parameter| !is_stripped(parameter))
+            .collect::<Vec<_>>();
+        let mut stripped = base.to_string();
+        if !kept.is_empty() {
+            stripped.push('?');
+            stripped.push_str(&kept.join("&"));
+        }
+        if let Some(fragment) = fragment {
+            stripped.push('#');
+            stripped.push_str(fragment);
+        }
+        stripped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_code_language_by_directory() {
+        let config = Config {
+            default_code_language: "cpp".to_string(),
+            code_languages: BTreeMap::from([("Lua".to_string(), "lua".to_string())]),
+            ..Config::default()
+        };
+
+        assert_eq!(config.default_code_language_for("Lua/Server/Vehicle"), "lua");
+        assert_eq!(config.default_code_language_for("Server/Console"), "cpp");
+        // A top-level page named after the directory is not inside it
+        assert_eq!(config.default_code_language_for("Lua"), "cpp");
+    }
+
+    #[test]
+    fn test_nofollow_outside_follow_domains() {
+        let config = Config {
+            follow_domains: Some(vec!["jc-mp.com".to_string()]),
+            ..Config::default()
+        };
+
+        assert_eq!(
+            config.external_link_rel("http://www.jc-mp.com/forums"),
+            "noopener noreferrer"
+        );
+        assert_eq!(
+            config.external_link_rel("https://jc-mp.com"),
+            "noopener noreferrer"
+        );
+        assert_eq!(
+            config.external_link_rel("https://notjc-mp.com"),
+            "noopener noreferrer nofollow"
+        );
+    }
+
+    #[test]
+    fn test_strip_query_parameters() {
+        let config = Config {
+            strip_query_parameters: vec!["utm_*".to_string(), "fbclid".to_string()],
+            ..Config::default()
+        };
+
+        assert_eq!(
+            config.strip_query_parameters("https://example.com/page?utm_source=x&id=1"),
+            "https://example.com/page?id=1"
+        );
+        assert_eq!(
+            config.strip_query_parameters("https://example.com/?fbclid=abc&utm_medium=y#top"),
+            "https://example.com/#top"
+        );
+        assert_eq!(
+            config.strip_query_parameters("https://example.com/?id=1&utm=2"),
+            "https://example.com/?id=1&utm=2"
+        );
+    }
+}
@@ -0,0 +1,112 @@
+//! The classes that wiki content may use on `<div>` and `<span>`. Other classes are
+//! dropped, so that content can only use styling the site provides.
+
+/// The Tailwind utilities available to content. Tailwind only generates the utilities
+/// it finds in its sources, so these are also safelisted in `src/tailwind.css`.
+pub const ALLOWED_UTILITY_CLASSES: &[&str] = &[
+    "clear-both",
+    "float-left",
+    "float-right",
+    "flex",
+    "flex-col",
+    "flex-row",
+    "flex-wrap",
+    "font-bold",
+    "font-mono",
+    "gap-2",
+    "gap-4",
+    "gap-6",
+    "grid",
+    "grid-cols-1",
+    "grid-cols-2",
+    "grid-cols-3",
+    "grid-cols-4",
+    "hidden",
+    "inline-block",
+    "italic",
+    "items-center",
+    "items-start",
+    "justify-between",
+    "justify-center",
+    "mx-auto",
+    "my-2",
+    "my-4",
+    "p-2",
+    "p-4",
+    "rounded",
+    "text-center",
+    "text-gray-600",
+    "text-left",
+    "text-right",
+    "text-sm",
+    "text-xs",
+    "w-full",
+];
+
+/// Classes from the original wiki that have no styling of their own, but are kept as
+/// hooks for styling their contents
+const LEGACY_CLASSES: &[&str] = &["inhalt"];
+
+/// The stylesheet, whose own classes (e.g. `.mw-headline`) content may also use
+const TAILWIND_INPUT: &str = include_str!("tailwind.css");
+
+/// Whether content may use `class`
+pub fn is_allowed(class: &str) -> bool {
+    ALLOWED_UTILITY_CLASSES.contains(&class)
+        || LEGACY_CLASSES.contains(&class)
+        || stylesheet_classes().any(|c| c == class)
+}
+
+/// Split the space-separated `classes` into those content may use, joined back together,
+/// and those it may not
+pub fn filter(classes: &str) -> (String, Vec<&str>) {
+    let (allowed, disallowed): (Vec<&str>, Vec<&str>) = classes
+        .split_whitespace()
+        .partition(|class| is_allowed(class));
+    (allowed.join(" "), disallowed)
+}
+
+/// The classes the stylesheet defines rules for
+fn stylesheet_classes() -> impl Iterator<Item = &'static str> {
+    TAILWIND_INPUT.match_indices('.').filter_map(|(index, _)| {
+        let starts_selector = TAILWIND_INPUT[..index]
+            .chars()
+            .next_back()
+            .is_none_or(|c| c.is_whitespace() || c == ',' || c == '>');
+        let rest = &TAILWIND_INPUT[index + 1..];
+        let end = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-' || c == '_'))
+            .unwrap_or(rest.len());
+        (starts_selector && end > 0 && !rest.starts_with(|c: char| c.is_ascii_digit()))
+            .then(|| &rest[..end])
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allowed_utilities_are_safelisted() {
+        let safelist = TAILWIND_INPUT
+            .split("@source inline(\"")
+            .nth(1)
+            .and_then(|rest| rest.split('"').next())
+            .unwrap()
+            .split_whitespace()
+            .collect::<Vec<_>>();
+        assert_eq!(safelist, ALLOWED_UTILITY_CLASSES);
+    }
+
+    #[test]
+    fn test_filter() {
+        assert_eq!(
+            filter("grid grid-cols-2 mw-headline bg-[url(evil)]"),
+            (
+                "grid grid-cols-2 mw-headline".to_string(),
+                vec!["bg-[url(evil)]"]
+            )
+        );
+        assert!(!is_allowed("rs"));
+    }
+}
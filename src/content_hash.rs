@@ -0,0 +1,31 @@
+//! A hash of each page's rendered content, emitted so that consumers such as caches can
+//! tell when a page has changed without diffing its HTML
+
+/// The `name` of the `<meta>` tag the hash is emitted in
+pub const META_NAME: &str = "content-hash";
+
+/// The 64-bit FNV-1a hash of `content` in hex. Unlike the hashers in `std`, this is
+/// stable across builds and Rust versions.
+pub fn content_hash(content: &str) -> String {
+    bytes_hash(content.as_bytes())
+}
+
+/// The hash of `bytes`, as with [`content_hash`], for files that may not be text
+pub fn bytes_hash(bytes: &[u8]) -> String {
+    let hash = bytes.iter().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    });
+    format!("{hash:016x}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_content_hash() {
+        assert_eq!(content_hash(""), "cbf29ce484222325");
+        assert_eq!(content_hash("a"), "af63dc4c8601ec8c");
+        assert_ne!(content_hash("<p>a</p>"), content_hash("<p>b</p>"));
+    }
+}
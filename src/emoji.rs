@@ -0,0 +1,134 @@
+use wikitext_simplified::WikitextSimplifiedNode as WSN;
+
+use crate::wikitext;
+
+/// Known shortcodes and their emoji, sorted by shortcode for lookup
+const SHORTCODES: &[(&str, &str)] = &[
+    ("+1", "👍"),
+    ("-1", "👎"),
+    ("bomb", "💣"),
+    ("bug", "🐛"),
+    ("car", "🚗"),
+    ("check", "✔️"),
+    ("clap", "👏"),
+    ("cry", "😢"),
+    ("eyes", "👀"),
+    ("fire", "🔥"),
+    ("gun", "🔫"),
+    ("heart", "❤️"),
+    ("helicopter", "🚁"),
+    ("information_source", "ℹ️"),
+    ("joy", "😂"),
+    ("laughing", "😆"),
+    ("lock", "🔒"),
+    ("memo", "📝"),
+    ("no_entry", "⛔"),
+    ("ok_hand", "👌"),
+    ("parachute", "🪂"),
+    ("party", "🎉"),
+    ("rocket", "🚀"),
+    ("sad", "😞"),
+    ("smile", "😄"),
+    ("sparkles", "✨"),
+    ("star", "⭐"),
+    ("tada", "🎉"),
+    ("thinking", "🤔"),
+    ("thumbsdown", "👎"),
+    ("thumbsup", "👍"),
+    ("warning", "⚠️"),
+    ("wink", "😉"),
+    ("wrench", "🔧"),
+    ("x", "❌"),
+];
+
+/// Replace the known `:shortcode:`s in the text of `nodes` with their emoji, leaving
+/// code and `nowiki` content untouched
+pub fn expand_in_nodes(nodes: &mut [WSN]) {
+    for node in nodes {
+        if wikitext::is_code(node) {
+            continue;
+        }
+        if let WSN::Text { text } = node {
+            *text = expand(text);
+        }
+        for children in wikitext::children_mut(node) {
+            expand_in_nodes(children);
+        }
+    }
+}
+
+/// Replace the known `:shortcode:`s in `text` with their emoji; unknown shortcodes are kept
+pub fn expand(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(':') {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let name_len = after
+            .find(|c: char| !(c.is_ascii_lowercase() || c.is_ascii_digit() || "_+-".contains(c)))
+            .unwrap_or(after.len());
+
+        if after[name_len..].starts_with(':')
+            && let Some(emoji) = lookup(&after[..name_len])
+        {
+            result.push_str(emoji);
+            rest = &after[name_len + 1..];
+        } else {
+            result.push(':');
+            rest = after;
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+fn lookup(shortcode: &str) -> Option<&'static str> {
+    SHORTCODES
+        .binary_search_by_key(&shortcode, |&(shortcode, _)| shortcode)
+        .ok()
+        .map(|idx| SHORTCODES[idx].1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shortcodes_are_sorted() {
+        assert!(SHORTCODES.windows(2).all(|w| w[0].0 < w[1].0));
+    }
+
+    #[test]
+    fn test_expand_known_and_unknown_shortcodes() {
+        assert_eq!(expand("Launch :rocket: now"), "Launch 🚀 now");
+        assert_eq!(expand(":not_an_emoji: stays"), ":not_an_emoji: stays");
+        assert_eq!(expand("Vehicle:GetPosition()"), "Vehicle:GetPosition()");
+        assert_eq!(expand("a::rocket:"), "a:🚀");
+    }
+
+    #[test]
+    fn test_shortcodes_expand_in_prose_but_not_code() {
+        let mut nodes = vec![
+            WSN::Text {
+                text: "Launch :rocket:".to_string(),
+            },
+            WSN::Tag {
+                name: "code".to_string(),
+                attributes: None,
+                children: vec![WSN::Text {
+                    text: ":rocket:".to_string(),
+                }],
+            },
+        ];
+        expand_in_nodes(&mut nodes);
+
+        let [WSN::Text { text }, WSN::Tag { children, .. }] = nodes.as_slice() else {
+            panic!("Unexpected nodes: {nodes:?}");
+        };
+        assert_eq!(text, "Launch 🚀");
+        assert!(
+            matches!(children.as_slice(), [WSN::Text { text }] if text == ":rocket:"),
+            "{children:?}"
+        );
+    }
+}
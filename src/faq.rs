@@ -0,0 +1,138 @@
+//! `FAQPage` structured data for search engines, from the definition lists of questions
+//! and answers in a page's FAQ sections
+
+use wikitext_simplified::{DefinitionListItemType, WikitextSimplifiedNode as WSN};
+
+use crate::wikitext;
+
+/// A question and its answer, as plain text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Faq {
+    pub question: String,
+    pub answer: String,
+}
+
+/// The questions and answers in the definition lists of `nodes` that are in a section
+/// whose heading is one of `sections` (compared case-insensitively). A definition list's
+/// terms are the questions, and the details following each term its answer.
+pub fn extract(nodes: &[WSN], sections: &[String]) -> Vec<Faq> {
+    let mut faqs = vec![];
+    // The level of the heading of the FAQ section being read, if one is
+    let mut faq_level = None;
+    for node in nodes {
+        match node {
+            WSN::Heading { level, children } => {
+                if faq_level.is_some_and(|faq_level| *level > faq_level) {
+                    continue;
+                }
+                let heading = wikitext::plain_text(children);
+                faq_level = sections
+                    .iter()
+                    .any(|section| section.trim().eq_ignore_ascii_case(heading.trim()))
+                    .then_some(*level);
+            }
+            WSN::DefinitionList { items } if faq_level.is_some() => {
+                for item in items {
+                    let text = wikitext::plain_text(&item.content).trim().to_string();
+                    match item.type_ {
+                        DefinitionListItemType::Term => faqs.push(Faq {
+                            question: text,
+                            answer: String::new(),
+                        }),
+                        DefinitionListItemType::Details => {
+                            // Details before any term have no question to answer
+                            if let Some(faq) = faqs.last_mut() {
+                                if !faq.answer.is_empty() {
+                                    faq.answer.push(' ');
+                                }
+                                faq.answer.push_str(&text);
+                            }
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    faqs.retain(|faq| !faq.question.is_empty() && !faq.answer.is_empty());
+    faqs
+}
+
+/// The `FAQPage` JSON-LD for `faqs`
+pub fn json_ld(faqs: &[Faq]) -> String {
+    let value = serde_json::json!({
+        "@context": "https://schema.org",
+        "@type": "FAQPage",
+        "mainEntity": faqs.iter().map(|faq| serde_json::json!({
+            "@type": "Question",
+            "name": faq.question,
+            "acceptedAnswer": {
+                "@type": "Answer",
+                "text": faq.answer,
+            },
+        })).collect::<Vec<_>>(),
+    });
+    // A `</script>` in the text would end the script element early
+    value.to_string().replace("</", "<\\/")
+}
+
+/// The `<script>` carrying the JSON-LD for `faqs`, for the page's `<head>`
+pub fn render(faqs: &[Faq]) -> paxhtml::Element {
+    paxhtml::html! {
+        <script type="application/ld+json">{paxhtml::Element::Raw { html: json_ld(faqs) }}</script>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_marked_definition_lists_become_faq_json_ld() {
+        let source = "== Frequently asked questions ==
+; Is Steam Family Sharing supported?
+: No, you must own Just Cause 2 on your own account.
+; Does the demo work?
+: No.
+: The demo isn't supported.
+=== Servers ===
+; Can I host a server?
+: Yes, see [[Server|the server guide]].
+== Glossary ==
+; JC2-MP
+: Just Cause 2: Multiplayer";
+        let pwt_configuration = wikitext_simplified::wikitext_util::wikipedia_pwt_configuration();
+        let nodes =
+            wikitext_simplified::parse_and_simplify_wikitext(source, &pwt_configuration).unwrap();
+        let faqs = extract(&nodes, &["Frequently Asked Questions".to_string()]);
+
+        let questions = faqs
+            .iter()
+            .map(|faq| faq.question.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            questions,
+            [
+                "Is Steam Family Sharing supported?",
+                "Does the demo work?",
+                "Can I host a server?"
+            ]
+        );
+
+        let json: serde_json::Value = serde_json::from_str(&json_ld(&faqs)).unwrap();
+        assert_eq!(json["@type"], "FAQPage");
+        assert_eq!(json["mainEntity"][1]["@type"], "Question");
+        assert_eq!(json["mainEntity"][1]["name"], "Does the demo work?");
+        assert_eq!(
+            json["mainEntity"][1]["acceptedAnswer"]["text"],
+            "No. The demo isn't supported."
+        );
+        assert_eq!(
+            json["mainEntity"][2]["acceptedAnswer"]["text"],
+            "Yes, see the server guide."
+        );
+
+        // Without a marked section, nothing is extracted
+        assert!(extract(&nodes, &[]).is_empty());
+    }
+}
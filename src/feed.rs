@@ -0,0 +1,105 @@
+//! `feed.xml`: an Atom feed of the pages that were modified most recently, written to
+//! the output root so that readers can follow changes to the wiki
+
+use std::{fmt::Write as _, path::Path};
+
+use crate::{build_date::BuildDate, xml::escape};
+
+/// The file name of the feed, relative to the output root
+pub const FEED_FILE: &str = "feed.xml";
+/// The number of characters of a page's text given as its summary
+const SUMMARY_LENGTH: usize = 200;
+
+/// A page as it appears in the feed
+pub struct FeedEntry<'a> {
+    pub title: &'a str,
+    pub url: String,
+    /// The time the page was last modified, in seconds since the Unix epoch
+    pub updated: i64,
+    pub summary: String,
+}
+
+/// The summary of a page with the plain text `text`: its first characters, with the
+/// whitespace collapsed
+pub fn summary(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() <= SUMMARY_LENGTH {
+        return text;
+    }
+    let mut summary = text.chars().take(SUMMARY_LENGTH).collect::<String>();
+    summary.push('…');
+    summary
+}
+
+/// The contents of the feed, with `entries` in order. `base_url` makes the URLs absolute,
+/// as Atom requires of ids and feed readers expect of links.
+pub fn atom(site_name: &str, base_url: &str, entries: &[FeedEntry]) -> String {
+    let base_url = base_url.trim_end_matches('/');
+    let updated = entries.iter().map(|entry| entry.updated).max().unwrap_or(0);
+
+    let mut feed = "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n".to_string();
+    feed.push_str("<feed xmlns=\"http://www.w3.org/2005/Atom\">\n");
+    writeln!(feed, "  <title>{}</title>", escape(site_name)).unwrap();
+    writeln!(feed, "  <id>{}/{FEED_FILE}</id>", escape(base_url)).unwrap();
+    writeln!(
+        feed,
+        "  <link rel=\"self\" href=\"{}/{FEED_FILE}\" />",
+        escape(base_url)
+    )
+    .unwrap();
+    writeln!(feed, "  <updated>{}</updated>", timestamp(updated)).unwrap();
+    for entry in entries {
+        let url = escape(&format!("{base_url}{}", entry.url));
+        feed.push_str("  <entry>\n");
+        writeln!(feed, "    <title>{}</title>", escape(entry.title)).unwrap();
+        writeln!(feed, "    <id>{url}</id>").unwrap();
+        writeln!(feed, "    <link href=\"{url}\" />").unwrap();
+        writeln!(feed, "    <updated>{}</updated>", timestamp(entry.updated)).unwrap();
+        writeln!(feed, "    <summary>{}</summary>", escape(&entry.summary)).unwrap();
+        feed.push_str("  </entry>\n");
+    }
+    feed.push_str("</feed>\n");
+    feed
+}
+
+/// Write the feed to the output root `dst`
+pub fn write(
+    site_name: &str,
+    base_url: &str,
+    entries: &[FeedEntry],
+    dst: &Path,
+) -> anyhow::Result<()> {
+    std::fs::write(dst.join(FEED_FILE), atom(site_name, base_url, entries))?;
+    Ok(())
+}
+
+/// `seconds` since the Unix epoch as an RFC 3339 timestamp
+fn timestamp(seconds: i64) -> String {
+    let time = seconds.rem_euclid(86_400);
+    format!(
+        "{}T{:02}:{:02}:{:02}Z",
+        BuildDate::from_unix_seconds(seconds),
+        time / 3600,
+        time / 60 % 60,
+        time % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timestamps_and_summaries() {
+        assert_eq!(timestamp(0), "1970-01-01T00:00:00Z");
+        assert_eq!(timestamp(1_700_000_000), "2023-11-14T22:13:20Z");
+
+        assert_eq!(
+            summary("Vehicles  can\nbe driven."),
+            "Vehicles can be driven."
+        );
+        let long = summary(&"word ".repeat(100));
+        assert_eq!(long.chars().count(), SUMMARY_LENGTH + 1);
+        assert!(long.ends_with('…'));
+    }
+}
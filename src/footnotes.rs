@@ -0,0 +1,79 @@
+/// The footnotes (`<ref>`s) collected while rendering a page
+#[derive(Debug, Default)]
+pub struct Footnotes {
+    /// The notes that have not yet been rendered by a `<references />`
+    notes: Vec<Footnote>,
+    /// The number of notes on the page so far, used to number new notes
+    count: usize,
+}
+
+#[derive(Debug)]
+struct Footnote {
+    number: usize,
+    name: Option<String>,
+    content: paxhtml::Element,
+}
+
+impl Footnotes {
+    pub fn is_empty(&self) -> bool {
+        self.notes.is_empty()
+    }
+
+    /// Record a `<ref>` and return the marker linking to it. A named reference that
+    /// has already been defined reuses the existing note's number.
+    pub fn add(&mut self, name: Option<&str>, content: paxhtml::Element) -> paxhtml::Element {
+        use paxhtml::html;
+
+        if let Some(existing) = name.and_then(|name| {
+            self.notes
+                .iter()
+                .find(|note| note.name.as_deref() == Some(name))
+        }) {
+            let number = existing.number;
+            return html! {
+                <sup><a class="text-blue-600 hover:underline" href={format!("#cite-note-{number}")}>{format!("[{number}]")}</a></sup>
+            };
+        }
+
+        self.count += 1;
+        let number = self.count;
+        self.notes.push(Footnote {
+            number,
+            name: name.map(|name| name.to_string()),
+            content,
+        });
+        html! {
+            <sup id={format!("cite-ref-{number}")}><a class="text-blue-600 hover:underline" href={format!("#cite-note-{number}")}>{format!("[{number}]")}</a></sup>
+        }
+    }
+
+    /// Render the pending notes as a list, clearing them.
+    ///
+    /// If `responsive` is set and there are at least `columns_threshold` notes, the
+    /// list is split into columns on wider screens.
+    pub fn render(&mut self, responsive: bool, columns_threshold: usize) -> paxhtml::Element {
+        use paxhtml::html;
+
+        let class = if responsive && self.notes.len() >= columns_threshold {
+            "list-decimal list-inside text-sm md:columns-2 lg:columns-3 gap-8"
+        } else {
+            "list-decimal list-inside text-sm"
+        };
+
+        let notes = std::mem::take(&mut self.notes);
+        html! {
+            <ol class={class}>
+                #{notes.into_iter().map(|note| {
+                    let number = note.number;
+                    html! {
+                        <li id={format!("cite-note-{number}")} value={number.to_string()}>
+                            <a class="text-blue-600 hover:underline" href={format!("#cite-ref-{number}")}>"↑"</a>
+                            " "
+                            {note.content}
+                        </li>
+                    }
+                })}
+            </ol>
+        }
+    }
+}
@@ -0,0 +1,110 @@
+//! Per-page metadata given as `key: value` lines between `---` lines at the top of a page
+
+/// A stylesheet or script added to a single page
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Asset {
+    /// A path relative to the `static` directory
+    Path(String),
+    /// Inline CSS or JavaScript
+    Inline(String),
+}
+impl Asset {
+    fn parse(value: &str, extension: &str) -> Self {
+        if value.ends_with(extension) && !value.contains(char::is_whitespace) {
+            Self::Path(value.trim_start_matches('/').to_string())
+        } else {
+            Self::Inline(value.to_string())
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Frontmatter {
+    /// Stylesheets added to the page's `<head>`
+    pub extra_css: Vec<Asset>,
+    /// Scripts added to the page's `<head>`
+    pub extra_js: Vec<Asset>,
+    /// Other titles that redirect to the page
+    pub aliases: Vec<String>,
+}
+
+/// Split the frontmatter, if any, from the start of `content`, returning it and the
+/// remaining wikitext
+pub fn split(content: &str) -> anyhow::Result<(Frontmatter, &str)> {
+    let mut frontmatter = Frontmatter::default();
+    let Some(rest) = content
+        .strip_prefix("---\n")
+        .or_else(|| content.strip_prefix("---\r\n"))
+    else {
+        return Ok((frontmatter, content));
+    };
+
+    let mut consumed = content.len() - rest.len();
+    for line in rest.split_inclusive('\n') {
+        consumed += line.len();
+        let line = line.trim();
+        if line == "---" {
+            return Ok((frontmatter, &content[consumed..]));
+        }
+        if line.is_empty() {
+            continue;
+        }
+
+        let Some((key, value)) = line.split_once(':') else {
+            anyhow::bail!("Expected `key: value` in frontmatter, got {line:?}");
+        };
+        let value = value.trim();
+        match key.trim() {
+            "extra_css" => frontmatter.extra_css.push(Asset::parse(value, ".css")),
+            "extra_js" => frontmatter.extra_js.push(Asset::parse(value, ".js")),
+            "aliases" => frontmatter.aliases.extend(parse_list(value)),
+            key => anyhow::bail!("Unknown frontmatter key: {key}"),
+        }
+    }
+
+    anyhow::bail!("Frontmatter is missing its closing `---`")
+}
+
+/// The items of a list written as `[a, b]`, or of a single item without brackets
+fn parse_list(value: &str) -> impl Iterator<Item = String> + '_ {
+    let value = value
+        .strip_prefix('[')
+        .and_then(|value| value.strip_suffix(']'))
+        .unwrap_or(value);
+    value
+        .split(',')
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_frontmatter() {
+        let (frontmatter, body) =
+            split("---\nextra_css: css/map.css\nextra_js: console.log('hi');\n---\n'''Map'''")
+                .unwrap();
+
+        assert_eq!(
+            frontmatter.extra_css,
+            vec![Asset::Path("css/map.css".to_string())]
+        );
+        assert_eq!(
+            frontmatter.extra_js,
+            vec![Asset::Inline("console.log('hi');".to_string())]
+        );
+        assert_eq!(body, "'''Map'''");
+
+        let (frontmatter, _) =
+            split("---\naliases: [Old Name, Lua/AltName]\naliases: Another\n---\n").unwrap();
+        assert_eq!(frontmatter.aliases, ["Old Name", "Lua/AltName", "Another"]);
+
+        let (frontmatter, body) = split("No frontmatter").unwrap();
+        assert_eq!(frontmatter, Frontmatter::default());
+        assert_eq!(body, "No frontmatter");
+
+        assert!(split("---\nextra_css: a.css\n").is_err());
+    }
+}
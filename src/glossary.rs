@@ -0,0 +1,150 @@
+//! Tooltips for jargon: the first use of each glossary term on a page is wrapped in an
+//! `<abbr>` carrying its definition
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    path::Path,
+};
+
+use wikitext_simplified::WikitextSimplifiedNode as WSN;
+
+use crate::wikitext;
+
+/// Terms and their definitions, loaded from a JSON object (`{"term": "definition"}`)
+#[derive(Debug, Default)]
+pub struct Glossary {
+    terms: BTreeMap<String, String>,
+}
+impl Glossary {
+    pub fn new(terms: BTreeMap<String, String>) -> Self {
+        Self { terms }
+    }
+
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Failed to read glossary {}: {e}", path.display()))?;
+        let terms = serde_json::from_str(&content)
+            .map_err(|e| anyhow::anyhow!("Failed to parse glossary {}: {e}", path.display()))?;
+        Ok(Self::new(terms))
+    }
+
+    /// Wrap the first occurrence of each term in the text of a page's `nodes`, skipping
+    /// code, links and headings
+    pub fn apply(&self, nodes: &mut Vec<WSN>) {
+        self.apply_to_nodes(nodes, &mut HashSet::new());
+    }
+
+    fn apply_to_nodes<'a>(&'a self, nodes: &mut Vec<WSN>, used: &mut HashSet<&'a str>) {
+        let mut i = 0;
+        while i < nodes.len() {
+            match &mut nodes[i] {
+                node if wikitext::is_code(node) => {}
+                WSN::Heading { .. } => {}
+                WSN::Tag { name, .. } if name == "abbr" => {}
+                WSN::Text { text } => {
+                    if let Some((start, end, term)) = self.find_term(text, used) {
+                        used.insert(term);
+                        let after = text.split_off(end);
+                        let matched = text.split_off(start);
+                        let abbr = WSN::Tag {
+                            name: "abbr".to_string(),
+                            attributes: Some(format!(
+                                "class=\"cursor-help underline decoration-dotted\" title=\"{}\"",
+                                self.terms[term].replace('"', "&quot;")
+                            )),
+                            children: vec![WSN::Text { text: matched }],
+                        };
+                        nodes.splice(i + 1..i + 1, [abbr, WSN::Text { text: after }]);
+                        // Continue with the text after the term
+                        i += 2;
+                        continue;
+                    }
+                }
+                node => {
+                    for children in wikitext::children_mut(node) {
+                        self.apply_to_nodes(children, used);
+                    }
+                }
+            }
+            i += 1;
+        }
+    }
+
+    /// The earliest whole-word occurrence of a term that hasn't been used yet, preferring
+    /// the longest term when several start at the same place
+    fn find_term(&self, text: &str, used: &HashSet<&str>) -> Option<(usize, usize, &str)> {
+        self.terms
+            .keys()
+            .filter(|term| !term.is_empty() && !used.contains(term.as_str()))
+            .filter_map(|term| {
+                let start = text.match_indices(term.as_str()).find_map(|(start, _)| {
+                    let end = start + term.len();
+                    let boundary_before = !text[..start]
+                        .chars()
+                        .next_back()
+                        .is_some_and(char::is_alphanumeric);
+                    let boundary_after = !text[end..]
+                        .chars()
+                        .next()
+                        .is_some_and(char::is_alphanumeric);
+                    (boundary_before && boundary_after).then_some(start)
+                })?;
+                Some((start, start + term.len(), term.as_str()))
+            })
+            .min_by_key(|&(start, end, _)| (start, std::cmp::Reverse(end)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_first_term_in_prose_is_wrapped_but_not_in_code() {
+        let glossary = Glossary::new(BTreeMap::from([(
+            "WNO".to_string(),
+            "World Network Object".to_string(),
+        )]));
+        let mut nodes = vec![
+            WSN::Tag {
+                name: "code".to_string(),
+                attributes: None,
+                children: vec![WSN::Text {
+                    text: "WNO.Create()".to_string(),
+                }],
+            },
+            WSN::Text {
+                text: "Create a WNO, then another WNO.".to_string(),
+            },
+        ];
+        glossary.apply(&mut nodes);
+
+        let [
+            WSN::Tag { children: code, .. },
+            WSN::Text { text: before },
+            WSN::Tag {
+                name,
+                attributes,
+                children,
+            },
+            WSN::Text { text: after },
+        ] = nodes.as_slice()
+        else {
+            panic!("Unexpected nodes: {nodes:?}");
+        };
+        assert!(
+            matches!(code.as_slice(), [WSN::Text { text }] if text == "WNO.Create()"),
+            "{code:?}"
+        );
+        assert_eq!(before, "Create a ");
+        assert_eq!(name, "abbr");
+        assert!(
+            attributes
+                .as_deref()
+                .unwrap()
+                .contains("title=\"World Network Object\"")
+        );
+        assert!(matches!(children.as_slice(), [WSN::Text { text }] if text == "WNO"));
+        assert_eq!(after, ", then another WNO.");
+    }
+}
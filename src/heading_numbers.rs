@@ -0,0 +1,57 @@
+//! Hierarchical section numbers (1, 1.1, 1.2, 2), added to a page's headings as
+//! they are rendered
+
+/// Numbers headings one at a time, in the order they appear
+#[derive(Debug, Default)]
+pub struct SectionNumbers {
+    /// The level of each enclosing section and its number within its parent
+    sections: Vec<(u8, usize)>,
+}
+impl SectionNumbers {
+    /// The number of the next heading, at `level`. A heading more than one level below
+    /// the previous one is numbered as if it were directly below it.
+    pub fn next(&mut self, level: u8) -> String {
+        let mut closed = None;
+        while let Some(&(section_level, number)) = self.sections.last()
+            && section_level > level
+        {
+            closed = Some(number);
+            self.sections.pop();
+        }
+        match self.sections.last_mut() {
+            Some((section_level, number)) if *section_level == level => *number += 1,
+            // Continue on from a deeper section that was closed, so that a heading
+            // after a skipped level doesn't repeat its number
+            _ => self
+                .sections
+                .push((level, closed.map_or(1, |number| number + 1))),
+        }
+        self.sections
+            .iter()
+            .map(|(_, number)| number.to_string())
+            .collect::<Vec<_>>()
+            .join(".")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_section_numbers() {
+        let section_numbers = |levels: &[u8]| {
+            let mut numbers = SectionNumbers::default();
+            levels
+                .iter()
+                .map(|&level| numbers.next(level))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(section_numbers(&[2, 3, 3, 2]), ["1", "1.1", "1.2", "2"]);
+        assert_eq!(
+            section_numbers(&[2, 4, 3, 2, 3]),
+            ["1", "1.1", "1.2", "2", "2.1"]
+        );
+        assert_eq!(section_numbers(&[3, 2, 3]), ["1", "2", "2.1"]);
+    }
+}
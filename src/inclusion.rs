@@ -0,0 +1,101 @@
+//! `<noinclude>`, `<includeonly>` and `<onlyinclude>`: the parts of a page that are only
+//! shown on the page itself, or only where it's transcluded as a template
+
+use std::ops::Range;
+
+const NOINCLUDE: &str = "noinclude";
+const INCLUDEONLY: &str = "includeonly";
+const ONLYINCLUDE: &str = "onlyinclude";
+
+/// A `<tag>...</tag>` block in a source
+struct Block {
+    /// The block, including its tags
+    outer: Range<usize>,
+    /// The content between its tags
+    inner: Range<usize>,
+}
+
+/// The `<tag>` blocks in `source`, matched case-insensitively. As in MediaWiki, a block
+/// that isn't closed runs to the end of the source.
+fn blocks(source: &str, tag: &str) -> Vec<Block> {
+    let lowercase = source.to_ascii_lowercase();
+    let (open, close) = (format!("<{tag}>"), format!("</{tag}>"));
+    let mut blocks = vec![];
+    let mut offset = 0;
+    while let Some(start) = lowercase[offset..].find(&open).map(|i| offset + i) {
+        let content = start + open.len();
+        let (inner_end, end) = match lowercase[content..].find(&close) {
+            Some(i) => (content + i, content + i + close.len()),
+            None => (source.len(), source.len()),
+        };
+        blocks.push(Block {
+            outer: start..end,
+            inner: content..inner_end,
+        });
+        offset = end;
+    }
+    blocks
+}
+
+/// `source` with each `<tag>` block replaced by its content, if `keep` is set, or else
+/// removed
+fn replace_blocks(source: &str, tag: &str, keep: bool) -> String {
+    let mut result = String::with_capacity(source.len());
+    let mut offset = 0;
+    for block in blocks(source, tag) {
+        result.push_str(&source[offset..block.outer.start]);
+        if keep {
+            result.push_str(&source[block.inner]);
+        }
+        offset = block.outer.end;
+    }
+    result.push_str(&source[offset..]);
+    result
+}
+
+/// The part of a template's `source` that is transcluded where it's used: only the
+/// content of its `<onlyinclude>` blocks if it has any, without its `<noinclude>` blocks,
+/// and with the content of its `<includeonly>` blocks
+pub fn transcluded(source: &str) -> String {
+    let only = blocks(source, ONLYINCLUDE);
+    let source = if only.is_empty() {
+        source.to_string()
+    } else {
+        only.into_iter().map(|block| &source[block.inner]).collect()
+    };
+    let source = replace_blocks(&source, NOINCLUDE, false);
+    replace_blocks(&source, INCLUDEONLY, true)
+}
+
+/// The part of a page's `source` that is shown on the page itself: without its
+/// `<includeonly>` blocks, and with the content of its `<noinclude>` and `<onlyinclude>`
+/// blocks
+pub fn rendered(source: &str) -> String {
+    let source = replace_blocks(source, INCLUDEONLY, false);
+    let source = replace_blocks(&source, NOINCLUDE, true);
+    replace_blocks(&source, ONLYINCLUDE, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inclusion_tags() {
+        let source = "'''{{{1}}}'''<includeonly>[[Category:Vehicles]]</includeonly><NoInclude>
+Shows a vehicle's name in bold.</NoInclude>";
+        assert_eq!(transcluded(source), "'''{{{1}}}'''[[Category:Vehicles]]");
+        assert_eq!(
+            rendered(source),
+            "'''{{{1}}}'''\nShows a vehicle's name in bold."
+        );
+
+        let source = "Intro <onlyinclude>A</onlyinclude> middle <onlyinclude>B<noinclude> (doc)</noinclude></onlyinclude> end";
+        assert_eq!(transcluded(source), "AB");
+        assert_eq!(rendered(source), "Intro A middle B (doc) end");
+
+        // An unclosed block runs to the end
+        assert_eq!(transcluded("Text<noinclude>Documentation"), "Text");
+        assert_eq!(transcluded("No tags"), "No tags");
+    }
+}
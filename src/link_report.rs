@@ -0,0 +1,150 @@
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    path::Path,
+};
+
+use crate::site::{Site, normalize_title};
+
+/// The file the link report is written to in JSON mode
+pub const LINK_REPORT_FILE: &str = "link-report.json";
+
+/// How the link report is presented
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    /// Printed to stdout for people to read
+    #[default]
+    Text,
+    /// Written to [`LINK_REPORT_FILE`] for CI to consume
+    Json,
+}
+impl std::str::FromStr for ReportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            _ => anyhow::bail!("Unknown report format: {s} (expected `text` or `json`)"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueKind {
+    /// A link to a page that doesn't exist
+    BrokenLink,
+    /// A page that no other page links to; `target` is the page itself
+    Orphan,
+    /// A redirect whose target is itself a redirect
+    RedirectChain,
+    /// A page marked as a stub or too short; `target` is the page itself
+    Stub,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, serde::Serialize)]
+pub struct LinkIssue {
+    pub source: String,
+    pub target: String,
+    pub issue: IssueKind,
+}
+
+/// Problems with the links between pages, gathered after every page has been rendered
+#[derive(Debug, Default, serde::Serialize)]
+pub struct LinkReport {
+    pub issues: Vec<LinkIssue>,
+}
+impl LinkReport {
+    pub fn new(site: &Site) -> Self {
+        let mut issues = BTreeSet::new();
+
+        let redirects: HashMap<String, &str> = site
+            .redirects
+            .iter()
+            .map(|(title, target)| (normalize_title(title), target.as_str()))
+            .collect();
+        let resolve = |title: &str| {
+            let title = normalize_title(title);
+            match redirects.get(&title) {
+                Some(target) => normalize_title(target),
+                None => title,
+            }
+        };
+
+        let mut linked = HashSet::new();
+        for (source, target) in site.links.borrow().iter() {
+            if !site.link_target_exists(target) {
+                issues.insert(LinkIssue {
+                    source: source.clone(),
+                    target: target.clone(),
+                    issue: IssueKind::BrokenLink,
+                });
+            }
+            if resolve(source) != resolve(target) {
+                linked.insert(resolve(target));
+            }
+        }
+
+        for title in &site.page_titles {
+            let is_main_page = normalize_title(title) == normalize_title(&site.config.main_page);
+            if !is_main_page && !linked.contains(&normalize_title(title)) {
+                issues.insert(LinkIssue {
+                    source: title.clone(),
+                    target: title.clone(),
+                    issue: IssueKind::Orphan,
+                });
+            }
+        }
+
+        for (title, target) in &site.redirects {
+            if redirects.contains_key(&normalize_title(target)) {
+                issues.insert(LinkIssue {
+                    source: title.clone(),
+                    target: target.clone(),
+                    issue: IssueKind::RedirectChain,
+                });
+            }
+        }
+
+        for title in &site.stubs {
+            issues.insert(LinkIssue {
+                source: title.clone(),
+                target: title.clone(),
+                issue: IssueKind::Stub,
+            });
+        }
+
+        Self {
+            issues: issues.into_iter().collect(),
+        }
+    }
+
+    /// Present the report in the given format
+    pub fn write(&self, format: ReportFormat) -> anyhow::Result<()> {
+        match format {
+            ReportFormat::Text => {
+                println!("Link report: {} issue(s)", self.issues.len());
+                for issue in &self.issues {
+                    match issue.issue {
+                        IssueKind::BrokenLink => {
+                            println!("  broken link: {} -> {}", issue.source, issue.target)
+                        }
+                        IssueKind::Orphan => println!("  orphan: {}", issue.source),
+                        IssueKind::RedirectChain => {
+                            println!("  redirect chain: {} -> {}", issue.source, issue.target)
+                        }
+                        IssueKind::Stub => println!("  stub: {}", issue.source),
+                    }
+                }
+            }
+            ReportFormat::Json => {
+                std::fs::write(
+                    Path::new(LINK_REPORT_FILE),
+                    serde_json::to_string_pretty(self)?,
+                )?;
+                println!("Link report written to {LINK_REPORT_FILE}");
+            }
+        }
+        Ok(())
+    }
+}
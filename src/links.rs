@@ -0,0 +1,86 @@
+//! Resolving the targets and text of internal `[[links]]`
+
+/// Resolve a link target relative to the page titled `current`: `/Sub` is a subpage of
+/// the current page and `../Sibling` a sibling of it. Other targets are absolute.
+pub fn resolve_title(current: &str, target: &str) -> String {
+    let (base, relative) = if let Some(relative) = target.strip_prefix('/') {
+        (current.to_string(), relative)
+    } else if target.starts_with("../") {
+        let mut base = current;
+        let mut relative = target;
+        while let Some(rest) = relative.strip_prefix("../") {
+            base = base.rsplit_once('/').map_or("", |(parent, _)| parent);
+            relative = rest;
+        }
+        (base.to_string(), relative)
+    } else {
+        return target.to_string();
+    };
+
+    let relative = relative.trim_end_matches('/');
+    match (base.is_empty(), relative.is_empty()) {
+        (_, true) => base,
+        (true, false) => relative.to_string(),
+        (false, false) => format!("{base}/{relative}"),
+    }
+}
+
+/// The text shown for a link written without any, such as a relative link whose
+/// trailing slash hides the leading path (`[[/Sub/]]` shows `Sub`)
+pub fn default_text(target: &str) -> &str {
+    let is_relative = target.starts_with('/') || target.starts_with("../");
+    match target.strip_suffix('/') {
+        Some(stripped) if is_relative => stripped.rsplit('/').next().unwrap_or(stripped),
+        _ => target,
+    }
+}
+
+/// The text produced by the pipe trick (`[[Target|]]`): the target without its
+/// namespace and any trailing parenthetical, or failing that, any trailing comma part
+pub fn pipe_trick(target: &str) -> String {
+    let title = match target.split_once(':') {
+        Some((namespace, title)) if !namespace.contains('/') => title,
+        _ => target,
+    }
+    .trim();
+
+    if let Some(open) = title.rfind(" (")
+        && title.ends_with(')')
+    {
+        return title[..open].trim().to_string();
+    }
+    title
+        .split_once(',')
+        .map_or(title, |(title, _)| title)
+        .trim()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_title() {
+        let current = "Lua/Server/Vehicle";
+        assert_eq!(
+            resolve_title(current, "/Functions"),
+            "Lua/Server/Vehicle/Functions"
+        );
+        assert_eq!(
+            resolve_title(current, "/Functions/"),
+            "Lua/Server/Vehicle/Functions"
+        );
+        assert_eq!(resolve_title(current, "../Player"), "Lua/Server/Player");
+        assert_eq!(resolve_title(current, "../../Client"), "Lua/Client");
+        assert_eq!(resolve_title(current, "Main Page"), "Main Page");
+    }
+
+    #[test]
+    fn test_pipe_trick() {
+        assert_eq!(pipe_trick("Vehicle (disambiguation)"), "Vehicle");
+        assert_eq!(pipe_trick("Help:Lua (scripting)"), "Lua");
+        assert_eq!(pipe_trick("Seattle, Washington"), "Seattle");
+        assert_eq!(pipe_trick("Lua/Server/Vehicle"), "Lua/Server/Vehicle");
+    }
+}
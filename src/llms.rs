@@ -0,0 +1,48 @@
+//! `llms.txt` and `llms-full.txt`: an index of the site's pages and a digest of their
+//! text, for assistants and other tools that read the site as plain text
+
+use std::{fmt::Write as _, path::Path};
+
+/// The index of every page, with links to them
+pub const LLMS_TXT: &str = "llms.txt";
+/// The text of every page, each under its title and URL
+pub const LLMS_FULL_TXT: &str = "llms-full.txt";
+
+/// A page as it appears in the digest
+pub struct DigestPage<'a> {
+    pub title: &'a str,
+    pub url: String,
+    pub text: String,
+}
+
+/// The contents of `llms.txt`, listing `pages` in order
+pub fn llms_txt(site_name: &str, pages: &[DigestPage]) -> String {
+    let mut index = format!(
+        "# {site_name}\n\n> The full text of these pages is in /{LLMS_FULL_TXT}.\n\n## Pages\n\n"
+    );
+    for page in pages {
+        writeln!(index, "- [{}]({})", page.title, page.url).unwrap();
+    }
+    index
+}
+
+/// The contents of `llms-full.txt`, with the text of `pages` in order
+pub fn llms_full_txt(site_name: &str, pages: &[DigestPage]) -> String {
+    let mut digest = format!("# {site_name}\n");
+    for page in pages {
+        write!(
+            digest,
+            "\n## {}\n\nURL: {}\n\n{}",
+            page.title, page.url, page.text
+        )
+        .unwrap();
+    }
+    digest
+}
+
+/// Write both files to the output root `dst`
+pub fn write(site_name: &str, pages: &[DigestPage], dst: &Path) -> anyhow::Result<()> {
+    std::fs::write(dst.join(LLMS_TXT), llms_txt(site_name, pages))?;
+    std::fs::write(dst.join(LLMS_FULL_TXT), llms_full_txt(site_name, pages))?;
+    Ok(())
+}
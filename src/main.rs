@@ -1,20 +1,148 @@
 use std::{
     collections::{BTreeMap, BTreeSet},
     fs,
-    path::Path,
+    path::{Path, PathBuf},
     sync::OnceLock,
+    time::Instant,
 };
 
 use template::{TemplateToInstantiate, Templates};
 use wikitext_simplified::{WikitextSimplifiedNode, wikitext_util::parse_wiki_text_2};
 
+mod api_index;
+mod build_date;
+mod check;
+mod cli;
+mod color;
+
+mod config;
+mod content_classes;
+mod content_hash;
+use config::{Config, UnknownCodeLanguage};
+
+mod emoji;
+mod faq;
+mod feed;
+mod footnotes;
+mod glossary;
+mod heading_numbers;
+mod inclusion;
+
+mod frontmatter;
+use frontmatter::{Asset, Frontmatter};
+
 mod page_context;
+mod param_docs;
+mod parser_functions;
+mod profile;
 use page_context::PageContext;
 
+mod site;
+use site::Site;
+mod slug;
+
+mod sidebar;
+mod sitemap;
 mod syntax;
+mod tailwind_cache;
 mod template;
+mod template_data;
+mod toc;
+mod wikitext;
+
+mod links;
+mod llms;
+mod math;
+mod mathml;
+mod media;
+mod namespaces;
+mod output_diff;
+mod print;
+mod recent_changes;
+mod relative_links;
+mod robots;
+mod search;
+mod xml;
+
+mod link_report;
+use link_report::LinkReport;
+mod warnings;
+
+#[cfg(test)]
+mod test_util;
 
 const WIKI_DIRECTORY: &str = "wiki";
+/// The name of the site, shown in page titles
+const SITE_NAME: &str = "JC2-MP Documentation";
+/// The scheme and host the site is served from, unless `base_url` is configured
+const BASE_URL: &str = "https://jc-mp.com";
+/// The file in the output root describing how the site was built
+const BUILD_MANIFEST: &str = "build.json";
+/// The directory whose contents are copied verbatim into the output
+const STATIC_DIRECTORY: &str = "static";
+/// The script that collapses long tables, relative to the static directory
+const COLLAPSE_TABLES_SCRIPT: &str = "js/tables.js";
+/// The helper template that renders another template alongside its source
+const TEMPLATE_DEMO: &str = "TemplateDemo";
+/// The built-in template that colors its text (`{{color|red|text}}`)
+const COLOR_TEMPLATE: &str = "color";
+/// The built-in template that marks a page as a stub
+const STUB_TEMPLATE: &str = "stub";
+/// The built-in templates that move the content after them below any floated images
+/// (`{{Clear}}`, or `{{Clear|left}}` for only those on one side)
+const CLEAR_TEMPLATES: &[&str] = &["clear", "-"];
+/// The built-in templates that list the current page's direct subpages
+const CHILD_PAGES_TEMPLATES: &[&str] = &["childpages", "subpages"];
+/// The hatnote templates (e.g. `{{Main|Page}}`), with their labels for one target and
+/// for several
+const HATNOTES: &[(&str, &str, &str)] = &[
+    ("main", "Main article", "Main articles"),
+    ("see also", "See also", "See also"),
+    ("seealso", "See also", "See also"),
+];
+/// The admonition templates (e.g. `{{Warning|text}}`), with the label, icon and classes
+/// of their callout boxes
+const ADMONITIONS: &[(&str, &str, &str, &str)] = &[
+    (
+        "note",
+        "Note",
+        "ℹ️",
+        "border-blue-400 bg-blue-50 text-blue-900",
+    ),
+    (
+        "tip",
+        "Tip",
+        "💡",
+        "border-green-400 bg-green-50 text-green-900",
+    ),
+    (
+        "warning",
+        "Warning",
+        "⚠️",
+        "border-red-400 bg-red-50 text-red-900",
+    ),
+];
+/// The built-in template that describes a parameter on a template's documentation page
+/// (`{{Param|name|description|required=yes}}`)
+const PARAM_TEMPLATE: &str = "param";
+/// The built-in template that renders the parameters described so far as a table
+const PARAMS_TEMPLATE: &str = "params";
+/// The admonition template that takes its type as its first parameter
+/// (`{{Admonition|note|text}}`). Types that aren't in [`ADMONITIONS`] get a neutral box.
+const ADMONITION_TEMPLATE: &str = "admonition";
+
+/// Whether `name` is one of the templates that are converted directly rather than
+/// loaded from the wiki
+pub fn is_builtin_template(name: &str) -> bool {
+    hatnote_labels(name).is_some()
+        || name.trim().eq_ignore_ascii_case(TEMPLATE_DEMO)
+        || name.trim().eq_ignore_ascii_case(COLOR_TEMPLATE)
+        || name.trim().eq_ignore_ascii_case(STUB_TEMPLATE)
+        || is_child_pages_template(name)
+        || is_admonition_template(name)
+        || is_param_docs_template(name)
+        || is_clear_template(name)
+}
 
 static SYNTAX_HIGHLIGHTER: OnceLock<syntax::SyntaxHighlighter> = OnceLock::new();
 
@@ -24,23 +152,186 @@ struct GeneratedPages {
     pages_by_directory: BTreeMap<String, BTreeSet<String>>,
 }
 
+/// A page found in the wiki directory, but not yet read
+struct PageFile {
+    path: PathBuf,
+    /// The directory containing the page, relative to the wiki root (e.g. `Lua/Server`)
+    relative_dir: String,
+    /// The file name of the page, without the extension
+    page_name: String,
+    /// The full title of the page (e.g. `Lua/Server/Vehicle`)
+    title: String,
+}
+
+/// A page read from the wiki directory and parsed, but not yet rendered
+struct SourcePage {
+    input_path: PathBuf,
+    /// The directory containing the page, relative to the wiki root (e.g. `Lua/Server`)
+    relative_dir: String,
+    /// The file name of the page, without the extension
+    page_name: String,
+    /// The full title of the page (e.g. `Lua/Server/Vehicle`)
+    title: String,
+    frontmatter: Frontmatter,
+    /// Whether the page's headings are listed, from its behavior switches
+    toc_switch: toc::TocSwitch,
+    /// The wikitext of the page as written, without its frontmatter
+    source: String,
+    simplified: Vec<WikitextSimplifiedNode>,
+}
+impl SourcePage {
+    fn is_redirect(&self) -> bool {
+        matches!(
+            self.simplified.as_slice(),
+            [WikitextSimplifiedNode::Redirect { .. }]
+        )
+    }
+}
+
 fn main() -> anyhow::Result<()> {
-    let output_dir = Path::new("output");
-    let _ = fs::remove_dir_all(output_dir);
+    let args = cli::Args::parse(std::env::args().skip(1))?;
+    let mut config = Config::load(Path::new(config::CONFIG_FILE))?;
+    if let Some(main_page) = &args.main_page {
+        config.main_page = main_page.clone();
+    }
+    if args.no_js {
+        config.scripts = false;
+    }
+
+    let targets = args.targets();
+    let site_target = targets
+        .iter()
+        .find(|target| target.kind == cli::TargetKind::Site);
+
+    // Rebuild a single page in place, leaving the rest of the previous build untouched
+    if let Some(title) = &args.page {
+        let output_dir = site_target.map_or(Path::new(cli::OUTPUT_DIRECTORY), |target| {
+            target.dir.as_path()
+        });
+        let site = generate_single_page(
+            config,
+            &args,
+            Path::new(WIKI_DIRECTORY),
+            &output_dir.join(WIKI_DIRECTORY),
+            title,
+        )?;
+        return finish_build(&site, &args);
+    }
+
+    // The previous build is compared against once it's been replaced
+    let previous_builds = if args.diff {
+        targets
+            .iter()
+            .map(|target| output_diff::Snapshot::take(&target.dir))
+            .collect::<anyhow::Result<Vec<_>>>()?
+    } else {
+        vec![]
+    };
+    let print_diff = || -> anyhow::Result<()> {
+        for (target, before) in targets.iter().zip(&previous_builds) {
+            let after = output_diff::Snapshot::take(&target.dir)?;
+            output_diff::OutputDiff::new(before, &after).print(&target.dir);
+        }
+        Ok(())
+    };
+
+    for target in &targets {
+        let _ = fs::remove_dir_all(&target.dir);
+    }
+    let Some(output_dir) = site_target.map(|target| target.dir.as_path()) else {
+        generate_targets(config, &args, Path::new(WIKI_DIRECTORY), &targets)?;
+        return print_diff();
+    };
     fs::create_dir_all(output_dir)?;
 
     // Copy the contents of the `static` folder into the output directory
-    copy_files_recursively(Path::new("static"), output_dir)?;
+    copy_files_recursively(Path::new(STATIC_DIRECTORY), output_dir)?;
 
     // Initialize Tailwind and generate CSS
-    let tailwind =
-        paxhtml_tailwind::Tailwind::download(paxhtml_tailwind::RECOMMENDED_VERSION, true)?;
-    let tailwind_css = tailwind.generate_from_file(Path::new("src/tailwind.css"))?;
+    let tailwind_input = Path::new("src/tailwind.css");
+    let tailwind_cache = tailwind_cache::cache_dir(args.tailwind_cache.as_deref());
+    let tailwind_css = tailwind_cache::tailwind(
+        tailwind_cache.as_deref(),
+        paxhtml_tailwind::RECOMMENDED_VERSION,
+    )?
+    .generate_from_file(tailwind_input)?;
     fs::create_dir_all(output_dir.join("style"))?;
     fs::write(output_dir.join("style/tailwind.css"), tailwind_css)?;
 
-    // Generate wiki
-    generate_wiki(Path::new(WIKI_DIRECTORY), &output_dir.join(WIKI_DIRECTORY))?;
+    // Generate wiki, along with any other targets
+    let site = generate_targets(config, &args, Path::new(WIKI_DIRECTORY), &targets)?
+        .expect("a site target is being built");
+    LinkReport::new(&site).write(args.report_format)?;
+    if site.config.robots_txt {
+        robots::write(&site.config, output_dir)?;
+    }
+    print_diff()?;
+
+    // The check can add warnings of its own, so they are reported afterwards
+    let check_result = if args.check {
+        for issue in check::link_casing(&site) {
+            site.warnings.warn(issue);
+        }
+        check::run(output_dir, &site.warnings)
+    } else {
+        Ok(())
+    };
+    finish_build(&site, &args)?;
+    check_result
+}
+
+/// Report the warnings gathered while building the site, failing if they should fail
+/// the build
+fn finish_build(site: &Site, args: &cli::Args) -> anyhow::Result<()> {
+    if let Some(profile) = &site.profile {
+        profile.print(profile::PROFILE_TOP_N);
+    }
+    let warnings = site.warnings.finish(args.deny_warnings);
+    if !args.quiet {
+        let site::BuildSummary {
+            pages,
+            redirects,
+            templates,
+        } = site.summary;
+        eprintln!(
+            "Built {pages} page(s) and {redirects} redirect(s) using {templates} template(s), with {} warning(s)",
+            site.warnings.len()
+        );
+    }
+    warnings?;
+
+    if args.deny_broken_links {
+        let broken_links = LinkReport::new(site)
+            .issues
+            .iter()
+            .filter(|issue| issue.issue == link_report::IssueKind::BrokenLink)
+            .count();
+        if broken_links > 0 {
+            anyhow::bail!(
+                "{broken_links} broken link(s) were found and --deny-broken-links is set"
+            );
+        }
+    }
+
+    if args.strict {
+        let dangling_redirects = site.dangling_redirects();
+        if !dangling_redirects.is_empty() {
+            anyhow::bail!(
+                "{} redirect(s) point to missing pages and --strict is set",
+                dangling_redirects.len()
+            );
+        }
+        if !site.oversized_media.is_empty() {
+            anyhow::bail!(
+                "{} media file(s) are over the size limit and --strict is set",
+                site.oversized_media.len()
+            );
+        }
+        let oversized_pages = site.oversized_pages.borrow().len();
+        if oversized_pages > 0 {
+            anyhow::bail!("{oversized_pages} page(s) are over the size budget and --strict is set");
+        }
+    }
 
     Ok(())
 }
@@ -62,7 +353,11 @@ fn copy_files_recursively(src: &Path, dst: &Path) -> std::io::Result<()> {
     Ok(())
 }
 
-fn generate_missing_index_pages(dst_root: &Path, generated: &GeneratedPages) -> anyhow::Result<()> {
+fn generate_missing_index_pages(
+    site: &Site,
+    dst_root: &Path,
+    generated: &GeneratedPages,
+) -> anyhow::Result<()> {
     // Collect all directory paths that need index pages
     let mut dirs_needing_index = BTreeSet::new();
 
@@ -104,13 +399,14 @@ fn generate_missing_index_pages(dst_root: &Path, generated: &GeneratedPages) ->
             parts[..parts.len() - 1].join("/")
         };
 
-        generate_index_page(dst_root, &parent_path, dir_name, generated)?;
+        generate_index_page(site, dst_root, &parent_path, dir_name, generated)?;
     }
 
     Ok(())
 }
 
 fn generate_index_page(
+    site: &Site,
     dst_root: &Path,
     parent_path: &str,
     dir_name: &str,
@@ -163,7 +459,7 @@ fn generate_index_page(
         let link_path = format!("{}/{}", full_path, child);
         items.push(paxhtml::html! {
             <li class="ml-4">
-                <a class="text-blue-600 hover:text-blue-800 hover:underline" href={page_title_to_route_path(&link_path).url_path()}>
+                <a class={site.config.link_class.clone()} href={page_url(&site.config, &link_path)}>
                     {display_name}
                 </a>
             </li>
@@ -174,15 +470,23 @@ fn generate_index_page(
         <ul class="list-disc list-inside">#{items}</ul>
     };
 
-    let document = layout(&title, content);
+    let document = layout(site, &title, vec![], content, &Default::default());
 
     // Write the document
-    let route_path = page_title_to_route_path(&full_path);
-    document.write_to_route(dst_root, route_path.clone())?;
+    let route_path = page_title_to_route_path(&site.config, &full_path);
+    write_document(&site.config, document, dst_root, route_path.clone())?;
+    site.sitemap_urls
+        .borrow_mut()
+        .insert(route_url(&site.config, &route_path));
+
+    // With clean URLs, the page is already at full_path/index.html
+    if site.config.clean_urls {
+        return Ok(());
+    }
 
     // Also create a redirect from full_path/index.html to full_path.html
     // This allows both /category and /category/ to work
-    let redirect_doc = redirect(&route_path.url_path());
+    let redirect_doc = redirect(&site.config, &route_url(&site.config, &route_path));
     let redirect_route = paxhtml::RoutePath::new(
         route_path
             .url_path()
@@ -191,19 +495,69 @@ fn generate_index_page(
             .split('/'),
         Some("index.html".to_string()),
     );
-    redirect_doc.write_to_route(dst_root, redirect_route)?;
+    write_document(&site.config, redirect_doc, dst_root, redirect_route)?;
 
     Ok(())
 }
 
-fn generate_wiki(src: &Path, dst: &Path) -> anyhow::Result<()> {
-    fs::create_dir_all(dst)?;
+/// Build each of `targets` from the wiki in `src`, reading and parsing its pages only
+/// once. Returns the site if one of the targets is the site.
+fn generate_targets(
+    config: Config,
+    args: &cli::Args,
+    src: &Path,
+    targets: &[cli::Target],
+) -> anyhow::Result<Option<Site>> {
+    let pwt_configuration = wikitext_simplified::wikitext_util::wikipedia_pwt_configuration();
+    let pages = read_wiki_pages(src, &config, &pwt_configuration)?;
+
+    for target in targets
+        .iter()
+        .filter(|target| target.kind == cli::TargetKind::Json)
+    {
+        write_pages_json(&target.dir, &pages, args.json_format)?;
+    }
+    targets
+        .iter()
+        .find(|target| target.kind == cli::TargetKind::Site)
+        .map(|target| {
+            emit_wiki(
+                config,
+                args,
+                src,
+                &target.dir.join(WIKI_DIRECTORY),
+                &pwt_configuration,
+                pages,
+            )
+        })
+        .transpose()
+}
 
+/// Generate the wiki, returning the site with the warnings and links gathered along the way
+#[cfg(test)]
+fn generate_wiki(config: Config, args: &cli::Args, src: &Path, dst: &Path) -> anyhow::Result<Site> {
     let pwt_configuration = wikitext_simplified::wikitext_util::wikipedia_pwt_configuration();
-    let loader = template::FileSystemLoader::new(src)?;
-    let mut templates = Templates::new(loader, &pwt_configuration)?;
+    let pages = read_wiki_pages(src, &config, &pwt_configuration)?;
+    emit_wiki(config, args, src, dst, &pwt_configuration, pages)
+}
+
+/// Generate the wiki from its already parsed `pages`
+fn emit_wiki(
+    config: Config,
+    args: &cli::Args,
+    src: &Path,
+    dst: &Path,
+    pwt_configuration: &parse_wiki_text_2::Configuration,
+    mut pages: Vec<SourcePage>,
+) -> anyhow::Result<Site> {
+    fs::create_dir_all(dst)?;
 
-    // Initialize syntax highlighter
+    let loader = template::FileSystemLoader::new(src, &config.source_extensions)?;
+    let mut templates = Templates::new(loader, pwt_configuration)?;
+    templates.set_check_parameters(args.check);
+
+    // Initialize the syntax highlighter up front, so that loading it doesn't delay the
+    // first page with a code block
     let highlighter = SYNTAX_HIGHLIGHTER.get_or_init(syntax::SyntaxHighlighter::default);
 
     // Generate syntax highlighting CSS
@@ -212,39 +566,408 @@ fn generate_wiki(src: &Path, dst: &Path) -> anyhow::Result<()> {
     fs::create_dir_all(output_dir.join("style"))?;
     fs::write(output_dir.join("style/syntax.css"), syntax_css)?;
 
+    check_route_collisions(&pages)?;
+    let aliases = page_aliases(&pages)?;
+    // A site without any content is almost certainly pointed at the wrong directory
+    if pages.iter().all(|page| page.is_redirect()) {
+        anyhow::bail!("No content pages were found in {}", src.display());
+    }
+    if config.emoji_shortcodes {
+        for page in &mut pages {
+            emoji::expand_in_nodes(&mut page.simplified);
+        }
+    }
+    if let Some(glossary_file) = &config.glossary_file {
+        let glossary = glossary::Glossary::load(Path::new(glossary_file))?;
+        for page in &mut pages {
+            glossary.apply(&mut page.simplified);
+        }
+    }
+    let media_src = PathBuf::from(&config.media_directory);
+    let mut site = Site::new(
+        config,
+        pages
+            .iter()
+            .filter(|page| !page.is_redirect())
+            .map(|page| page.title.clone())
+            .collect(),
+        pages
+            .iter()
+            .filter_map(|page| match page.simplified.as_slice() {
+                [WikitextSimplifiedNode::Redirect { target }] => {
+                    Some((page.title.clone(), target.clone()))
+                }
+                _ => None,
+            })
+            .chain(aliases.iter().cloned())
+            .collect(),
+    );
+
+    for (title, target) in site.dangling_redirects() {
+        site.warnings
+            .warn(format!("Redirect {title} points to missing page {target}"));
+    }
+    if args.check {
+        for page in &pages {
+            for (marker, line) in check::todo_markers(&site.config, &page.simplified) {
+                site.warnings
+                    .warn(format!("{}: {marker} left in content: {line}", page.title));
+            }
+        }
+    }
+
+    let media = media::copy_media(
+        &site.config,
+        &site.warnings,
+        &media_src,
+        &output_dir.join(media::MEDIA_DIRECTORY),
+    )?;
+    for (name, size) in &media.oversized {
+        site.warnings.warn(format!(
+            "Media file {name} is {size} bytes, over the limit of {} bytes; consider optimizing it",
+            site.config.max_media_bytes.unwrap_or_default()
+        ));
+    }
+    site.thumbnails = media.thumbnails;
+    site.after_content = load_after_content(&site.config)?;
+    site.oversized_media = media.oversized;
+    site.stubs = pages
+        .iter()
+        .filter(|page| is_stub(&site.config, page))
+        .map(|page| page.title.clone())
+        .collect();
+
+    if args.profile {
+        site.profile = Some(profile::Profile::default());
+    }
+
     let mut generated = GeneratedPages::default();
-    generate_wiki_folder(
-        &mut templates,
+    for page in &pages {
+        generate_wiki_page(
+            &mut templates,
+            pwt_configuration,
+            &site,
+            dst,
+            output_dir,
+            page,
+            args.json_format,
+        )?;
+
+        // Track this generated page
+        generated
+            .pages_by_directory
+            .entry(page.relative_dir.clone())
+            .or_default()
+            .insert(page.page_name.clone());
+    }
+
+    // Categories without a page of their own get one listing their pages
+    for (category, members) in site.category_members.borrow().iter() {
+        let title = format!("{}:{category}", namespaces::CATEGORY_NAMESPACE);
+        if namespaces::split(&site.config, &title).is_none() || site.link_target_exists(&title) {
+            continue;
+        }
+        let document = layout(
+            &site,
+            &title,
+            vec![],
+            namespaces::render_category(&site, members),
+            &Default::default(),
+        );
+        let route_path = page_title_to_route_path(&site.config, &title);
+        write_document(&site.config, document, output_dir, route_path.clone())?;
+        site.sitemap_urls
+            .borrow_mut()
+            .insert(route_url(&site.config, &route_path));
+        let path = namespaces::title_path(&site.config, &title);
+        let (directory, page_name) = path.rsplit_once('/').unwrap();
+        generated
+            .pages_by_directory
+            .entry(directory.to_string())
+            .or_default()
+            .insert(page_name.to_string());
+    }
+
+    // The feed lists the same changes, so they're only looked up once
+    let changes = recent_changes::recent_changes(
         src,
-        dst,
-        dst,
-        &pwt_configuration,
-        "",
-        &mut generated,
+        pages
+            .iter()
+            .filter(|page| !page.is_redirect())
+            .map(|page| (page.title.as_str(), page.input_path.as_path())),
+        site.config.recent_changes_count.max(site.config.feed_count),
+    );
+    if site.config.feed_count > 0 {
+        let entries = changes
+            .iter()
+            .take(site.config.feed_count)
+            .map(|(title, time)| feed::FeedEntry {
+                title,
+                url: page_url(&site.config, title),
+                updated: *time,
+                summary: pages
+                    .iter()
+                    .find(|page| &page.title == title)
+                    .map(|page| feed::summary(&wikitext::plain_text(&page.simplified)))
+                    .unwrap_or_default(),
+            })
+            .collect::<Vec<_>>();
+        feed::write(SITE_NAME, site.config.site_url(), &entries, output_dir)?;
+    }
+    let recent_changes = &changes[..changes.len().min(site.config.recent_changes_count)];
+    let document = layout(
+        &site,
+        recent_changes::RECENT_CHANGES,
+        vec![],
+        recent_changes::render(&site, recent_changes),
+        &Default::default(),
+    );
+    write_document(
+        &site.config,
+        document,
+        output_dir,
+        page_title_to_route_path(&site.config, recent_changes::RECENT_CHANGES),
+    )?;
+    for (alias, title) in &aliases {
+        let document = redirect(&site.config, &page_url(&site.config, title));
+        write_document(
+            &site.config,
+            document,
+            output_dir,
+            page_title_to_route_path(&site.config, alias),
+        )?;
+        let (directory, page_name) = alias.rsplit_once('/').unwrap_or(("", alias));
+        generated
+            .pages_by_directory
+            .entry(slug::title_slug(directory))
+            .or_default()
+            .insert(slug::title_slug(page_name));
+    }
+
+    let (directory, page_name) = recent_changes::RECENT_CHANGES.rsplit_once('/').unwrap();
+    generated
+        .pages_by_directory
+        .entry(directory.to_string())
+        .or_default()
+        .insert(page_name.to_string());
+
+    let api_entries = pages
+        .iter()
+        .flat_map(|page| {
+            api_index::extract(
+                &page.title,
+                &page.simplified,
+                &site.config.api_index_columns,
+            )
+        })
+        .collect::<Vec<_>>();
+    let document = layout(
+        &site,
+        api_index::API_INDEX,
+        vec![],
+        api_index::render(&site, &api_entries),
+        &Default::default(),
+    );
+    write_document(
+        &site.config,
+        document,
+        output_dir,
+        page_title_to_route_path(&site.config, api_index::API_INDEX),
     )?;
+    let (directory, page_name) = api_index::API_INDEX.rsplit_once('/').unwrap();
+    generated
+        .pages_by_directory
+        .entry(directory.to_string())
+        .or_default()
+        .insert(page_name.to_string());
+
+    if site.config.llms_txt {
+        let mut digest_pages = pages
+            .iter()
+            .filter(|page| !page.is_redirect())
+            .map(|page| llms::DigestPage {
+                title: &page.title,
+                url: canonical_url(&site.config, &page.title)
+                    .unwrap_or_else(|| page_url(&site.config, &page.title)),
+                text: wikitext::plain_text(&page.simplified),
+            })
+            .collect::<Vec<_>>();
+        digest_pages.sort_by(|a, b| a.title.cmp(b.title));
+        llms::write(SITE_NAME, &digest_pages, output_dir)?;
+    }
+
+    if site.config.print_document {
+        print::write(&site, SITE_NAME, output_dir)?;
+    }
 
     // Generate missing index pages
-    generate_missing_index_pages(output_dir, &generated)?;
+    generate_missing_index_pages(&site, output_dir, &generated)?;
+
+    sitemap::write(
+        site.config.site_url(),
+        &site.sitemap_urls.borrow(),
+        output_dir,
+    )?;
 
-    redirect(&page_title_to_route_path("Main_Page").url_path())
-        .write_to_route(dst, paxhtml::RoutePath::new([], "index.html".to_string()))?;
+    let search_entries = pages
+        .iter()
+        .filter(|page| !page.is_redirect())
+        .map(|page| {
+            let entry = search::SearchEntry {
+                title: page.title.clone(),
+                text: search::page_text(&page.simplified),
+            };
+            let url = page_url(&site.config, &page.title);
+            (url.trim_start_matches('/').to_string(), entry)
+        })
+        .collect();
+    search::write(&search_entries, output_dir)?;
 
-    Ok(())
+    let document = redirect(
+        &site.config,
+        &page_url(&site.config, &site.config.main_page),
+    );
+    write_document(
+        &site.config,
+        document,
+        output_dir,
+        paxhtml::RoutePath::new([WIKI_DIRECTORY], "index.html".to_string()),
+    )?;
+
+    let manifest = serde_json::json!({
+        "build_date": site.build_date.to_string(),
+        "build_profile": site.config.build_profile,
+        "pages": site.page_titles.len(),
+    });
+    fs::write(
+        output_dir.join(BUILD_MANIFEST),
+        serde_json::to_string_pretty(&manifest)?,
+    )?;
+
+    let redirects = pages.iter().filter(|page| page.is_redirect()).count();
+    site.summary = site::BuildSummary {
+        pages: pages.len() - redirects,
+        redirects,
+        templates: templates.loaded_count(),
+    };
+
+    Ok(site)
 }
 
-fn generate_wiki_folder(
-    templates: &mut Templates,
+/// Generate only the page with the given title, returning the site with the warnings
+/// gathered along the way.
+///
+/// The other pages are only skimmed for their titles and redirects, so that links can
+/// still be resolved.
+fn generate_single_page(
+    config: Config,
+    args: &cli::Args,
     src: &Path,
-    dst_root: &Path,
     dst: &Path,
-    pwt_configuration: &parse_wiki_text_2::Configuration,
-    relative_path: &str,
-    generated: &mut GeneratedPages,
-) -> anyhow::Result<()> {
+    title: &str,
+) -> anyhow::Result<Site> {
     fs::create_dir_all(dst)?;
+    let output_dir = dst.parent().unwrap();
+
+    let pwt_configuration = wikitext_simplified::wikitext_util::wikipedia_pwt_configuration();
+    let loader = template::FileSystemLoader::new(src, &config.source_extensions)?;
+    let mut templates = Templates::new(loader, &pwt_configuration)?;
+    templates.set_check_parameters(args.check);
+    SYNTAX_HIGHLIGHTER.get_or_init(syntax::SyntaxHighlighter::default);
+
+    let mut files = vec![];
+    discover_wiki_folder(src, &config, "", &mut files)?;
 
-    let files = fs::read_dir(src)?;
+    let mut page_titles = BTreeSet::new();
+    let mut redirects = BTreeMap::new();
+    let mut page_file = None;
     for file in files {
+        if let Some(target) = redirect_target(&fs::read_to_string(&file.path)?) {
+            redirects.insert(file.title.clone(), target);
+        } else {
+            page_titles.insert(file.title.clone());
+        }
+        if site::normalize_title(&file.title) == site::normalize_title(title) {
+            page_file = Some(file);
+        }
+    }
+    let Some(page_file) = page_file else {
+        anyhow::bail!("No page titled {title} in {}", src.display());
+    };
+
+    let mut page = read_source_page(page_file, &pwt_configuration)?;
+    if config.emoji_shortcodes {
+        emoji::expand_in_nodes(&mut page.simplified);
+    }
+    if let Some(glossary_file) = &config.glossary_file {
+        glossary::Glossary::load(Path::new(glossary_file))?.apply(&mut page.simplified);
+    }
+
+    let mut site = Site::new(config, page_titles, redirects);
+    if args.profile {
+        site.profile = Some(profile::Profile::default());
+    }
+    site.after_content = load_after_content(&site.config)?;
+    // The media is left as the last full build copied it
+    site.thumbnails = media::existing_thumbnails(&output_dir.join(media::MEDIA_DIRECTORY));
+    if is_stub(&site.config, &page) {
+        site.stubs.insert(page.title.clone());
+    }
+    generate_wiki_page(
+        &mut templates,
+        &pwt_configuration,
+        &site,
+        dst,
+        output_dir,
+        &page,
+        args.json_format,
+    )?;
+
+    let redirects = usize::from(page.is_redirect());
+    site.summary = site::BuildSummary {
+        pages: 1 - redirects,
+        redirects,
+        templates: templates.loaded_count(),
+    };
+
+    Ok(site)
+}
+
+/// Read and parse every page in `src`, so that the whole wiki is known before anything
+/// is written. This is the work shared by every target.
+fn read_wiki_pages(
+    src: &Path,
+    config: &Config,
+    pwt_configuration: &parse_wiki_text_2::Configuration,
+) -> anyhow::Result<Vec<SourcePage>> {
+    let mut files = vec![];
+    discover_wiki_folder(src, config, "", &mut files)?;
+    files
+        .into_iter()
+        .map(|file| read_source_page(file, pwt_configuration))
+        .collect()
+}
+
+/// Write the simplified wikitext of each of `pages` as JSON to `dst`
+fn write_pages_json(
+    dst: &Path,
+    pages: &[SourcePage],
+    json_format: cli::JsonFormat,
+) -> anyhow::Result<()> {
+    for page in pages {
+        write_page_json(dst, page, json_format)?;
+    }
+    Ok(())
+}
+
+/// Find every page in `src` with one of the source extensions, without reading them
+fn discover_wiki_folder(
+    src: &Path,
+    config: &Config,
+    relative_path: &str,
+    files: &mut Vec<PageFile>,
+) -> anyhow::Result<()> {
+    for file in fs::read_dir(src)? {
         let file = file?;
         let path = file.path();
 
@@ -255,158 +978,624 @@ fn generate_wiki_folder(
             } else {
                 format!("{}/{}", relative_path, dir_name)
             };
-            generate_wiki_folder(
-                templates,
-                &path,
-                dst_root,
-                &dst.join(path.file_name().unwrap()),
-                pwt_configuration,
-                &new_relative_path,
-                generated,
-            )?;
+            discover_wiki_folder(&path, config, &new_relative_path, files)?;
+            continue;
+        }
+        if !template::is_source_file(&path, &config.source_extensions) {
             continue;
         }
-        let content = fs::read_to_string(&path)?;
-        let simplified =
-            wikitext_simplified::parse_and_simplify_wikitext(&content, pwt_configuration).map_err(
-                |e| {
-                    anyhow::anyhow!(
-                        "Failed to parse and simplify wiki file {}: {e:?}",
-                        path.display()
-                    )
-                },
-            )?;
-
-        let output_json = dst.join(path.with_extension("json").file_name().unwrap());
-        fs::write(&output_json, serde_json::to_string_pretty(&simplified)?)?;
-
-        let output_html = output_json.with_extension("html");
-        let output_html_rel = output_html.strip_prefix(dst_root).unwrap();
-
-        let route_path = paxhtml::RoutePath::new(
-            output_html_rel.parent().iter().flat_map(|p| {
-                p.components().filter_map(|comp| match comp {
-                    std::path::Component::Normal(name) => name.to_str(),
-                    _ => None,
-                })
-            }),
-            output_html_rel
-                .file_name()
-                .and_then(|s| s.to_str())
-                .map(|s| s.to_string()),
-        );
-
-        let document = if let [WikitextSimplifiedNode::Redirect { target }] = simplified.as_slice()
-        {
-            redirect(&page_title_to_route_path(target).url_path())
-        } else {
-            let sub_page_name = path
-                .with_extension("")
-                .file_name()
-                .unwrap()
-                .to_string_lossy()
-                .to_string();
-
-            let page_context = PageContext {
-                input_path: path.clone(),
-                title: output_html_rel
-                    .with_extension("")
-                    .to_str()
-                    .map(|s| s.to_string())
-                    .unwrap()
-                    .replace("\\", "/")
-                    .replace("_", " "),
-                route_path: route_path.clone(),
-                sub_page_name,
-            };
-
-            layout(
-                &page_context.title,
-                paxhtml::Element::from_iter(simplified.iter().map(|node| {
-                    convert_wikitext_to_html(templates, pwt_configuration, node, &page_context)
-                })),
-            )
-        };
-
-        document.write_to_route(dst_root, route_path)?;
 
-        // Track this generated page
         let page_name = path
             .with_extension("")
             .file_name()
             .unwrap()
             .to_string_lossy()
             .to_string();
-        generated
-            .pages_by_directory
-            .entry(relative_path.to_string())
-            .or_default()
-            .insert(page_name);
+        let path_title = if relative_path.is_empty() {
+            page_name.clone()
+        } else {
+            format!("{}/{}", relative_path, page_name)
+        };
+        let title = namespaces::title_from_path(config, &path_title).replace("_", " ");
+
+        files.push(PageFile {
+            path,
+            relative_dir: relative_path.to_string(),
+            page_name,
+            title,
+        });
     }
 
     Ok(())
 }
 
-fn layout(title: &str, inner: paxhtml::Element) -> paxhtml::Document {
-    let mut links = vec![(
-        "Home",
-        paxhtml::RoutePath::new(
-            std::iter::once(WIKI_DIRECTORY),
-            Some("Main_Page.html".to_string()),
-        ),
-    )];
+/// Read and parse a page found by [`discover_wiki_folder`]
+fn read_source_page(
+    file: PageFile,
+    pwt_configuration: &parse_wiki_text_2::Configuration,
+) -> anyhow::Result<SourcePage> {
+    let path = file.path;
+    let content = fs::read_to_string(&path)?;
+    let (frontmatter, content) = frontmatter::split(&content)
+        .map_err(|e| anyhow::anyhow!("Invalid frontmatter in {}: {e}", path.display()))?;
+    let source = content.to_string();
+    let (content, toc_switch) = toc::TocSwitch::extract(&inclusion::rendered(content));
+    let simplified = wikitext_simplified::parse_and_simplify_wikitext(&content, pwt_configuration)
+        .map_err(|e| {
+            anyhow::anyhow!(
+                "Failed to parse and simplify wiki file {}: {e:?}",
+                path.display()
+            )
+        })?;
+
+    Ok(SourcePage {
+        input_path: path,
+        relative_dir: file.relative_dir,
+        page_name: file.page_name,
+        title: file.title,
+        frontmatter,
+        toc_switch,
+        source,
+        simplified,
+    })
+}
 
-    if title != "Main Page" {
-        let mut components = vec![];
-        for component in title.split('/') {
-            let route_path = paxhtml::RoutePath::new(
-                std::iter::once(WIKI_DIRECTORY).chain(components.iter().copied()),
-                Some(format!("{}.html", component.replace(" ", "_"))),
-            );
-            links.push((component, route_path));
-            components.push(component);
-        }
+/// The target of a `#REDIRECT [[Target]]` page, found without parsing the page
+fn redirect_target(content: &str) -> Option<String> {
+    let content = content.trim_start();
+    let directive = content.get(.."#REDIRECT".len())?;
+    if !directive.eq_ignore_ascii_case("#REDIRECT") {
+        return None;
     }
+    let (_, target) = content.split_once("[[")?;
+    let (target, _) = target.split_once("]]")?;
+    let target = target.split('|').next().unwrap_or_default().trim();
+    Some(target.replace("_", " "))
+}
 
-    let mut breadcrumbs = vec![];
-    for (idx, (component, route_path)) in links.into_iter().enumerate() {
-        if idx > 0 {
-            breadcrumbs.push(paxhtml::html! { <span class="text-gray-400">" / "</span> });
+/// The aliases given in the frontmatter of `pages`, as `(alias, page title)`. It's an
+/// error for an alias to have the same route as a page or another alias.
+fn page_aliases(pages: &[SourcePage]) -> anyhow::Result<Vec<(String, String)>> {
+    let route = |title: &str| slug::title_slug(title).to_lowercase();
+    let mut titles_by_route = pages
+        .iter()
+        .map(|page| (route(&page.title), page.title.as_str()))
+        .collect::<BTreeMap<_, _>>();
+
+    let mut aliases = vec![];
+    for page in pages {
+        for alias in &page.frontmatter.aliases {
+            if let Some(existing) = titles_by_route.insert(route(alias), page.title.as_str()) {
+                anyhow::bail!(
+                    "The alias {alias} of {} has the same route as {existing}",
+                    page.title
+                );
+            }
+            aliases.push((alias.clone(), page.title.clone()));
         }
-        breadcrumbs.push(paxhtml::html! { <a class="text-blue-600 hover:text-blue-800 hover:underline" href={route_path.url_path()}>{component}</a> });
     }
+    Ok(aliases)
+}
 
-    paxhtml::Document::new([
-        paxhtml::builder::doctype(["html".into()]),
-        paxhtml::html! {
-            <html lang="en">
-            <head>
-                <meta charset="UTF-8" />
-                <meta name="viewport" content="width=device-width, initial-scale=1.0" />
-                <title>{format!("JC2-MP Documentation - {title}")}</title>
-                <link href="/style/tailwind.css" rel="stylesheet" />
-                <link href="/style/syntax.css" rel="stylesheet" />
-            </head>
-            <body class="bg-gray-100">
-                <nav class="bg-gray-900 text-white mb-4">
-                    <div class="max-w-7xl mx-auto px-4 sm:px-6 lg:px-8">
-                        <div class="flex items-center justify-between h-16">
-                            <div class="flex items-center">
-                                <a class="text-xl font-semibold" href="/wiki">"Just Cause 2: Multiplayer"</a>
-                            </div>
+/// Ensure that no two source files map to the same output route, as the routes are
+/// case-insensitive and extensionless, and one would otherwise silently overwrite the other
+fn check_route_collisions(pages: &[SourcePage]) -> anyhow::Result<()> {
+    let mut sources_by_route: BTreeMap<String, Vec<&Path>> = BTreeMap::new();
+    for page in pages {
+        sources_by_route
+            .entry(slug::title_slug(&page.title).to_lowercase())
+            .or_default()
+            .push(&page.input_path);
+    }
+
+    let collisions = sources_by_route
+        .iter()
+        .filter(|(_, sources)| sources.len() > 1)
+        .map(|(route, sources)| {
+            let mut sources = sources
+                .iter()
+                .map(|p| p.display().to_string())
+                .collect::<Vec<_>>();
+            sources.sort();
+            format!("{route}: {}", sources.join(", "))
+        })
+        .collect::<Vec<_>>();
+
+    if !collisions.is_empty() {
+        anyhow::bail!(
+            "Multiple source files map to the same route:\n{}",
+            collisions.join("\n")
+        );
+    }
+
+    Ok(())
+}
+
+fn generate_wiki_page(
+    templates: &mut Templates,
+    pwt_configuration: &parse_wiki_text_2::Configuration,
+    site: &Site,
+    dst: &Path,
+    output_dir: &Path,
+    page: &SourcePage,
+    json_format: cli::JsonFormat,
+) -> anyhow::Result<()> {
+    let started = site.profile.as_ref().map(|_| Instant::now());
+
+    write_page_json(dst, page, json_format)?;
+
+    let route_path = page_title_to_route_path(&site.config, &page.title);
+    let document =
+        if let [WikitextSimplifiedNode::Redirect { target }] = page.simplified.as_slice() {
+            let target = site.moved_title(target).unwrap_or_else(|| target.clone());
+            redirect(&site.config, &page_url(&site.config, &target))
+        } else {
+            let page_context = PageContext {
+                input_path: page.input_path.clone(),
+                title: page.title.clone(),
+                route_path: route_path.clone(),
+                sub_page_name: page.page_name.clone(),
+                site,
+                state: Default::default(),
+            };
+            page_context.state.borrow_mut().toc.switch = page.toc_switch;
+
+            let mut content = convert_page_content(
+                templates,
+                pwt_configuration,
+                &page.simplified,
+                &page_context,
+            );
+
+            let mut state = page_context.state.borrow_mut();
+            for category in &state.categories {
+                site.category_members
+                    .borrow_mut()
+                    .entry(category.clone())
+                    .or_default()
+                    .insert(page.title.clone());
+            }
+
+            // Footnotes without a `<references />` to render them go at the end of the page
+            if !state.footnotes.is_empty() {
+                content.push(state.footnotes.render(
+                    false,
+                    site.config.references_columns_threshold,
+                ));
+            }
+
+            // The `{{Params}}` tables can only be filled in once every `{{Param}}` has been
+            // seen; without one, the parameters go at the end of the page too
+            if state.param_docs.has_tables() {
+                let html = paxhtml::Document::new([paxhtml::Element::from_iter(content)])
+                    .write_to_string()?;
+                content = vec![paxhtml::Element::Raw {
+                    html: state.param_docs.fill(&html)?,
+                }];
+            } else if !state.param_docs.is_empty() {
+                content.push(state.param_docs.render());
+            }
+
+            match site.config.source_view {
+                config::SourceView::None => {}
+                config::SourceView::Details => content.push(paxhtml::html! {
+                    <details class="mt-8">
+                        <summary class="cursor-pointer text-sm text-gray-600">"View source"</summary>
+                        {render_source(&page.source)}
+                    </details>
+                }),
+                config::SourceView::Page => {
+                    let url =
+                        route_url(&site.config, &source_route_path(&site.config, &page.title));
+                    content.push(paxhtml::html! {
+                        <p class="mt-8 text-sm">
+                            <a class={site.config.link_class.clone()} href={url}>"View source"</a>
+                        </p>
+                    });
+                }
+            }
+
+            if site.stubs.contains(&page.title) {
+                content.insert(
+                    0,
+                    paxhtml::html! {
+                        <div class="mb-6 px-4 py-3 border-l-4 border-yellow-400 bg-yellow-50 text-yellow-900" role="note">
+                            "This page is a stub: its content is incomplete."
+                        </div>
+                    },
+                );
+            }
+
+            let mut head = extra_head(&page.frontmatter, &page_context);
+            if state.has_math {
+                head.extend(math::head(site.config.effective_math_backend()));
+            }
+            if state.has_collapsed_table && site.config.scripts {
+                head.push(paxhtml::html! {
+                    <script src={format!("/{COLLAPSE_TABLES_SCRIPT}")} defer></script>
+                });
+            }
+            let faqs = faq::extract(&page.simplified, &site.config.faq_sections);
+            if !faqs.is_empty() {
+                head.push(faq::render(&faqs));
+            }
+            if site.config.keywords_meta && !state.categories.is_empty() {
+                let keywords = state.categories.join(", ");
+                head.push(paxhtml::html! { <meta name="keywords" content={keywords} /> });
+            }
+            let mut inner = paxhtml::Element::from_iter(content);
+            if site.config.content_hash || site.config.print_document {
+                let body = paxhtml::Document::new([inner]).write_to_string()?;
+                if site.config.content_hash {
+                    // The hash covers the page's own content, not the layout shared by every page
+                    let hash = content_hash::content_hash(&body);
+                    head.push(
+                        paxhtml::html! { <meta name={content_hash::META_NAME} content={hash} /> },
+                    );
+                }
+                if site.config.print_document {
+                    site.print_pages
+                        .borrow_mut()
+                        .push((page.title.clone(), body.clone()));
+                }
+                inner = paxhtml::Element::Raw { html: body };
+            }
+
+            layout(site, &page_context.title, head, inner, &state.toc)
+        };
+
+    write_document(&site.config, document, output_dir, route_path.clone())?;
+    if !page.is_redirect() {
+        site.sitemap_urls
+            .borrow_mut()
+            .insert(route_url(&site.config, &route_path));
+    }
+
+    if let Some(max) = site.config.max_page_bytes
+        && !page.is_redirect()
+    {
+        let path = output_dir.join(route_path.url_path().trim_start_matches('/'));
+        let size = fs::metadata(&path)?.len();
+        if size > max {
+            site.warnings.warn(format!(
+                "Page {} is {size} bytes of HTML, over the budget of {max} bytes",
+                page.title
+            ));
+            site.oversized_pages
+                .borrow_mut()
+                .push((page.title.clone(), size));
+        }
+    }
+
+    if site.config.source_view == config::SourceView::Page && !page.is_redirect() {
+        let inner = paxhtml::html! {
+            <>
+                <p class="mb-4">
+                    "The wikitext source of "
+                    <a class={site.config.link_class.clone()} href={page_url(&site.config, &page.title)}>{page.title.clone()}</a>
+                    "."
+                </p>
+                {render_source(&page.source)}
+            </>
+        };
+        let document = layout(site, &page.title, vec![], inner, &Default::default());
+        write_document(
+            &site.config,
+            document,
+            output_dir,
+            source_route_path(&site.config, &page.title),
+        )?;
+    }
+
+    if let (Some(profile), Some(started)) = (&site.profile, started) {
+        profile.record_page(&page.title, started.elapsed());
+    }
+    Ok(())
+}
+
+/// The classes styling the inline annotation tag `name` (`<del>`, `<ins>` or `<mark>`),
+/// or `None` if it isn't one
+fn annotation_class(name: &str) -> Option<&'static str> {
+    match name.to_lowercase().as_str() {
+        "del" => Some("line-through text-red-800 bg-red-50"),
+        "ins" => Some("underline text-green-800 bg-green-50"),
+        "mark" => Some("px-0.5 bg-yellow-200 rounded"),
+        _ => None,
+    }
+}
+
+/// The name of the category that the link `[[title]]` puts its page in, if it's a
+/// category link. Links starting with a colon (`[[:Category:...]]`) only link to the
+/// category.
+fn category_name(title: &str) -> Option<&str> {
+    let title = title.trim();
+    let (namespace, name) = title.split_once(':')?;
+    (namespace.trim().eq_ignore_ascii_case("category") && !name.trim().is_empty())
+        .then(|| name.trim())
+}
+
+/// The HTML from the configured `after_content_file`, if there is one
+fn load_after_content(config: &Config) -> anyhow::Result<Option<String>> {
+    config
+        .after_content_file
+        .as_ref()
+        .map(|path| {
+            fs::read_to_string(path)
+                .map_err(|e| anyhow::anyhow!("Failed to read after_content_file {path}: {e}"))
+        })
+        .transpose()
+}
+
+/// Whether `page` is a stub: marked with `{{Stub}}`, or shorter than the configured
+/// threshold
+fn is_stub(config: &Config, page: &SourcePage) -> bool {
+    if page.is_redirect() {
+        return false;
+    }
+    let mut marked = false;
+    for node in &page.simplified {
+        node.visit(&mut |node| {
+            marked |= matches!(
+                node,
+                WikitextSimplifiedNode::Template { name, .. }
+                    if name.trim().eq_ignore_ascii_case(STUB_TEMPLATE)
+            );
+        });
+    }
+    marked
+        || config.stub_threshold.is_some_and(|threshold| {
+            wikitext::plain_text(&page.simplified)
+                .trim()
+                .chars()
+                .count()
+                < threshold
+        })
+}
+
+/// Write the simplified wikitext of `page` alongside where its HTML goes
+fn write_page_json(
+    dst: &Path,
+    page: &SourcePage,
+    json_format: cli::JsonFormat,
+) -> anyhow::Result<()> {
+    let json_dir = dst.join(&page.relative_dir);
+    fs::create_dir_all(&json_dir)?;
+    fs::write(
+        json_dir.join(format!("{}.json", page.page_name)),
+        json_format.to_string(&page.simplified)?,
+    )?;
+    Ok(())
+}
+
+/// The stylesheets and scripts a page's frontmatter adds to its `<head>`, warning about
+/// any that refer to files missing from the static directory
+fn extra_head(frontmatter: &Frontmatter, page_context: &PageContext) -> Vec<paxhtml::Element> {
+    use paxhtml::html;
+
+    let mut check_exists = |path: &str| {
+        if !Path::new(STATIC_DIRECTORY).join(path).is_file() {
+            page_context.warn(format!("{STATIC_DIRECTORY}/{path} does not exist"));
+        }
+    };
+
+    let mut head = vec![];
+    for asset in &frontmatter.extra_css {
+        head.push(match asset {
+            Asset::Path(path) => {
+                check_exists(path);
+                html! { <link href={format!("/{path}")} rel="stylesheet" /> }
+            }
+            Asset::Inline(css) => {
+                html! { <style>{paxhtml::Element::Raw { html: css.clone() }}</style> }
+            }
+        });
+    }
+    // Scripts are left out entirely when they're disabled
+    let scripts = if page_context.site.config.scripts {
+        frontmatter.extra_js.as_slice()
+    } else {
+        &[]
+    };
+    for asset in scripts {
+        head.push(match asset {
+            Asset::Path(path) => {
+                check_exists(path);
+                html! { <script src={format!("/{path}")}></script> }
+            }
+            Asset::Inline(js) => {
+                html! { <script>{paxhtml::Element::Raw { html: js.clone() }}</script> }
+            }
+        });
+    }
+    head
+}
+
+fn layout(
+    site: &Site,
+    title: &str,
+    mut extra_head: Vec<paxhtml::Element>,
+    inner: paxhtml::Element,
+    toc: &toc::Toc,
+) -> paxhtml::Document {
+    let mut links = vec![("Home", page_url(&site.config, &site.config.main_page))];
+
+    if site::normalize_title(title) != site::normalize_title(&site.config.main_page) {
+        let mut components = vec![];
+        for component in title.split('/') {
+            components.push(component);
+            links.push((component, page_url(&site.config, &components.join("/"))));
+        }
+    }
+
+    // Pages with translations link to every version of themselves
+    let (language, versions) = site.page_languages(title);
+    let alternates = versions.iter().map(|(language, title)| {
+        paxhtml::html! {
+            <link rel="alternate" hreflang={*language} href={page_url(&site.config, title)} />
+        }
+    });
+    let language_switcher = (!versions.is_empty()).then(|| {
+        paxhtml::html! {
+            <div class="mb-4 text-sm text-gray-600">
+                "Languages: "
+                #{versions.iter().map(|(version_language, version_title)| {
+                    if *version_language == language {
+                        paxhtml::html! { <span class="mr-2 font-semibold text-gray-900">{*version_language}</span> }
+                    } else {
+                        paxhtml::html! {
+                            <a class={format!("mr-2 {}", site.config.link_class)} href={page_url(&site.config, version_title)} hreflang={*version_language}>
+                                {*version_language}
+                            </a>
+                        }
+                    }
+                })}
+            </div>
+        }
+    });
+
+    let canonical = canonical_url(&site.config, title)
+        .map(|url| paxhtml::html! { <link rel="canonical" href={url} /> });
+    let feed_link = (site.config.feed_count > 0).then(|| {
+        paxhtml::html! {
+            <link rel="alternate" type="application/atom+xml" title={SITE_NAME} href={format!("/{}", feed::FEED_FILE)} />
+        }
+    });
+
+    let theme_colors = match (&site.config.theme_color, &site.config.theme_color_dark) {
+        (Some(light), Some(dark)) => vec![
+            paxhtml::html! { <meta name="theme-color" content={light.clone()} media="(prefers-color-scheme: light)" /> },
+            paxhtml::html! { <meta name="theme-color" content={dark.clone()} media="(prefers-color-scheme: dark)" /> },
+        ],
+        (Some(color), None) | (None, Some(color)) => {
+            vec![paxhtml::html! { <meta name="theme-color" content={color.clone()} /> }]
+        }
+        (None, None) => vec![],
+    };
+    let mobile_web_app = site.config.mobile_web_app.then(|| {
+        paxhtml::html! {
+            <>
+                <meta name="mobile-web-app-capable" content="yes" />
+                <meta name="apple-mobile-web-app-capable" content="yes" />
+                <meta name="apple-mobile-web-app-title" content={SITE_NAME} />
+                <meta name="apple-mobile-web-app-status-bar-style" content="default" />
+            </>
+        }
+    });
+
+    let on_this_page = toc::render_sidebar(&site.config, toc);
+    let content_class = if site.config.break_long_words {
+        "space-y-4 break-words hyphens-auto"
+    } else {
+        "space-y-4"
+    };
+    if on_this_page.is_some() && site.config.scripts {
+        extra_head.push(paxhtml::html! {
+            <script src={format!("/{}", toc::SCROLL_SPY_SCRIPT)} defer></script>
+        });
+    }
+    let mut search = None;
+    if site.config.scripts {
+        extra_head.push(paxhtml::html! {
+            <script src={format!("/{}", search::SEARCH_SCRIPT)} defer></script>
+        });
+        search = Some(paxhtml::html! {
+            <div class="relative mr-2">
+                <input id="search" class="w-48 px-3 py-1 rounded text-gray-900" type="search" placeholder="Search" aria-label="Search the wiki" data-index={format!("/{}", search::SEARCH_INDEX)} />
+                <ul id="search-results" class="absolute right-0 z-10 w-72 mt-1 bg-white rounded shadow-lg text-sm" hidden></ul>
+            </div>
+        });
+    }
+
+    // Only the wiki's own pages get the configured content after them and analytics
+    let is_content_page =
+        site.page_titles.contains(title) && !title.starts_with(recent_changes::SPECIAL_PREFIX);
+    if is_content_page
+        && site.config.scripts
+        && let Some(script) = &site.config.analytics_script
+    {
+        let attributes = site
+            .config
+            .analytics_attributes
+            .iter()
+            .flat_map(|(name, value)| {
+                paxhtml::Attribute::parse_from_str(&format!(
+                    "{name}=\"{}\"",
+                    value.replace('"', "&quot;")
+                ))
+                .unwrap_or_default()
+            })
+            .collect::<Vec<_>>();
+        extra_head.push(paxhtml::html! {
+            <script src={script.clone()} defer {attributes}></script>
+        });
+    }
+    let after_content = site
+        .after_content
+        .as_ref()
+        .filter(|_| is_content_page)
+        .map(|html| {
+            paxhtml::html! {
+                <div class="mt-8 pt-6 border-t border-gray-200">
+                    {paxhtml::Element::Raw { html: html.clone() }}
+                </div>
+            }
+        });
+
+    let mut breadcrumbs = vec![];
+    for (idx, (component, url)) in links.into_iter().enumerate() {
+        if idx > 0 {
+            breadcrumbs.push(paxhtml::html! { <span class="text-gray-400">" / "</span> });
+        }
+        breadcrumbs.push(
+            paxhtml::html! { <a class={site.config.link_class.clone()} href={url}>{component}</a> },
+        );
+    }
+
+    paxhtml::Document::new([
+        paxhtml::builder::doctype(["html".into()]),
+        paxhtml::html! {
+            <html lang={language}>
+            <head>
+                <meta charset="UTF-8" />
+                <meta name="viewport" content="width=device-width, initial-scale=1.0" />
+                <title>{format!("{SITE_NAME} - {title}")}</title>
+                #{theme_colors}
+                #{mobile_web_app}
+                <link href="/style/tailwind.css" rel="stylesheet" />
+                <link href="/style/syntax.css" rel="stylesheet" />
+                #{canonical}
+                #{feed_link}
+                #{alternates}
+                #{extra_head}
+            </head>
+            <body class="bg-gray-100">
+                <nav class="bg-gray-900 text-white mb-4">
+                    <div class="max-w-7xl mx-auto px-4 sm:px-6 lg:px-8">
+                        <div class="flex items-center justify-between h-16">
                             <div class="flex items-center">
+                                <a class="text-xl font-semibold" href="/wiki">"Just Cause 2: Multiplayer"</a>
+                            </div>
+                            <div class="flex items-center">
+                                #{search}
+                                <a class="text-gray-300 hover:text-white px-3 py-2" href={page_url(&site.config, recent_changes::RECENT_CHANGES)}>"Recent changes"</a>
                                 <a class="text-gray-300 hover:text-white px-3 py-2" href="/">"Website"</a>
                             </div>
                         </div>
                     </div>
                 </nav>
-                <div class="max-w-7xl mx-auto px-4 sm:px-6 lg:px-8">
-                    <div class="bg-white p-8 rounded-lg shadow-sm">
+                <div class="max-w-7xl mx-auto px-4 sm:px-6 lg:px-8 flex gap-6">
+                    <aside class="hidden lg:block w-64 shrink-0 bg-white p-4 rounded-lg shadow-sm self-start">
+                        {site.sidebar.render(&site.config, title)}
+                    </aside>
+                    <div class="flex-1 min-w-0 bg-white p-8 rounded-lg shadow-sm">
                         <h1 class="text-3xl font-bold border-b-2 border-gray-300 pb-2 mb-6">#{breadcrumbs}</h1>
-                        <div class="space-y-4">
+                        #{language_switcher}
+                        <div class={content_class}>
                             {inner}
                         </div>
+                        #{after_content}
                     </div>
+                    #{on_this_page}
                 </div>
             </body>
             </html>
@@ -414,6 +1603,132 @@ fn layout(title: &str, inner: paxhtml::Element) -> paxhtml::Document {
     ])
 }
 
+/// Convert the top-level nodes of a page, grouping runs of inline content into
+/// paragraphs. Templates are instantiated first, so that those that expand to
+/// block-level content (e.g. tables) aren't placed in a paragraph.
+fn convert_page_content(
+    templates: &mut Templates,
+    pwt_configuration: &parse_wiki_text_2::Configuration,
+    nodes: &[WikitextSimplifiedNode],
+    page_context: &PageContext,
+) -> Vec<paxhtml::Element> {
+    use WikitextSimplifiedNode as WSN;
+    use paxhtml::html;
+
+    fn flush_paragraph(
+        templates: &mut Templates,
+        pwt_configuration: &parse_wiki_text_2::Configuration,
+        paragraph: &mut Vec<WSN>,
+        page_context: &PageContext,
+        content: &mut Vec<paxhtml::Element>,
+    ) {
+        let is_blank = |node: &WSN| {
+            matches!(node, WSN::Newline)
+                || matches!(node, WSN::Text { text } if text.trim().is_empty())
+        };
+        let start = paragraph
+            .iter()
+            .position(|node| !is_blank(node))
+            .unwrap_or(paragraph.len());
+        let end = paragraph
+            .iter()
+            .rposition(|node| !is_blank(node))
+            .map_or(start, |end| end + 1);
+        if start < end {
+            let children = convert_nodes(
+                templates,
+                pwt_configuration,
+                &paragraph[start..end],
+                page_context,
+            );
+            content.push(html! { <p class="mb-4">#{children}</p> });
+        }
+        paragraph.clear();
+    }
+
+    let mut content = vec![];
+    let mut paragraph = vec![];
+    for node in nodes {
+        let node = match node {
+            WSN::Template { name, parameters } => templates.instantiate(
+                pwt_configuration,
+                TemplateToInstantiate::Name(name),
+                parameters,
+                page_context,
+            ),
+            _ => node.clone(),
+        };
+        let is_block = match &node {
+            WSN::Template { name, .. } => !name.trim().eq_ignore_ascii_case(COLOR_TEMPLATE),
+            node => wikitext::is_block(node),
+        };
+
+        if matches!(node, WSN::ParagraphBreak) {
+            flush_paragraph(
+                templates,
+                pwt_configuration,
+                &mut paragraph,
+                page_context,
+                &mut content,
+            );
+        } else if is_block {
+            flush_paragraph(
+                templates,
+                pwt_configuration,
+                &mut paragraph,
+                page_context,
+                &mut content,
+            );
+            content.push(convert_wikitext_to_html(
+                templates,
+                pwt_configuration,
+                &node,
+                page_context,
+            ));
+        } else {
+            paragraph.push(node);
+        }
+    }
+    flush_paragraph(
+        templates,
+        pwt_configuration,
+        &mut paragraph,
+        page_context,
+        &mut content,
+    );
+    content
+}
+
+/// Convert a sequence of sibling nodes, handling the constructs that span several of them
+fn convert_nodes(
+    templates: &mut Templates,
+    pwt_configuration: &parse_wiki_text_2::Configuration,
+    nodes: &[WikitextSimplifiedNode],
+    page_context: &PageContext,
+) -> Vec<paxhtml::Element> {
+    use WikitextSimplifiedNode as WSN;
+    use paxhtml::html;
+
+    let is_kbd = |node: Option<&WSN>| matches!(node, Some(WSN::Tag { name, .. }) if name.eq_ignore_ascii_case("kbd"));
+
+    nodes
+        .iter()
+        .enumerate()
+        .map(|(i, node)| match node {
+            // The `+` in a key combination (`<kbd>Ctrl</kbd>+<kbd>C</kbd>`)
+            WSN::Text { text }
+                if text.trim() == "+"
+                    && i > 0
+                    && is_kbd(nodes.get(i - 1))
+                    && is_kbd(nodes.get(i + 1)) =>
+            {
+                html! { <span class="mx-0.5 text-gray-500">"+"</span> }
+            }
+            _ => convert_wikitext_to_html(templates, pwt_configuration, node, page_context),
+        })
+        .collect()
+}
+
 fn convert_wikitext_to_html(
     templates: &mut Templates,
     pwt_configuration: &parse_wiki_text_2::Configuration,
@@ -465,12 +1780,18 @@ fn convert_wikitext_to_html(
             .join("");
 
         if merged_text.is_empty() && !attributes.is_empty() {
-            panic!(
-                "Table {attributes_context} attributes must contain text; got {attributes:?} in {page_context}"
-            );
+            page_context.warn(format!(
+                "table {attributes_context} attributes must contain text; got {attributes:?}"
+            ));
+            return vec![];
         }
 
-        paxhtml::Attribute::parse_from_str(&merged_text).unwrap()
+        paxhtml::Attribute::parse_from_str(&merged_text).unwrap_or_else(|e| {
+            page_context.warn(format!(
+                "failed to parse table {attributes_context} attributes {merged_text:?}: {e:?}"
+            ));
+            vec![]
+        })
     }
 
     fn parse_optional_attributes_from_wsn(
@@ -494,20 +1815,34 @@ fn convert_wikitext_to_html(
             .unwrap_or_default()
     }
 
+    /// A header cell's `attributes` with `scope` added, so screen readers announce it
+    /// with the cells it labels, unless the author has given it a scope of their own
+    fn with_scope(attributes: Option<&[WSN]>, scope: &str) -> Vec<WSN> {
+        let mut attributes = attributes.unwrap_or_default().to_vec();
+        if wikitext::tag_attribute(&wikitext::plain_text(&attributes), "scope").is_none() {
+            attributes.push(WSN::Text {
+                text: format!(" scope=\"{scope}\""),
+            });
+        }
+        attributes
+    }
+
     let convert_children = |templates: &mut Templates, children: &[WikitextSimplifiedNode]| {
-        paxhtml::Element::from_iter(
-            children
-                .iter()
-                .skip_while(|node| matches!(node, WSN::ParagraphBreak | WSN::Newline))
-                .map(|node| {
-                    convert_wikitext_to_html(templates, pwt_configuration, node, page_context)
-                }),
-        )
+        let start = children
+            .iter()
+            .position(|node| !matches!(node, WSN::ParagraphBreak | WSN::Newline))
+            .unwrap_or(children.len());
+        paxhtml::Element::from_iter(convert_nodes(
+            templates,
+            pwt_configuration,
+            &children[start..],
+            page_context,
+        ))
     };
 
     match node {
         WSN::Fragment { children } => convert_children(templates, children),
-        WSN::Template { name, parameters } => {
+        WSN::Template { name, parameters } if !templates.is_builtin(name) => {
             let template = templates.instantiate(
                 pwt_configuration,
                 TemplateToInstantiate::Name(name),
@@ -516,33 +1851,202 @@ fn convert_wikitext_to_html(
             );
             convert_wikitext_to_html(templates, pwt_configuration, &template, page_context)
         }
+        WSN::Template { name, parameters } if hatnote_labels(name).is_some() => {
+            convert_hatnote(templates, pwt_configuration, name, parameters, page_context)
+        }
+        WSN::Template { name, parameters } if name.trim().eq_ignore_ascii_case(TEMPLATE_DEMO) => {
+            convert_template_demo(templates, pwt_configuration, parameters, page_context)
+        }
+        WSN::Template { name, parameters } if name.trim().eq_ignore_ascii_case(COLOR_TEMPLATE) => {
+            convert_color_template(templates, pwt_configuration, parameters, page_context)
+        }
+        // Stubs are marked by a banner at the top of the page instead
+        WSN::Template { name, .. } if name.trim().eq_ignore_ascii_case(STUB_TEMPLATE) => {
+            paxhtml::Element::from_iter(std::iter::empty())
+        }
+        WSN::Template { name, parameters } if is_clear_template(name) => {
+            let side = parameters
+                .iter()
+                .find(|p| p.name == "1")
+                .map(|p| p.value.trim().to_lowercase());
+            let class = match side.as_deref() {
+                Some("left") => "clear-left",
+                Some("right") => "clear-right",
+                _ => "clear-both",
+            };
+            html! { <div class={class}></div> }
+        }
+        WSN::Template { name, .. } if is_child_pages_template(name) => {
+            convert_child_pages(templates, pwt_configuration, page_context)
+        }
+        WSN::Template { name, parameters } if is_admonition_template(name) => {
+            convert_admonition(templates, pwt_configuration, name, parameters, page_context)
+        }
+        WSN::Template { name, parameters } if name.trim().eq_ignore_ascii_case(PARAM_TEMPLATE) => {
+            convert_param(templates, pwt_configuration, parameters, page_context)
+        }
+        WSN::Template { name, .. } if name.trim().eq_ignore_ascii_case(PARAMS_TEMPLATE) => {
+            page_context.state.borrow_mut().param_docs.placeholder()
+        }
+        // Every built-in template is converted above
+        template @ WSN::Template { .. } => {
+            html! { <>{template.to_wikitext()}</> }
+        }
         tpu @ WSN::TemplateParameterUse { .. } => {
             html! { <>{tpu.to_wikitext()}</> }
         }
-        WSN::Heading { level, children } => {
-            let class = match level {
-                2 => "text-2xl font-bold mt-8 mb-4",
-                3 => "text-xl font-bold mt-6 mb-3",
-                4 => "text-lg font-semibold mt-4 mb-2",
-                _ => "font-semibold mt-4 mb-2",
+        WSN::Heading {
+            level: wikitext_level,
+            children,
+        } => {
+            let config = &page_context.site.config;
+            let level = config.heading_level(*wikitext_level);
+            let class = config.heading_class(level);
+
+            // The `id` comes from the converted heading, so that it includes the text
+            // of any templates in it
+            let body = paxhtml::Document::new([convert_children(templates, children)])
+                .write_to_string()
+                .unwrap_or_default();
+            let text = wikitext::html_text(&body).trim().to_string();
+            let (id, number) = {
+                let mut state = page_context.state.borrow_mut();
+                // Section numbers are only added to the rendered heading, so they aren't
+                // part of its `id`
+                let id = toc::heading_id(
+                    &text,
+                    config.heading_ids,
+                    &config.anchor_prefix,
+                    &state.toc.entries,
+                );
+                let number = config
+                    .number_headings
+                    .then(|| state.section_numbers.next(*wikitext_level));
+                state.toc.entries.push(toc::TocEntry {
+                    level: *wikitext_level,
+                    text: match &number {
+                        Some(number) => format!("{number} {text}"),
+                        None => text,
+                    },
+                    id: id.clone(),
+                });
+                (id, number)
             };
+            let permalink = config.heading_permalinks.then(|| {
+                html! {
+                    <a class="heading-permalink ml-2 text-gray-400 no-underline" href={format!("#{id}")} aria-label="Link to this section">"#"</a>
+                }
+            });
+            let mut content = number
+                .map(|number| html! { <>{format!("{number} ")}</> })
+                .into_iter()
+                .collect::<Vec<_>>();
+            content.push(paxhtml::Element::Raw { html: body });
+            content.extend(permalink);
             paxhtml::builder::tag(
                 format!("h{level}"),
-                paxhtml::Attribute::parse_from_str(&format!("class=\"{}\"", class)).unwrap(),
+                paxhtml::Attribute::parse_from_str(&format!("class=\"{class}\" id=\"{id}\""))
+                    .unwrap(),
                 false,
-            )(convert_children(templates, children))
+            )(paxhtml::Element::from_iter(content))
         }
-        WSN::Link { text, title } => {
-            html! {
-                <a class="text-blue-600 hover:text-blue-800 hover:underline" href={page_title_to_route_path(title).url_path()}>
-                    {paxhtml::Element::Raw { html: text.to_string() }}
-                </a>
+        WSN::Link { text, title } if media::file_name(title).is_some() => {
+            let name = media::file_name(title).unwrap();
+            // Without any options, the link's text is just its title
+            let mut options = if text == title {
+                media::FileOptions::default()
+            } else {
+                media::FileOptions::parse(media::split_options(text))
+            };
+            // Captions can contain links and formatting, so they're rendered as wikitext,
+            // with their text standing in for a missing alt text
+            let caption = options.caption.as_deref().map(|caption| {
+                wikitext_simplified::parse_and_simplify_wikitext(caption, pwt_configuration)
+                    .unwrap_or_else(|_| {
+                        vec![WSN::Text {
+                            text: caption.to_string(),
+                        }]
+                    })
+            });
+            if options.alt.is_none()
+                && let Some(caption) = &caption
+            {
+                options.alt = Some(wikitext::plain_text(caption).trim().to_string());
             }
+            let caption = caption.map(|caption| convert_children(templates, &caption));
+            media::render_file_link(page_context.site, &name, &options, caption)
         }
-        WSN::ExtLink { link, text } => {
+        WSN::Link { text, title } if media::linked_file_name(title).is_some() => {
+            // Links straight to the file, instead of showing it
+            let name = media::linked_file_name(title).unwrap();
+            let text = if text == title {
+                title.trim().trim_start_matches(':').to_string()
+            } else {
+                text.to_string()
+            };
+            let config = &page_context.site.config;
+            let class = if Path::new(&config.media_directory).join(&name).is_file() {
+                config.link_class.clone()
+            } else {
+                page_context.warn(format!("broken link to the file {name}"));
+                config.broken_link_class()
+            };
+            html! {
+                <a class={class} href={format!("/{}/{name}", media::MEDIA_DIRECTORY)}>{text}</a>
+            }
+        }
+        WSN::Link { text, title } => {
+            let mut target = links::resolve_title(&page_context.title, title);
+            if let Some(moved) = page_context.site.moved_title(&target) {
+                if page_context.site.config.warn_moved_links {
+                    page_context.warn(format!("link to {target}, which has moved to {moved}"));
+                }
+                target = moved;
+            }
+            let text = if text.is_empty() {
+                links::pipe_trick(&target)
+            } else if text == title {
+                links::default_text(title).to_string()
+            } else {
+                text.to_string()
+            };
+
+            if let Some(category) = category_name(title) {
+                let mut state = page_context.state.borrow_mut();
+                if !state.categories.iter().any(|c| c == category) {
+                    state.categories.push(category.to_string());
+                }
+            }
+
+            // Links to namespaces that aren't configured (e.g. `Special:`) don't correspond
+            // to pages, and category pages are only known once every page is rendered
+            let namespaced = target.split('/').next().is_some_and(|s| s.contains(':'))
+                && namespaces::split(&page_context.site.config, &target)
+                    .is_none_or(|(namespace, _)| namespace == namespaces::CATEGORY_NAMESPACE);
+            if !namespaced {
+                page_context.site.record_link(&page_context.title, &target);
+                if !page_context.site.link_target_exists(&target) {
+                    page_context.warn(format!("broken link to {target}"));
+                }
+            }
+            let config = &page_context.site.config;
+            let class = if namespaced || page_context.site.link_target_exists(&target) {
+                config.link_class.clone()
+            } else {
+                config.broken_link_class()
+            };
+            html! {
+                <a class={class} href={page_url(config, &target)}>
+                    {paxhtml::Element::Raw { html: text }}
+                </a>
+            }
+        }
+        WSN::ExtLink { link, text } => {
+            let rel = page_context.site.config.external_link_rel(link);
+            let href = page_context.site.config.strip_query_parameters(link);
             html! {
-                <a class="text-blue-600 hover:text-blue-800 hover:underline" href={link}>
-                    {paxhtml::Element::Raw { html: text.as_ref().unwrap_or(link).to_string() }}
+                <a class={page_context.site.config.external_link_class()} href={href} rel={rel}>
+                    {paxhtml::Element::Raw { html: text.clone().unwrap_or_else(|| href.clone()) }}
                 </a>
             }
         }
@@ -573,7 +2077,8 @@ fn convert_wikitext_to_html(
             children,
         } => {
             if name == "syntaxhighlight" {
-                // Extract language from attributes string before parsing, defaulting to Lua
+                // Extract language from attributes string before parsing, defaulting to
+                // the configured language for the page's directory
                 let attrs_str = attributes.as_deref().unwrap_or_default();
                 let lang = if attrs_str.contains("lang=") || attrs_str.contains("language=") {
                     // Simple extraction of lang attribute value
@@ -588,7 +2093,13 @@ fn convert_wikitext_to_html(
                     })
                 } else {
                     None
-                };
+                }
+                .unwrap_or_else(|| {
+                    page_context
+                        .site
+                        .config
+                        .default_code_language_for(&page_context.title)
+                });
 
                 // Get the code text
                 let code = if let [WSN::Text { text }] = children.as_slice() {
@@ -599,24 +2110,129 @@ fn convert_wikitext_to_html(
                     return html! { <pre {parsed_attributes}><code>{convert_children(templates, children)}</code></pre> };
                 };
 
-                // Use syntax highlighter
-                if let Some(highlighter) = SYNTAX_HIGHLIGHTER.get() {
-                    match highlighter.highlight_code(lang, code) {
-                        Ok(highlighted) => {
-                            html! { <pre class="bg-gray-900 text-gray-100 p-4 rounded-lg overflow-x-auto my-4"><code>{highlighted}</code></pre> }
-                        }
-                        Err(_) => {
-                            // Fallback to plain text if highlighting fails
-                            let parsed_attributes =
-                                paxhtml::Attribute::parse_from_str(attrs_str).unwrap();
-                            html! { <pre class="bg-gray-900 text-gray-100 p-4 rounded-lg overflow-x-auto my-4" {parsed_attributes}><code>{code}</code></pre> }
-                        }
-                    }
+                convert_code_block(lang, code, attrs_str, page_context)
+            } else if name.eq_ignore_ascii_case("math") {
+                let display = attributes
+                    .as_deref()
+                    .and_then(|a| wikitext::tag_attribute(a, "display"))
+                    .is_some_and(|display| display.eq_ignore_ascii_case("block"));
+                // The formula is TeX, not wikitext, so it's rendered from its source
+                let tex = WSN::Fragment {
+                    children: children.clone(),
+                }
+                .to_wikitext();
+                page_context.state.borrow_mut().has_math = true;
+                math::render(
+                    page_context.site.config.effective_math_backend(),
+                    &tex,
+                    display,
+                    page_context,
+                )
+            } else if name.eq_ignore_ascii_case("kbd") {
+                let parsed_attributes =
+                    paxhtml::Attribute::parse_from_str(attributes.as_deref().unwrap_or_default())
+                        .unwrap();
+                html! {
+                    <kbd class="px-1.5 py-0.5 font-mono text-sm bg-gray-100 border border-gray-300 rounded shadow-sm" {parsed_attributes}>
+                        {convert_children(templates, children)}
+                    </kbd>
+                }
+            } else if let Some(class) = annotation_class(name) {
+                // The annotation's styling goes before any classes of its own
+                let attributes = attributes.as_deref().unwrap_or_default();
+                let mut pairs = wikitext::tag_attributes(attributes);
+                let class = match pairs
+                    .iter()
+                    .position(|(key, _)| key.eq_ignore_ascii_case("class"))
+                {
+                    Some(index) => format!("{class} {}", pairs.remove(index).1),
+                    None => class.to_string(),
+                };
+                pairs.push(("class", &class));
+
+                let parsed_attributes =
+                    paxhtml::Attribute::parse_from_str(&wikitext::format_tag_attributes(pairs))
+                        .unwrap();
+                let children = convert_children(templates, children);
+                paxhtml::builder::tag(name.to_lowercase(), parsed_attributes, false)(children)
+            } else if name.eq_ignore_ascii_case("code") {
+                // Character references in inline code are shown as the characters they
+                // stand for (e.g. `&#91;` as `[`); in code blocks they're left as written
+                let attributes = attributes.as_deref().unwrap_or_default();
+                let parsed_attributes = if page_context.site.config.break_long_words {
+                    // Long identifiers wrap instead of widening the page
+                    let mut pairs = wikitext::tag_attributes(attributes);
+                    let class = match pairs
+                        .iter()
+                        .position(|(key, _)| key.eq_ignore_ascii_case("class"))
+                    {
+                        Some(index) => format!("{} break-words", pairs.remove(index).1),
+                        None => "break-words".to_string(),
+                    };
+                    pairs.push(("class", &class));
+                    paxhtml::Attribute::parse_from_str(&wikitext::format_tag_attributes(pairs))
                 } else {
-                    // Fallback if highlighter not initialized
-                    let parsed_attributes = paxhtml::Attribute::parse_from_str(attrs_str).unwrap();
-                    html! { <pre class="bg-gray-900 text-gray-100 p-4 rounded-lg overflow-x-auto my-4" {parsed_attributes}><code>{code}</code></pre> }
+                    paxhtml::Attribute::parse_from_str(attributes)
+                }
+                .unwrap();
+                let children = children
+                    .iter()
+                    .map(|node| match node {
+                        WSN::Text { text } => paxhtml::Element::Raw {
+                            html: wikitext::decode_numeric_references(text),
+                        },
+                        _ => convert_wikitext_to_html(
+                            templates,
+                            pwt_configuration,
+                            node,
+                            page_context,
+                        ),
+                    })
+                    .collect::<Vec<_>>();
+                html! { <code {parsed_attributes}>#{children}</code> }
+            } else if name.eq_ignore_ascii_case("div") || name.eq_ignore_ascii_case("span") {
+                // Only the classes the site provides styling for are kept
+                let attributes = attributes.as_deref().unwrap_or_default();
+                let mut pairs = wikitext::tag_attributes(attributes);
+                let mut allowed = String::new();
+                if let Some((_, classes)) = pairs
+                    .iter()
+                    .find(|(key, _)| key.eq_ignore_ascii_case("class"))
+                {
+                    let (kept, dropped) = content_classes::filter(classes);
+                    if !dropped.is_empty() {
+                        page_context.warn(format!(
+                            "unsupported classes on <{name}> were dropped: {}",
+                            dropped.join(" ")
+                        ));
+                    }
+                    allowed = kept;
                 }
+                pairs.retain(|(key, _)| !key.eq_ignore_ascii_case("class"));
+                if !allowed.is_empty() {
+                    pairs.push(("class", &allowed));
+                }
+
+                let parsed_attributes =
+                    paxhtml::Attribute::parse_from_str(&wikitext::format_tag_attributes(pairs))
+                        .unwrap();
+                let children = convert_children(templates, children);
+                paxhtml::builder::tag(name.to_string(), parsed_attributes, false)(children)
+            } else if name == "ref" {
+                let name = attributes
+                    .as_deref()
+                    .and_then(|a| wikitext::tag_attribute(a, "name"));
+                let content = convert_children(templates, children);
+                page_context.state.borrow_mut().footnotes.add(name, content)
+            } else if name == "references" {
+                let responsive = attributes
+                    .as_deref()
+                    .and_then(|a| wikitext::tag_attribute(a, "responsive"))
+                    .is_some_and(|value| value != "0");
+                page_context.state.borrow_mut().footnotes.render(
+                    responsive,
+                    page_context.site.config.references_columns_threshold,
+                )
             } else {
                 let parsed_attributes =
                     paxhtml::Attribute::parse_from_str(attributes.as_deref().unwrap_or_default())
@@ -636,9 +2252,8 @@ fn convert_wikitext_to_html(
             // Add Bootstrap classes to table attributes
             let mut modified_attributes = attributes.clone();
 
-            // Add Bootstrap table classes if not already present
-            let has_class_attr = if !attributes.is_empty() {
-                // Check if there's already a class attribute by instantiating and checking text
+            // Find the table's classes by instantiating its attributes
+            let classes = if !attributes.is_empty() {
                 let instantiated = templates.instantiate(
                     pwt_configuration,
                     TemplateToInstantiate::Node(WikitextSimplifiedNode::Fragment {
@@ -647,20 +2262,30 @@ fn convert_wikitext_to_html(
                     &[],
                     page_context,
                 );
-                if let WSN::Fragment { children } = instantiated {
-                    if let Some(WSN::Text { text }) = children.first() {
-                        text.contains("class=")
-                    } else {
-                        false
-                    }
+                if let WSN::Fragment { children } = instantiated
+                    && let Some(WSN::Text { text }) = children.first()
+                {
+                    wikitext::tag_attribute(text, "class").map(str::to_string)
                 } else {
-                    false
+                    None
                 }
             } else {
-                false
+                None
+            };
+            // Layout tables opt out of the borders, header colors and striping
+            let plain = classes.as_deref().is_some_and(|classes| {
+                classes
+                    .split_whitespace()
+                    .any(|class| class == "plain" || class == "borderless")
+            });
+            let (head_class, body_class) = if plain {
+                ("", "")
+            } else {
+                ("bg-gray-800 text-white", "divide-y divide-gray-200")
             };
 
-            if !has_class_attr {
+            // Add Tailwind table classes if not already present
+            if classes.is_none() {
                 // Add Tailwind table classes
                 modified_attributes.push(WSN::Text {
                     text: " class=\"min-w-full divide-y divide-gray-200 border border-gray-300\""
@@ -668,6 +2293,16 @@ fn convert_wikitext_to_html(
                 });
             }
 
+            // Long tables are marked for the script that collapses them
+            if let Some(max_rows) = page_context.site.config.collapse_table_rows
+                && rows.len() > max_rows
+            {
+                modified_attributes.push(WSN::Text {
+                    text: format!(" data-collapse-rows=\"{max_rows}\""),
+                });
+                page_context.state.borrow_mut().has_collapsed_table = true;
+            }
+
             let attributes = parse_attributes_from_wsn(
                 templates,
                 pwt_configuration,
@@ -677,17 +2312,17 @@ fn convert_wikitext_to_html(
             );
             html! {
                 <table {attributes}>
-                    <thead class="bg-gray-800 text-white">
+                    <thead class={head_class}>
                         <tr>
                             #{captions
                                 .iter()
                                 .map(|caption| {
-                                    let attributes = parse_optional_attributes_from_wsn(
+                                    let attributes = parse_attributes_from_wsn(
                                         templates,
                                         pwt_configuration,
                                         page_context,
                                         "caption",
-                                        &caption.attributes,
+                                        &with_scope(caption.attributes.as_deref(), "col"),
                                     );
                                     html! {
                                         <th class="px-4 py-2 text-left" {attributes}>
@@ -698,7 +2333,7 @@ fn convert_wikitext_to_html(
                             }
                         </tr>
                     </thead>
-                    <tbody class="divide-y divide-gray-200">
+                    <tbody class={body_class}>
                         #{rows
                             .iter()
                             .enumerate()
@@ -710,12 +2345,39 @@ fn convert_wikitext_to_html(
                                     "row",
                                     &row.attributes,
                                 );
-                                let row_class = if idx % 2 == 0 { "bg-white" } else { "bg-gray-50" };
+                                let row_class = if plain {
+                                    ""
+                                } else if idx % 2 == 0 {
+                                    "bg-white hover:bg-gray-100"
+                                } else {
+                                    "bg-gray-50 hover:bg-gray-100"
+                                };
+                                // Header cells in a row of data label that row; in a row of
+                                // only header cells, they label their column
+                                let scope = if row.cells.iter().all(|cell| cell.is_header) {
+                                    "col"
+                                } else {
+                                    "row"
+                                };
                                 html! {
-                                    <tr class={format!("{} hover:bg-gray-100", row_class)} {attributes}>
+                                    <tr class={row_class} {attributes}>
                                         #{row.cells
                                             .iter()
                                             .map(|cell| {
+                                                if cell.is_header {
+                                                    let attributes = parse_attributes_from_wsn(
+                                                        templates,
+                                                        pwt_configuration,
+                                                        page_context,
+                                                        "cell",
+                                                        &with_scope(cell.attributes.as_deref(), scope),
+                                                    );
+                                                    return html! {
+                                                        <th class="px-4 py-2 text-left" {attributes}>
+                                                            {convert_children(templates, &cell.content)}
+                                                        </th>
+                                                    };
+                                                }
                                                 let attributes = parse_optional_attributes_from_wsn(
                                                     templates,
                                                     pwt_configuration,
@@ -764,20 +2426,35 @@ fn convert_wikitext_to_html(
         }
         WSN::DefinitionList { items } => {
             use wikitext_simplified::DefinitionListItemType;
+
+            // Each term is grouped with all of the details that follow it, as a term can
+            // have several (`;term` followed by multiple `:detail` lines)
+            let mut groups: Vec<Vec<paxhtml::Element>> = vec![];
+            for item in items {
+                let children = convert_children(templates, &item.content);
+                let element = match item.type_ {
+                    DefinitionListItemType::Term => {
+                        html! { <dt class="font-semibold mt-2">{children}</dt> }
+                    }
+                    DefinitionListItemType::Details => {
+                        html! { <dd class="ml-6 text-gray-700">{children}</dd> }
+                    }
+                };
+                match groups.last_mut() {
+                    Some(group) if matches!(item.type_, DefinitionListItemType::Details) => {
+                        group.push(element)
+                    }
+                    _ => groups.push(vec![element]),
+                }
+            }
             html! {
                 <dl>
-                    #{items.iter().map(|i| {
-                        let children = convert_children(templates, &i.content);
-                        match i.type_ {
-                            DefinitionListItemType::Term => html! { <dt class="font-semibold mt-2">{children}</dt> },
-                            DefinitionListItemType::Details => html! { <dd class="ml-6 text-gray-700">{children}</dd> },
-                        }
-                    })}
+                    #{groups.into_iter().map(|group| html! { <div>#{group}</div> })}
                 </dl>
             }
         }
         WSN::Redirect { target } => html! {
-            <a class="text-blue-600 hover:text-blue-800 hover:underline" href={page_title_to_route_path(target).url_path()}>
+            <a class={page_context.site.config.link_class.clone()} href={page_url(&page_context.site.config, target)}>
                 "REDIRECT: "{target}
             </a>
         },
@@ -787,9 +2464,382 @@ fn convert_wikitext_to_html(
     }
 }
 
-fn page_title_to_route_path(title: &str) -> paxhtml::RoutePath {
-    let title_link = title.replace(" ", "_");
+/// Render a `syntaxhighlight` block, highlighting it if its language is known and
+/// otherwise following the configured policy for unknown languages
+fn convert_code_block(
+    lang: &str,
+    code: &str,
+    attrs_str: &str,
+    page_context: &PageContext,
+) -> paxhtml::Element {
+    use paxhtml::html;
+
+    let plain = || {
+        let parsed_attributes = paxhtml::Attribute::parse_from_str(attrs_str).unwrap();
+        html! { <pre class="bg-gray-900 text-gray-100 p-4 rounded-lg overflow-x-auto my-4" {parsed_attributes}><code>{code}</code></pre> }
+    };
+    let Some(highlighter) = SYNTAX_HIGHLIGHTER.get() else {
+        return plain();
+    };
+
+    let syntax = highlighter.find_language(lang).or_else(|| {
+        match page_context.site.config.unknown_code_language {
+            UnknownCodeLanguage::Plain => None,
+            UnknownCodeLanguage::Error => {
+                page_context.warn(format!("unknown code language {lang}"));
+                None
+            }
+            UnknownCodeLanguage::Autodetect => highlighter.detect_language(code),
+        }
+    });
+    let Some(syntax) = syntax else {
+        return plain();
+    };
+
+    match highlighter.highlight_code(syntax, code) {
+        Ok(highlighted) => {
+            html! { <pre class="bg-gray-900 text-gray-100 p-4 rounded-lg overflow-x-auto my-4"><code>{highlighted}</code></pre> }
+        }
+        // Fall back to plain text if highlighting fails
+        Err(_) => plain(),
+    }
+}
+
+/// The labels of the hatnote template `name` for one target and for several, if it is one
+fn hatnote_labels(name: &str) -> Option<(&'static str, &'static str)> {
+    let name = name.trim().replace('_', " ").to_lowercase();
+    HATNOTES
+        .iter()
+        .find(|(template, _, _)| *template == name)
+        .map(|(_, single, multiple)| (*single, *multiple))
+}
+
+/// Render a hatnote such as `{{Main|Page|Other page}}`: an italic note linking to each
+/// of the positional targets
+fn convert_hatnote(
+    templates: &mut Templates,
+    pwt_configuration: &parse_wiki_text_2::Configuration,
+    name: &str,
+    parameters: &[wikitext_simplified::TemplateParameter],
+    page_context: &PageContext,
+) -> paxhtml::Element {
+    use paxhtml::html;
+
+    let (single, multiple) = hatnote_labels(name).unwrap();
+    let mut targets = parameters
+        .iter()
+        .filter_map(|p| Some((p.name.parse::<usize>().ok()?, p.value.trim())))
+        .filter(|(_, target)| !target.is_empty())
+        .collect::<Vec<_>>();
+    targets.sort_by_key(|(index, _)| *index);
+    if targets.is_empty() {
+        page_context.warn(format!("{{{{{name}}}}} requires at least one page"));
+    }
+
+    let label = if targets.len() > 1 { multiple } else { single };
+    let mut children = vec![html! { <>{format!("{label}: ")}</> }];
+    for (i, (_, target)) in targets.into_iter().enumerate() {
+        if i > 0 {
+            children.push(html! { <>", "</> });
+        }
+        let link = WikitextSimplifiedNode::Link {
+            text: target.to_string(),
+            title: target.to_string(),
+        };
+        children.push(convert_wikitext_to_html(
+            templates,
+            pwt_configuration,
+            &link,
+            page_context,
+        ));
+    }
+
+    html! {
+        <div class="italic text-gray-600 pl-6 mb-2" role="note">#{children}</div>
+    }
+}
+
+fn is_admonition_template(name: &str) -> bool {
+    let name = name.trim().to_lowercase();
+    name == ADMONITION_TEMPLATE || ADMONITIONS.iter().any(|(template, ..)| *template == name)
+}
+
+/// Render an admonition such as `{{Warning|text}}` or `{{Admonition|type|text}}`: a
+/// callout box colored for its type, whose text can contain any markup
+fn convert_admonition(
+    templates: &mut Templates,
+    pwt_configuration: &parse_wiki_text_2::Configuration,
+    name: &str,
+    parameters: &[wikitext_simplified::TemplateParameter],
+    page_context: &PageContext,
+) -> paxhtml::Element {
+    use paxhtml::html;
+
+    let parameter = |name: &str| {
+        parameters
+            .iter()
+            .find(|p| p.name == name)
+            .map_or("", |p| p.value.as_str())
+    };
+    let name = name.trim().to_lowercase();
+    let (kind, text) = if name == ADMONITION_TEMPLATE {
+        (parameter("1").trim().to_lowercase(), parameter("2"))
+    } else {
+        (name, parameter("1"))
+    };
+
+    let fallback_label = {
+        let mut chars = kind.chars();
+        chars.next().map_or_else(
+            || "Note".to_string(),
+            |first| first.to_uppercase().chain(chars).collect(),
+        )
+    };
+    let (label, icon, class) = ADMONITIONS
+        .iter()
+        .find(|(template, ..)| *template == kind)
+        .map_or(
+            (
+                fallback_label.as_str(),
+                "💬",
+                "border-gray-400 bg-gray-50 text-gray-900",
+            ),
+            |(_, label, icon, class)| (*label, *icon, *class),
+        );
+
+    let body = match wikitext_simplified::parse_and_simplify_wikitext(text, pwt_configuration) {
+        Ok(children) => paxhtml::Element::from_iter(convert_page_content(
+            templates,
+            pwt_configuration,
+            &children,
+            page_context,
+        )),
+        Err(e) => {
+            page_context.warn(format!("failed to parse {{{{{label}}}}} text: {e:?}"));
+            html! { <>{text}</> }
+        }
+    };
+
+    html! {
+        <div class={format!("flex gap-3 my-4 px-4 py-3 border-l-4 rounded {class}")} role="note" aria-label={label}>
+            <span class="shrink-0" aria-hidden="true">{icon}</span>
+            <div class="min-w-0">
+                <p class="font-semibold">{label}</p>
+                {body}
+            </div>
+        </div>
+    }
+}
+
+fn is_clear_template(name: &str) -> bool {
+    let name = name.trim().to_lowercase();
+    CLEAR_TEMPLATES.contains(&name.as_str())
+}
+
+fn is_param_docs_template(name: &str) -> bool {
+    let name = name.trim();
+    name.eq_ignore_ascii_case(PARAM_TEMPLATE) || name.eq_ignore_ascii_case(PARAMS_TEMPLATE)
+}
+
+/// Record a `{{Param|name|description|required=yes}}` for the page's parameter table.
+/// It renders nothing itself; the description can contain any markup.
+fn convert_param(
+    templates: &mut Templates,
+    pwt_configuration: &parse_wiki_text_2::Configuration,
+    parameters: &[wikitext_simplified::TemplateParameter],
+    page_context: &PageContext,
+) -> paxhtml::Element {
+    let parameter = |name: &str| {
+        parameters
+            .iter()
+            .find(|p| p.name == name)
+            .map_or("", |p| p.value.as_str())
+    };
+    let name = parameter("1");
+    if name.trim().is_empty() {
+        page_context.warn("{{Param}} is missing the name of the parameter");
+    }
+    let required = matches!(
+        parameter("required").trim().to_lowercase().as_str(),
+        "yes" | "y" | "true" | "1"
+    );
+
+    let description = parameter("2");
+    let description =
+        match wikitext_simplified::parse_and_simplify_wikitext(description, pwt_configuration) {
+            Ok(children) => paxhtml::Element::from_iter(
+                children
+                    .iter()
+                    .map(|child| {
+                        convert_wikitext_to_html(templates, pwt_configuration, child, page_context)
+                    })
+                    .collect::<Vec<_>>(),
+            ),
+            Err(e) => {
+                page_context.warn(format!("failed to parse {{{{Param}}}} description: {e:?}"));
+                paxhtml::html! { <>{description}</> }
+            }
+        };
+
+    page_context
+        .state
+        .borrow_mut()
+        .param_docs
+        .add(name, description, required);
+    paxhtml::Element::from_iter(std::iter::empty())
+}
+
+fn is_child_pages_template(name: &str) -> bool {
+    let name = name.trim().to_lowercase();
+    CHILD_PAGES_TEMPLATES.contains(&name.as_str())
+}
+
+/// Render `{{ChildPages}}`: a list linking to the pages directly below the current page,
+/// excluding its translations. Nothing is rendered if there are none.
+fn convert_child_pages(
+    templates: &mut Templates,
+    pwt_configuration: &parse_wiki_text_2::Configuration,
+    page_context: &PageContext,
+) -> paxhtml::Element {
+    use paxhtml::html;
+
+    let site = page_context.site;
+    let prefix = format!("{}/", page_context.title);
+    let items = site
+        .page_titles
+        .iter()
+        .filter_map(|title| Some((title, title.strip_prefix(&prefix)?)))
+        .filter(|(_, name)| !name.contains('/'))
+        .filter(|(_, name)| !site.config.translation_languages.iter().any(|l| l == name))
+        .map(|(title, name)| {
+            let link = WikitextSimplifiedNode::Link {
+                text: name.to_string(),
+                title: title.clone(),
+            };
+            let link = convert_wikitext_to_html(templates, pwt_configuration, &link, page_context);
+            html! { <li>{link}</li> }
+        })
+        .collect::<Vec<_>>();
+    if items.is_empty() {
+        return paxhtml::Element::from_iter(std::iter::empty());
+    }
+
+    html! {
+        <ul class="list-disc pl-6 mb-4 space-y-1">#{items}</ul>
+    }
+}
+
+/// Render `{{TemplateDemo|Name|args...}}`: the output of `{{Name|args...}}`, followed by a
+/// collapsible block showing the invocation's source
+fn convert_template_demo(
+    templates: &mut Templates,
+    pwt_configuration: &parse_wiki_text_2::Configuration,
+    parameters: &[wikitext_simplified::TemplateParameter],
+    page_context: &PageContext,
+) -> paxhtml::Element {
+    use paxhtml::html;
+
+    let Some(name) = parameters.iter().find(|p| p.name == "1") else {
+        page_context.warn(format!("{TEMPLATE_DEMO} requires a template name"));
+        return paxhtml::Element::from_iter(std::iter::empty());
+    };
+
+    // The remaining positional parameters move down by one to become the demoed
+    // template's own
+    let demo = WikitextSimplifiedNode::Template {
+        name: name.value.trim().to_string(),
+        parameters: parameters
+            .iter()
+            .filter(|p| p.name != "1")
+            .filter_map(|p| {
+                let name = match p.name.parse::<usize>() {
+                    Ok(index) => match index.checked_sub(1) {
+                        Some(index) => index.to_string(),
+                        // There's no parameter before the template name to move it to
+                        None => {
+                            page_context.warn(format!(
+                                "{TEMPLATE_DEMO} ignores the parameter {:?}",
+                                p.name
+                            ));
+                            return None;
+                        }
+                    },
+                    Err(_) => p.name.clone(),
+                };
+                Some(wikitext_simplified::TemplateParameter {
+                    name,
+                    value: p.value.clone(),
+                })
+            })
+            .collect(),
+    };
+
+    html! {
+        <div class="my-4 border border-gray-300 rounded">
+            <div class="p-4">
+                {convert_wikitext_to_html(templates, pwt_configuration, &demo, page_context)}
+            </div>
+            <details class="border-t border-gray-300 bg-gray-50">
+                <summary class="px-4 py-2 cursor-pointer text-sm text-gray-700">"Show source"</summary>
+                <pre class="px-4 pb-4 text-sm overflow-x-auto"><code>{demo.to_wikitext()}</code></pre>
+            </details>
+        </div>
+    }
+}
+
+/// Render `{{color|value|text}}` as the text in the given color. Invalid colors are
+/// warned about and the text is rendered uncolored.
+fn convert_color_template(
+    templates: &mut Templates,
+    pwt_configuration: &parse_wiki_text_2::Configuration,
+    parameters: &[wikitext_simplified::TemplateParameter],
+    page_context: &PageContext,
+) -> paxhtml::Element {
+    use paxhtml::html;
+
+    let parameter = |name: &str| {
+        parameters
+            .iter()
+            .find(|p| p.name == name)
+            .map_or("", |p| p.value.as_str())
+    };
+    let value = parameter("1").trim();
+    let text =
+        match wikitext_simplified::parse_and_simplify_wikitext(parameter("2"), pwt_configuration) {
+            Ok(children) => convert_wikitext_to_html(
+                templates,
+                pwt_configuration,
+                &WikitextSimplifiedNode::Fragment { children },
+                page_context,
+            ),
+            Err(e) => {
+                page_context.warn(format!(
+                    "failed to parse {{{{{COLOR_TEMPLATE}}}}} text: {e:?}"
+                ));
+                html! { <>{parameter("2")}</> }
+            }
+        };
+
+    if color::Color::parse(value).is_none() {
+        page_context.warn(format!(
+            "invalid color {value:?} in {{{{{COLOR_TEMPLATE}}}}}"
+        ));
+        return text;
+    }
+    html! { <span style={format!("color:{value}")}>{text}</span> }
+}
+
+fn page_title_to_route_path(config: &Config, title: &str) -> paxhtml::RoutePath {
+    let title_link = namespaces::title_path(config, title);
     let segments = title_link.split('/').collect::<Vec<_>>();
+
+    if config.clean_urls {
+        return paxhtml::RoutePath::new(
+            std::iter::once(WIKI_DIRECTORY).chain(segments.iter().copied()),
+            Some("index.html".to_string()),
+        );
+    }
+
     let (page_name, directories) = segments.split_last().unwrap();
 
     paxhtml::RoutePath::new(
@@ -798,7 +2848,92 @@ fn page_title_to_route_path(title: &str) -> paxhtml::RoutePath {
     )
 }
 
-fn redirect(to_url: &str) -> paxhtml::Document {
+/// The route of the page showing the source of the page `title`, next to the page
+fn source_route_path(config: &Config, title: &str) -> paxhtml::RoutePath {
+    let title_link = namespaces::title_path(config, title);
+    let segments = title_link.split('/').collect::<Vec<_>>();
+    let (directories, file_name) = if config.clean_urls {
+        (segments.as_slice(), "index.source.html".to_string())
+    } else {
+        let (page_name, directories) = segments.split_last().unwrap();
+        (directories, format!("{page_name}.source.html"))
+    };
+
+    paxhtml::RoutePath::new(
+        std::iter::once(WIKI_DIRECTORY).chain(directories.iter().copied()),
+        Some(file_name),
+    )
+}
+
+/// The wikitext `source` of a page, escaped and preformatted
+fn render_source(source: &str) -> paxhtml::Element {
+    paxhtml::html! {
+        <pre class="p-4 bg-gray-50 border border-gray-200 rounded text-sm overflow-x-auto whitespace-pre-wrap"><code>{source.trim().to_string()}</code></pre>
+    }
+}
+
+/// The URL of a route, without a trailing `index.html` so that clean URLs point at
+/// the directory. Whether that ends with a slash is configurable. Relative links keep
+/// the `index.html`, as there's no server to find it.
+fn route_url(config: &Config, route_path: &paxhtml::RoutePath) -> String {
+    let url = slug::encode_url(&route_path.url_path());
+    if config.relative_links {
+        return url;
+    }
+    let Some(directory) = url.strip_suffix("index.html") else {
+        return url;
+    };
+    if config.trailing_slash || directory == "/" {
+        directory.to_string()
+    } else {
+        directory.trim_end_matches('/').to_string()
+    }
+}
+
+/// Write `document` to `route_path` under the output root `output_dir`, making its links
+/// relative to it if configured to
+fn write_document(
+    config: &Config,
+    document: paxhtml::Document,
+    output_dir: &Path,
+    route_path: paxhtml::RoutePath,
+) -> anyhow::Result<()> {
+    if !config.relative_links {
+        document.write_to_route(output_dir, route_path)?;
+        return Ok(());
+    }
+    let page_path = slug::encode_url(&route_path.url_path());
+    let html = relative_links::relativize(&document.write_to_string()?, &page_path);
+    let path = output_dir.join(route_path.url_path().trim_start_matches('/'));
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, html)?;
+    Ok(())
+}
+
+/// The URL of the page with the given title. A section in the title (`Page#Section`)
+/// becomes the URL's fragment, and a title of only a section (`#Section`) links to it on
+/// the current page.
+fn page_url(config: &Config, title: &str) -> String {
+    let Some((title, section)) = title.split_once('#') else {
+        return route_url(config, &page_title_to_route_path(config, title));
+    };
+    let fragment = toc::heading_id(section, config.heading_ids, &config.anchor_prefix, &[]);
+    if title.trim().is_empty() {
+        return format!("#{fragment}");
+    }
+    let url = route_url(config, &page_title_to_route_path(config, title));
+    format!("{url}#{fragment}")
+}
+
+/// The absolute URL of the page with the given title, if the site's base URL is known
+fn canonical_url(config: &Config, title: &str) -> Option<String> {
+    let base_url = config.base_url.as_deref()?.trim_end_matches('/');
+    Some(format!("{base_url}{}", page_url(config, title)))
+}
+
+fn redirect(config: &Config, to_url: &str) -> paxhtml::Document {
     paxhtml::Document::new([
         paxhtml::builder::doctype(["html".into()]),
         paxhtml::html! {
@@ -813,7 +2948,7 @@ fn redirect(to_url: &str) -> paxhtml::Document {
                     <div class="text-center">
                         <p class="text-xl mb-4">"Redirecting..."</p>
                         <p>
-                            <a class="text-blue-600 hover:text-blue-800 hover:underline" href={to_url} title="Click here if you are not redirected">
+                            <a class={config.link_class.clone()} href={to_url} title="Click here if you are not redirected">
                                 "Click here if you are not redirected"
                             </a>
                         </p>
@@ -823,3 +2958,2044 @@ fn redirect(to_url: &str) -> paxhtml::Document {
         },
     ])
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{MockLoader, render_wikitext, temp_dir};
+
+    #[test]
+    fn test_non_ascii_titles_get_consistent_routes() {
+        let config = Config::default();
+        assert_eq!(
+            page_url(&config, "Lua/Server/Vehicle"),
+            "/wiki/Lua/Server/Vehicle.html"
+        );
+        assert_eq!(
+            page_url(&config, "Guías/Vehículo rápido"),
+            "/wiki/Guias/Vehiculo_rapido.html"
+        );
+        assert_eq!(
+            page_url(&config, "Транспорт"),
+            "/wiki/%D0%A2%D1%80%D0%B0%D0%BD%D1%81%D0%BF%D0%BE%D1%80%D1%82.html"
+        );
+    }
+
+    #[test]
+    fn test_route_collision_is_an_error() {
+        let dir = temp_dir("route_collision");
+        fs::write(dir.join("Foo.wikitext"), "First").unwrap();
+        fs::write(dir.join("foo.wikitext"), "Second").unwrap();
+
+        let pwt_configuration = wikitext_simplified::wikitext_util::wikipedia_pwt_configuration();
+        let mut files = vec![];
+        discover_wiki_folder(&dir, &Config::default(), "", &mut files).unwrap();
+        let pages = files
+            .into_iter()
+            .map(|file| read_source_page(file, &pwt_configuration))
+            .collect::<anyhow::Result<Vec<_>>>()
+            .unwrap();
+
+        let error = check_route_collisions(&pages).unwrap_err().to_string();
+        assert!(error.contains("foo:"), "{error}");
+        assert!(error.contains("Foo.wikitext"), "{error}");
+        assert!(error.contains("foo.wikitext"), "{error}");
+    }
+
+    #[test]
+    fn test_build_summary_and_empty_wiki() {
+        let dir = temp_dir("build_summary");
+        let src = dir.join("wiki");
+        fs::create_dir_all(&src).unwrap();
+        let dst = dir.join("output").join(WIKI_DIRECTORY);
+
+        // An empty wiki directory fails the build
+        assert!(generate_wiki(Config::default(), &cli::Args::default(), &src, &dst).is_err());
+
+        fs::write(src.join("Main_Page.wikitext"), "{{Greeting}}").unwrap();
+        fs::write(src.join("Greeting.wikitext"), "Hello").unwrap();
+        fs::write(src.join("Home.wikitext"), "#REDIRECT [[Main Page]]").unwrap();
+        let site = generate_wiki(Config::default(), &cli::Args::default(), &src, &dst).unwrap();
+        assert_eq!(
+            site.summary,
+            site::BuildSummary {
+                pages: 2,
+                redirects: 1,
+                templates: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn test_main_page_is_configurable() {
+        let dir = temp_dir("main_page");
+        let src = dir.join("wiki");
+        fs::create_dir_all(&src).unwrap();
+        fs::write(src.join("Main_Page.wikitext"), "Welcome").unwrap();
+        fs::write(src.join("Home.wikitext"), "Home sweet home").unwrap();
+        let dst = dir.join("output").join(WIKI_DIRECTORY);
+
+        let config = Config {
+            main_page: "Home".to_string(),
+            ..Config::default()
+        };
+        generate_wiki(config, &cli::Args::default(), &src, &dst).unwrap();
+
+        let root = fs::read_to_string(dst.join("index.html")).unwrap();
+        assert!(root.contains("/wiki/Home.html"), "{root}");
+        assert!(!root.contains("Main_Page"), "{root}");
+
+        // The home page's breadcrumbs are just the home link
+        let home = fs::read_to_string(dst.join("Home.html")).unwrap();
+        assert!(
+            !home.contains(r#"<span class="text-gray-400"> / </span>"#),
+            "{home}"
+        );
+    }
+
+    #[test]
+    fn test_redirect_targets_directory_with_clean_urls() {
+        let dir = temp_dir("clean_url_redirect");
+        let src = dir.join("wiki");
+        fs::create_dir_all(&src).unwrap();
+        fs::write(src.join("Main_Page.wikitext"), "Welcome").unwrap();
+        fs::write(src.join("New_Page.wikitext"), "Content").unwrap();
+        fs::write(src.join("Old_Page.wikitext"), "#REDIRECT [[New Page]]").unwrap();
+
+        let config = Config {
+            clean_urls: true,
+            ..Config::default()
+        };
+        let dst = dir.join("output").join(WIKI_DIRECTORY);
+        generate_wiki(config, &cli::Args::default(), &src, &dst).unwrap();
+
+        let old_page = fs::read_to_string(dst.join("Old_Page/index.html")).unwrap();
+        assert!(old_page.contains("url=/wiki/New_Page/\""), "{old_page}");
+
+        let root = fs::read_to_string(dst.join("index.html")).unwrap();
+        assert!(root.contains("url=/wiki/Main_Page/\""), "{root}");
+        assert!(!dst.join("New_Page.html").exists());
+    }
+
+    #[test]
+    fn test_frontmatter_extra_css_is_linked() {
+        let dir = temp_dir("extra_css");
+        let src = dir.join("wiki");
+        fs::create_dir_all(&src).unwrap();
+        fs::write(
+            src.join("Map.wikitext"),
+            "---\nextra_css: css/map.css\n---\nThe map.",
+        )
+        .unwrap();
+        fs::write(src.join("Main_Page.wikitext"), "Welcome").unwrap();
+
+        let dst = dir.join("output").join(WIKI_DIRECTORY);
+        let site = generate_wiki(Config::default(), &cli::Args::default(), &src, &dst).unwrap();
+
+        let map = fs::read_to_string(dst.join("Map.html")).unwrap();
+        assert!(map.contains(r#"href="/css/map.css""#), "{map}");
+        assert!(!map.contains("extra_css"), "{map}");
+        let main_page = fs::read_to_string(dst.join("Main_Page.html")).unwrap();
+        assert!(!main_page.contains("/css/map.css"), "{main_page}");
+
+        // The stylesheet doesn't exist in the static directory
+        assert_eq!(site.warnings.len(), 1);
+    }
+
+    /// Write the simplified wikitext of every page in `src` as JSON to `dst`, as
+    /// `--output-json-only` does
+    fn generate_json(src: &Path, dst: &Path, json_format: cli::JsonFormat) -> anyhow::Result<()> {
+        let args = cli::Args {
+            json_format,
+            ..cli::Args::default()
+        };
+        let target = cli::Target {
+            kind: cli::TargetKind::Json,
+            dir: dst.to_path_buf(),
+        };
+        generate_targets(Config::default(), &args, src, &[target]).map(|_| ())
+    }
+
+    #[test]
+    fn test_json_only_output() {
+        let dir = temp_dir("json_only");
+        let src = dir.join("wiki");
+        fs::create_dir_all(src.join("Lua")).unwrap();
+        fs::write(src.join("Main_Page.wikitext"), "Welcome").unwrap();
+        fs::write(src.join("Lua/Vehicle.wikitext"), "'''Vehicles'''").unwrap();
+        let dst = dir.join("output").join(WIKI_DIRECTORY);
+
+        generate_json(&src, &dst, cli::JsonFormat::default()).unwrap();
+
+        let json = fs::read_to_string(dst.join("Lua/Vehicle.json")).unwrap();
+        let nodes: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert!(
+            nodes.as_array().is_some_and(|nodes| !nodes.is_empty()),
+            "{json}"
+        );
+        assert!(dst.join("Main_Page.json").exists());
+
+        let mut outputs = vec![];
+        let mut directories = vec![dir.join("output")];
+        while let Some(directory) = directories.pop() {
+            for entry in fs::read_dir(directory).unwrap() {
+                let path = entry.unwrap().path();
+                if path.is_dir() {
+                    directories.push(path);
+                } else {
+                    outputs.push(path);
+                }
+            }
+        }
+        assert_eq!(outputs.len(), 2, "{outputs:?}");
+        assert!(
+            outputs
+                .iter()
+                .all(|path| path.extension().is_some_and(|e| e == "json"))
+        );
+    }
+
+    #[test]
+    fn test_one_run_builds_site_and_json_targets() {
+        let dir = temp_dir("multiple_targets");
+        let src = dir.join("wiki");
+        fs::create_dir_all(src.join("Lua")).unwrap();
+        fs::write(src.join("Main_Page.wikitext"), "See [[Lua/Vehicle]].").unwrap();
+        fs::write(src.join("Lua/Vehicle.wikitext"), "A '''vehicle'''.").unwrap();
+
+        let args = cli::Args::parse([
+            "--target".to_string(),
+            format!("site:{}", dir.join("site").display()),
+            "--target".to_string(),
+            format!("json:{}", dir.join("json").display()),
+        ])
+        .unwrap();
+        let site = generate_targets(Config::default(), &args, &src, &args.targets())
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(site.summary.pages, 2);
+        let html = fs::read_to_string(dir.join("site").join(WIKI_DIRECTORY).join("Main_Page.html"))
+            .unwrap();
+        assert!(html.contains("Lua/Vehicle.html"), "{html}");
+        let json = fs::read_to_string(dir.join("json/Lua/Vehicle.json")).unwrap();
+        assert!(json.contains("vehicle"), "{json}");
+        // The export only has the JSON, not the site's HTML
+        assert!(!dir.join("json/Main_Page.html").exists());
+    }
+
+    #[test]
+    fn test_sitemap_lists_pages_but_not_redirects() {
+        let dir = temp_dir("sitemap");
+        let src = dir.join("wiki");
+        fs::create_dir_all(src.join("Lua")).unwrap();
+        fs::write(src.join("Main_Page.wikitext"), "See [[Lua/Vehicle]].").unwrap();
+        fs::write(src.join("Lua/Vehicle.wikitext"), "A '''vehicle'''.").unwrap();
+        fs::write(src.join("Old_Page.wikitext"), "#REDIRECT [[Main Page]]").unwrap();
+        let dst = dir.join("output").join(WIKI_DIRECTORY);
+
+        let config = Config {
+            base_url: Some("https://jc-mp.com/".to_string()),
+            ..Config::default()
+        };
+        generate_wiki(config, &cli::Args::default(), &src, &dst).unwrap();
+
+        let sitemap = fs::read_to_string(dir.join("output").join(robots::SITEMAP_FILE)).unwrap();
+        let locs = sitemap
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix("<url><loc>"))
+            .filter_map(|line| line.strip_suffix("</loc></url>"))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            locs,
+            [
+                "https://jc-mp.com/wiki/Lua.html",
+                "https://jc-mp.com/wiki/Lua/Vehicle.html",
+                "https://jc-mp.com/wiki/Main_Page.html",
+            ],
+            "{sitemap}"
+        );
+
+        // Without a configured base URL, the URLs are still absolute
+        fs::remove_dir_all(dir.join("output")).unwrap();
+        let site = generate_wiki(Config::default(), &cli::Args::default(), &src, &dst).unwrap();
+        let sitemap = fs::read_to_string(dir.join("output").join(robots::SITEMAP_FILE)).unwrap();
+        assert!(
+            sitemap.contains(&format!("<loc>{BASE_URL}/wiki/Main_Page.html</loc>")),
+            "{sitemap}"
+        );
+        assert!(site.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_compact_json_is_minified() {
+        let dir = temp_dir("compact_json");
+        let src = dir.join("wiki");
+        fs::create_dir_all(&src).unwrap();
+        fs::write(
+            src.join("Main_Page.wikitext"),
+            "== Welcome ==\n'''Vehicles'''",
+        )
+        .unwrap();
+        let dst = dir.join("output").join(WIKI_DIRECTORY);
+
+        generate_json(&src, &dst, cli::JsonFormat::Pretty).unwrap();
+        let pretty = fs::read_to_string(dst.join("Main_Page.json")).unwrap();
+        generate_json(&src, &dst, cli::JsonFormat::Compact).unwrap();
+        let compact = fs::read_to_string(dst.join("Main_Page.json")).unwrap();
+
+        assert!(pretty.contains('\n'), "{pretty}");
+        assert!(!compact.contains('\n'), "{compact}");
+        assert!(compact.len() < pretty.len());
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&compact).unwrap(),
+            serde_json::from_str::<serde_json::Value>(&pretty).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_build_profile_selects_content() {
+        let wikitext = "{{#ifeq:{{BUILDPROFILE}}|developer|Internal notes|Player guide}}";
+        let render = |build_profile: &str| {
+            let site = Site::new(
+                Config {
+                    build_profile: build_profile.to_string(),
+                    ..Config::default()
+                },
+                Default::default(),
+                Default::default(),
+            );
+            render_wikitext(&site, MockLoader::new(), "Test", wikitext)
+        };
+
+        let html = render("developer");
+        assert!(html.contains("Internal notes"), "{html}");
+        assert!(!html.contains("Player guide"), "{html}");
+
+        let html = render("player");
+        assert!(html.contains("Player guide"), "{html}");
+        assert!(!html.contains("Internal notes"), "{html}");
+    }
+
+    #[test]
+    fn test_child_pages_lists_direct_subpages() {
+        let site = Site::new(
+            Config::default(),
+            [
+                "Hub",
+                "Hub/Vehicles",
+                "Hub/Weapons",
+                "Hub/Weapons/Pistol",
+                "Other",
+            ]
+            .map(String::from)
+            .into(),
+            Default::default(),
+        );
+
+        let html = render_wikitext(&site, MockLoader::new(), "Hub", "{{ChildPages}}");
+        assert!(html.contains("href=\"/wiki/Hub/Vehicles.html\""), "{html}");
+        assert!(html.contains("href=\"/wiki/Hub/Weapons.html\""), "{html}");
+        assert!(!html.contains("Pistol"), "{html}");
+        assert!(!html.contains("Other"), "{html}");
+
+        let html = render_wikitext(&site, MockLoader::new(), "Other", "{{Subpages}}");
+        assert!(!html.contains("<ul"), "{html}");
+    }
+
+    #[test]
+    fn test_llms_txt_digest() {
+        let dir = temp_dir("llms_txt");
+        let src = dir.join("wiki");
+        fs::create_dir_all(src.join("Lua")).unwrap();
+        fs::write(src.join("Main_Page.wikitext"), "Welcome").unwrap();
+        fs::write(
+            src.join("Lua/Vehicle.wikitext"),
+            "'''Vehicles''' can be driven.",
+        )
+        .unwrap();
+        fs::write(src.join("Car.wikitext"), "#REDIRECT [[Lua/Vehicle]]").unwrap();
+        let output = dir.join("output");
+
+        let config = Config {
+            llms_txt: true,
+            ..Config::default()
+        };
+        generate_wiki(
+            config,
+            &cli::Args::default(),
+            &src,
+            &output.join(WIKI_DIRECTORY),
+        )
+        .unwrap();
+
+        let digest = fs::read_to_string(output.join(llms::LLMS_FULL_TXT)).unwrap();
+        assert!(digest.contains("## Lua/Vehicle"), "{digest}");
+        assert!(digest.contains("Vehicles can be driven."), "{digest}");
+        assert!(!digest.contains("## Car"), "{digest}");
+        // Pages are ordered by title
+        assert!(digest.find("## Lua/Vehicle") < digest.find("## Main Page"));
+
+        let index = fs::read_to_string(output.join(llms::LLMS_TXT)).unwrap();
+        assert!(
+            index.contains("- [Lua/Vehicle](/wiki/Lua/Vehicle.html)"),
+            "{index}"
+        );
+    }
+
+    #[test]
+    fn test_feed_lists_recently_updated_pages() {
+        let dir = temp_dir("feed");
+        let src = dir.join("wiki");
+        fs::create_dir_all(&src).unwrap();
+        fs::write(src.join("Main_Page.wikitext"), "Welcome").unwrap();
+        fs::write(
+            src.join("Vehicle.wikitext"),
+            "'''Vehicles''' can be driven.",
+        )
+        .unwrap();
+        let output = dir.join("output");
+
+        let config = Config {
+            base_url: Some("https://docs.example.com".to_string()),
+            ..Config::default()
+        };
+        generate_wiki(
+            config,
+            &cli::Args::default(),
+            &src,
+            &output.join(WIKI_DIRECTORY),
+        )
+        .unwrap();
+
+        let feed = fs::read_to_string(output.join(feed::FEED_FILE)).unwrap();
+        assert!(feed.contains("<title>Vehicle</title>"), "{feed}");
+        assert!(
+            feed.contains(r#"<link href="https://docs.example.com/wiki/Vehicle.html" />"#),
+            "{feed}"
+        );
+        assert!(
+            feed.contains("<summary>Vehicles can be driven.</summary>"),
+            "{feed}"
+        );
+
+        let page = fs::read_to_string(output.join("wiki/Vehicle.html")).unwrap();
+        assert!(page.contains(r#"type="application/atom+xml""#), "{page}");
+
+        // Atom ids have to be absolute, so they use `BASE_URL` without a configured one
+        fs::remove_dir_all(&output).unwrap();
+        generate_wiki(
+            Config::default(),
+            &cli::Args::default(),
+            &src,
+            &output.join(WIKI_DIRECTORY),
+        )
+        .unwrap();
+        let feed = fs::read_to_string(output.join(feed::FEED_FILE)).unwrap();
+        assert!(
+            feed.contains(&format!("<id>{BASE_URL}/{}</id>", feed::FEED_FILE)),
+            "{feed}"
+        );
+        assert!(
+            feed.contains(&format!("<id>{BASE_URL}/wiki/Vehicle.html</id>")),
+            "{feed}"
+        );
+    }
+
+    #[test]
+    fn test_after_content_is_only_added_to_content_pages() {
+        let dir = temp_dir("after_content");
+        let src = dir.join("wiki");
+        fs::create_dir_all(&src).unwrap();
+        fs::write(src.join("Main_Page.wikitext"), "Welcome").unwrap();
+        fs::write(src.join("Home.wikitext"), "#REDIRECT [[Main Page]]").unwrap();
+        let after_content_file = dir.join("after_content.html");
+        fs::write(&after_content_file, r#"<div id="comments"></div>"#).unwrap();
+        let dst = dir.join("output").join(WIKI_DIRECTORY);
+
+        let config = Config {
+            after_content_file: Some(after_content_file.display().to_string()),
+            ..Config::default()
+        };
+        generate_wiki(config, &cli::Args::default(), &src, &dst).unwrap();
+
+        let main_page = fs::read_to_string(dst.join("Main_Page.html")).unwrap();
+        assert!(
+            main_page.contains(r#"<div id="comments"></div>"#),
+            "{main_page}"
+        );
+        let redirect = fs::read_to_string(dst.join("Home.html")).unwrap();
+        assert!(!redirect.contains("comments"), "{redirect}");
+        let recent_changes = fs::read_to_string(dst.join("Special/RecentChanges.html")).unwrap();
+        assert!(!recent_changes.contains("comments"), "{recent_changes}");
+    }
+
+    #[test]
+    fn test_source_view_shows_escaped_wikitext() {
+        let dir = temp_dir("source_view");
+        let src = dir.join("wiki");
+        fs::create_dir_all(&src).unwrap();
+        fs::write(
+            src.join("Main_Page.wikitext"),
+            "Use <code>Vehicle</code> & [[Main Page|links]].",
+        )
+        .unwrap();
+        let escaped = "Use &lt;code&gt;Vehicle&lt;/code&gt; &amp; [[Main Page|links]].";
+
+        let dst = dir.join("details").join(WIKI_DIRECTORY);
+        let config = Config {
+            source_view: config::SourceView::Details,
+            ..Config::default()
+        };
+        generate_wiki(config, &cli::Args::default(), &src, &dst).unwrap();
+        let main_page = fs::read_to_string(dst.join("Main_Page.html")).unwrap();
+        let details = &main_page[main_page.find("<details").unwrap()..];
+        assert!(details.contains(escaped), "{main_page}");
+
+        let dst = dir.join("page").join(WIKI_DIRECTORY);
+        let config = Config {
+            source_view: config::SourceView::Page,
+            ..Config::default()
+        };
+        generate_wiki(config, &cli::Args::default(), &src, &dst).unwrap();
+        let main_page = fs::read_to_string(dst.join("Main_Page.html")).unwrap();
+        assert!(
+            main_page.contains(r#"href="/wiki/Main_Page.source.html""#),
+            "{main_page}"
+        );
+        let source = fs::read_to_string(dst.join("Main_Page.source.html")).unwrap();
+        assert!(source.contains(escaped), "{source}");
+    }
+
+    #[test]
+    fn test_analytics_are_only_added_to_content_pages() {
+        let dir = temp_dir("analytics");
+        let src = dir.join("wiki");
+        fs::create_dir_all(&src).unwrap();
+        fs::write(src.join("Main_Page.wikitext"), "Welcome").unwrap();
+        fs::write(src.join("Home.wikitext"), "#REDIRECT [[Main Page]]").unwrap();
+        let script = "https://plausible.io/js/script.js";
+
+        let dst = dir.join("without").join(WIKI_DIRECTORY);
+        generate_wiki(Config::default(), &cli::Args::default(), &src, &dst).unwrap();
+        let main_page = fs::read_to_string(dst.join("Main_Page.html")).unwrap();
+        assert!(!main_page.contains(script), "{main_page}");
+
+        let dst = dir.join("with").join(WIKI_DIRECTORY);
+        let config = Config {
+            analytics_script: Some(script.to_string()),
+            analytics_attributes: [("data-domain".to_string(), "wiki.jc-mp.com".to_string())]
+                .into(),
+            ..Config::default()
+        };
+        generate_wiki(config, &cli::Args::default(), &src, &dst).unwrap();
+        let main_page = fs::read_to_string(dst.join("Main_Page.html")).unwrap();
+        assert!(main_page.contains(script), "{main_page}");
+        assert!(
+            main_page.contains(r#"data-domain="wiki.jc-mp.com""#),
+            "{main_page}"
+        );
+        let redirect = fs::read_to_string(dst.join("Home.html")).unwrap();
+        assert!(!redirect.contains(script), "{redirect}");
+        let recent_changes = fs::read_to_string(dst.join("Special/RecentChanges.html")).unwrap();
+        assert!(!recent_changes.contains(script), "{recent_changes}");
+    }
+
+    #[test]
+    fn test_relative_links_resolve_from_nested_pages() {
+        let dir = temp_dir("relative_links");
+        let src = dir.join("wiki");
+        fs::create_dir_all(src.join("Lua")).unwrap();
+        fs::write(src.join("Main_Page.wikitext"), "Welcome").unwrap();
+        fs::write(
+            src.join("Lua/Server.wikitext"),
+            "See [[Lua/Client#Events|the client]].",
+        )
+        .unwrap();
+        fs::write(src.join("Lua/Client.wikitext"), "== Events ==").unwrap();
+        let dst = dir.join("output").join(WIKI_DIRECTORY);
+
+        let config = Config {
+            relative_links: true,
+            ..Config::default()
+        };
+        generate_wiki(config, &cli::Args::default(), &src, &dst).unwrap();
+
+        let server = fs::read_to_string(dst.join("Lua/Server.html")).unwrap();
+        assert!(server.contains(r#"href="Client.html#events""#), "{server}");
+        assert!(
+            server.contains(r#"href="../../style/tailwind.css""#),
+            "{server}"
+        );
+        assert!(!server.contains(r#"href="/wiki/"#), "{server}");
+        assert!(
+            server.contains(r#"data-index="../../search-index.json""#),
+            "{server}"
+        );
+
+        // The URLs in the search index are relative to it
+        let index = fs::read_to_string(dir.join("output").join(search::SEARCH_INDEX)).unwrap();
+        assert!(index.contains(r#""wiki/Lua/Server.html":"#), "{index}");
+        assert!(!index.contains(r#""/wiki/"#), "{index}");
+    }
+
+    #[test]
+    fn test_aliases_redirect_to_their_page() {
+        let dir = temp_dir("aliases");
+        let src = dir.join("wiki");
+        fs::create_dir_all(src.join("Lua")).unwrap();
+        fs::write(src.join("Main_Page.wikitext"), "Welcome").unwrap();
+        fs::write(
+            src.join("Lua/Vehicle.wikitext"),
+            "---\naliases: [Car, Lua/Automobile]\n---\n'''Vehicles''' can be driven.",
+        )
+        .unwrap();
+        let dst = dir.join("output").join(WIKI_DIRECTORY);
+
+        generate_wiki(Config::default(), &cli::Args::default(), &src, &dst).unwrap();
+
+        for alias in ["Car.html", "Lua/Automobile.html"] {
+            let stub = fs::read_to_string(dst.join(alias)).unwrap();
+            assert!(stub.contains("url=/wiki/Lua/Vehicle.html"), "{stub}");
+        }
+
+        // An alias can't take the title of a page
+        fs::write(src.join("Car.wikitext"), "A different page").unwrap();
+        let error = generate_wiki(Config::default(), &cli::Args::default(), &src, &dst)
+            .err()
+            .unwrap();
+        assert!(error.to_string().contains("alias Car"), "{error}");
+    }
+
+    #[test]
+    fn test_no_js_builds_have_no_scripts() {
+        let dir = temp_dir("no_js");
+        let src = dir.join("wiki");
+        fs::create_dir_all(&src).unwrap();
+        fs::write(
+            src.join("Main_Page.wikitext"),
+            "---\nextra_js: console.log('hi');\n---\n== A ==\n<math>x^2</math>\n== B ==\n== C ==\n== D ==\n{| class=\"wikitable\"\n!Prototype\n|-\n|Execute()\n|}",
+        )
+        .unwrap();
+        let dst = dir.join("output").join(WIKI_DIRECTORY);
+
+        let config = Config {
+            scripts: false,
+            ..Config::default()
+        };
+        generate_wiki(config, &cli::Args::default(), &src, &dst).unwrap();
+
+        fn html_files(dir: &Path, files: &mut Vec<PathBuf>) {
+            for entry in fs::read_dir(dir).unwrap() {
+                let path = entry.unwrap().path();
+                if path.is_dir() {
+                    html_files(&path, files);
+                } else if path.extension().is_some_and(|ext| ext == "html") {
+                    files.push(path);
+                }
+            }
+        }
+        let mut files = vec![];
+        html_files(&dst, &mut files);
+        assert!(files.len() >= 3, "{files:?}");
+        for file in files {
+            let html = fs::read_to_string(&file).unwrap();
+            assert!(!html.contains("<script"), "{}: {html}", file.display());
+        }
+
+        let main_page = fs::read_to_string(dst.join("Main_Page.html")).unwrap();
+        assert!(main_page.contains(r#"id="on-this-page""#), "{main_page}");
+        // Formulae are rendered to MathML instead of by KaTeX
+        assert!(
+            main_page.contains(r#"<msup><mi>x</mi><mn>2</mn></msup></math>"#),
+            "{main_page}"
+        );
+    }
+
+    #[test]
+    fn test_content_hash_changes_with_content() {
+        let dir = temp_dir("content_hash");
+        let src = dir.join("wiki");
+        fs::create_dir_all(&src).unwrap();
+        fs::write(src.join("Main_Page.wikitext"), "Welcome").unwrap();
+        fs::write(src.join("Other.wikitext"), "Unrelated").unwrap();
+        let dst = dir.join("output").join(WIKI_DIRECTORY);
+
+        let config = || Config {
+            content_hash: true,
+            ..Config::default()
+        };
+        let hash = |page: &str| {
+            let html = fs::read_to_string(dst.join(page)).unwrap();
+            let prefix = format!(r#"<meta name="{}" content=""#, content_hash::META_NAME);
+            let start = html.find(&prefix).unwrap() + prefix.len();
+            html[start..start + 16].to_string()
+        };
+
+        generate_wiki(config(), &cli::Args::default(), &src, &dst).unwrap();
+        let (main_page, other) = (hash("Main_Page.html"), hash("Other.html"));
+        assert_ne!(main_page, other);
+
+        fs::write(src.join("Main_Page.wikitext"), "Welcome back").unwrap();
+        generate_wiki(config(), &cli::Args::default(), &src, &dst).unwrap();
+        assert_ne!(hash("Main_Page.html"), main_page);
+        assert_eq!(hash("Other.html"), other);
+
+        generate_wiki(Config::default(), &cli::Args::default(), &src, &dst).unwrap();
+        let html = fs::read_to_string(dst.join("Main_Page.html")).unwrap();
+        assert!(!html.contains(content_hash::META_NAME), "{html}");
+    }
+
+    #[test]
+    fn test_long_pages_list_their_headings() {
+        let dir = temp_dir("on_this_page");
+        let src = dir.join("wiki");
+        fs::create_dir_all(&src).unwrap();
+        fs::write(
+            src.join("Main_Page.wikitext"),
+            "== Usage ==\nA\n=== Server ===\nB\n== Examples ==\nC\n== Usage ==\nD",
+        )
+        .unwrap();
+        fs::write(
+            src.join("Short.wikitext"),
+            "== Usage ==\nA\n== Examples ==\nB",
+        )
+        .unwrap();
+        let dst = dir.join("output").join(WIKI_DIRECTORY);
+
+        generate_wiki(Config::default(), &cli::Args::default(), &src, &dst).unwrap();
+
+        let main_page = fs::read_to_string(dst.join("Main_Page.html")).unwrap();
+        assert!(main_page.contains(r#"id="on-this-page""#), "{main_page}");
+        assert!(main_page.contains(r#"src="/js/toc.js""#), "{main_page}");
+        for id in ["usage", "server", "examples", "usage-2"] {
+            assert!(main_page.contains(&format!(r#"id="{id}""#)), "{main_page}");
+            assert!(
+                main_page.contains(&format!(r##"href="#{id}""##)),
+                "{main_page}"
+            );
+        }
+
+        let short = fs::read_to_string(dst.join("Short.html")).unwrap();
+        assert!(short.contains(r#"id="usage""#), "{short}");
+        assert!(!short.contains("on-this-page"), "{short}");
+        assert!(!short.contains("toc.js"), "{short}");
+    }
+
+    #[test]
+    fn test_forcetoc_lists_headings_of_short_pages() {
+        let dir = temp_dir("forcetoc");
+        let src = dir.join("wiki");
+        fs::create_dir_all(&src).unwrap();
+        fs::write(
+            src.join("Main_Page.wikitext"),
+            "__FORCETOC__\n== Usage ==\nA\n== Examples ==\nB",
+        )
+        .unwrap();
+        fs::write(
+            src.join("Hidden.wikitext"),
+            "__FORCETOC__ __NOTOC__\n== Usage ==\nA\n== Examples ==\nB",
+        )
+        .unwrap();
+        let dst = dir.join("output").join(WIKI_DIRECTORY);
+
+        generate_wiki(Config::default(), &cli::Args::default(), &src, &dst).unwrap();
+
+        let main_page = fs::read_to_string(dst.join("Main_Page.html")).unwrap();
+        assert!(main_page.contains(r#"id="on-this-page""#), "{main_page}");
+        assert!(!main_page.contains("FORCETOC"), "{main_page}");
+
+        let hidden = fs::read_to_string(dst.join("Hidden.html")).unwrap();
+        assert!(!hidden.contains("on-this-page"), "{hidden}");
+        assert!(!hidden.contains("NOTOC"), "{hidden}");
+    }
+
+    #[test]
+    fn test_categories_become_keywords_meta() {
+        let dir = temp_dir("keywords_meta");
+        let src = dir.join("wiki");
+        fs::create_dir_all(&src).unwrap();
+        fs::write(
+            src.join("Main_Page.wikitext"),
+            "Welcome\n[[Category:Guides]]\n[[Category:Lua]]\n[[Category:Guides]]",
+        )
+        .unwrap();
+        fs::write(src.join("Other.wikitext"), "See [[:Category:Guides]]").unwrap();
+        let dst = dir.join("output").join(WIKI_DIRECTORY);
+
+        let config = Config {
+            keywords_meta: true,
+            ..Config::default()
+        };
+        generate_wiki(config, &cli::Args::default(), &src, &dst).unwrap();
+        let main_page = fs::read_to_string(dst.join("Main_Page.html")).unwrap();
+        assert!(
+            main_page.contains(r#"<meta name="keywords" content="Guides, Lua">"#),
+            "{main_page}"
+        );
+        let other = fs::read_to_string(dst.join("Other.html")).unwrap();
+        assert!(!other.contains(r#"name="keywords""#), "{other}");
+
+        generate_wiki(Config::default(), &cli::Args::default(), &src, &dst).unwrap();
+        let main_page = fs::read_to_string(dst.join("Main_Page.html")).unwrap();
+        assert!(!main_page.contains(r#"name="keywords""#), "{main_page}");
+    }
+
+    #[test]
+    fn test_translations_are_cross_linked() {
+        let site = Site::new(
+            Config {
+                translation_languages: vec!["de".to_string()],
+                ..Config::default()
+            },
+            ["Guide", "Guide/de", "Other"].map(String::from).into(),
+            Default::default(),
+        );
+
+        for title in ["Guide", "Guide/de"] {
+            let html = layout(
+                &site,
+                title,
+                vec![],
+                paxhtml::html! { <p>"Content"</p> },
+                &Default::default(),
+            )
+            .write_to_string()
+            .unwrap();
+            assert!(
+                html.contains(r#"<link rel="alternate" hreflang="en" href="/wiki/Guide.html""#),
+                "{html}"
+            );
+            assert!(
+                html.contains(r#"<link rel="alternate" hreflang="de" href="/wiki/Guide/de.html""#),
+                "{html}"
+            );
+        }
+
+        let html = layout(
+            &site,
+            "Guide/de",
+            vec![],
+            paxhtml::html! { <p>"Content"</p> },
+            &Default::default(),
+        )
+        .write_to_string()
+        .unwrap();
+        assert!(html.contains(r#"<html lang="de""#), "{html}");
+
+        let html = layout(
+            &site,
+            "Other",
+            vec![],
+            paxhtml::html! { <p>"Content"</p> },
+            &Default::default(),
+        )
+        .write_to_string()
+        .unwrap();
+        assert!(!html.contains("hreflang"), "{html}");
+    }
+
+    #[test]
+    fn test_theme_color_is_configurable() {
+        let render = |config: Config| {
+            let site = Site::new(config, Default::default(), Default::default());
+            layout(
+                &site,
+                "Page",
+                vec![],
+                paxhtml::html! { <p>"Content"</p> },
+                &Default::default(),
+            )
+            .write_to_string()
+            .unwrap()
+        };
+
+        let html = render(Config {
+            theme_color: Some("#2563eb".to_string()),
+            ..Config::default()
+        });
+        assert!(
+            html.contains(r##"<meta name="theme-color" content="#2563eb">"##),
+            "{html}"
+        );
+        assert!(html.contains("apple-mobile-web-app-capable"), "{html}");
+
+        let html = render(Config {
+            theme_color: Some("#ffffff".to_string()),
+            theme_color_dark: Some("#111827".to_string()),
+            mobile_web_app: false,
+            ..Config::default()
+        });
+        assert!(
+            html.contains(r##"content="#ffffff" media="(prefers-color-scheme: light)""##),
+            "{html}"
+        );
+        assert!(
+            html.contains(r##"content="#111827" media="(prefers-color-scheme: dark)""##),
+            "{html}"
+        );
+        assert!(!html.contains("apple-mobile-web-app"), "{html}");
+
+        let html = render(Config {
+            theme_color: None,
+            ..Config::default()
+        });
+        assert!(!html.contains("theme-color"), "{html}");
+    }
+
+    #[test]
+    fn test_table_header_cells_have_a_scope() {
+        let site = Site::new(Config::default(), Default::default(), Default::default());
+        let html = render_wikitext(
+            &site,
+            MockLoader::new(),
+            "Test",
+            "{|\n!Name\n! scope=\"colgroup\" | Value\n! data-scope=\"x\" | Notes\n|-\n!a\n|b\n|c\n|}",
+        );
+        assert_eq!(html.matches(r#"scope="col""#).count(), 2, "{html}");
+        assert!(html.contains(r#"scope="colgroup""#), "{html}");
+        assert!(!html.contains(r#"scope="col" scope"#), "{html}");
+        // A header cell before the data in its row labels the row
+        assert_eq!(html.matches(r#"scope="row""#).count(), 1, "{html}");
+        assert!(!html.contains("<td scope"), "{html}");
+    }
+
+    #[test]
+    fn test_nested_tables_render_inside_their_cell() {
+        let site = Site::new(
+            Config::default(),
+            ["Main Page".to_string()].into(),
+            Default::default(),
+        );
+        let mut loader = MockLoader::new();
+        loader.add(
+            "Layout",
+            "{| class=\"outer\"\n|-\n| '''{{{1}}}'''\n|\n{| class=\"inner\"\n|-\n| [[Main Page|Inner]] cell\n|}\n|}",
+        );
+        let html = render_wikitext(&site, loader, "Test", "{{Layout|Outer}}");
+
+        assert_eq!(html.matches("<table").count(), 2, "{html}");
+        let inner = &html[html.find(r#"class="inner""#).unwrap()..];
+        let outer = &html[..html.find(r#"class="inner""#).unwrap()];
+        assert!(outer.contains(r#"class="outer""#), "{html}");
+        assert!(outer.contains("<strong>Outer</strong>"), "{html}");
+        assert!(
+            inner.contains(r#"href="/wiki/Main_Page.html">Inner</a>"#),
+            "{html}"
+        );
+        // The inner table closes before the outer one
+        assert!(inner.matches("</table>").count() == 2, "{html}");
+    }
+
+    #[test]
+    fn test_only_long_tables_are_marked_for_collapsing() {
+        let site = Site::new(
+            Config {
+                collapse_table_rows: Some(5),
+                ..Config::default()
+            },
+            Default::default(),
+            Default::default(),
+        );
+        let table = |rows: usize| {
+            let rows = (0..rows)
+                .map(|row| format!("|-\n|Row {row}\n"))
+                .collect::<String>();
+            format!("{{|\n!Name\n{rows}|}}")
+        };
+
+        let html = render_wikitext(&site, MockLoader::new(), "Test", &table(8));
+        assert!(html.contains(r#"data-collapse-rows="5""#), "{html}");
+        // Every row is still rendered, for readers without scripts
+        assert!(html.contains("Row 7"), "{html}");
+        let html = render_wikitext(&site, MockLoader::new(), "Test", &table(2));
+        assert!(!html.contains("data-collapse-rows"), "{html}");
+    }
+
+    #[test]
+    fn test_single_page_build_writes_only_that_page() {
+        let dir = temp_dir("single_page");
+        let src = dir.join("wiki");
+        fs::create_dir_all(src.join("Lua")).unwrap();
+        fs::write(src.join("Main_Page.wikitext"), "Welcome").unwrap();
+        fs::write(src.join("Old_Guide.wikitext"), "#REDIRECT [[Guide]]").unwrap();
+        fs::write(
+            src.join("Guide.wikitext"),
+            "See [[Main Page]], [[Old Guide]] and [[Lua]].",
+        )
+        .unwrap();
+        fs::write(src.join("Lua/Vehicle.wikitext"), "Vehicles").unwrap();
+
+        let dst = dir.join("output").join(WIKI_DIRECTORY);
+        let site = generate_single_page(
+            Config::default(),
+            &cli::Args::default(),
+            &src,
+            &dst,
+            "Guide",
+        )
+        .unwrap();
+
+        let mut outputs = fs::read_dir(&dst)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect::<Vec<_>>();
+        outputs.sort();
+        assert_eq!(outputs, ["Guide.html", "Guide.json"]);
+        assert_eq!(fs::read_dir(dir.join("output")).unwrap().count(), 1);
+
+        // Links to pages that weren't built are still resolved
+        assert!(site.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_pipe_trick_and_subpage_links() {
+        let site = Site::new(
+            Config::default(),
+            [
+                "Lua/Server/Vehicle",
+                "Lua/Server/Vehicle/Functions",
+                "Vehicle (disambiguation)",
+            ]
+            .map(String::from)
+            .into(),
+            Default::default(),
+        );
+        let html = render_wikitext(
+            &site,
+            MockLoader::new(),
+            "Lua/Server/Vehicle",
+            "[[Vehicle (disambiguation)|]] [[/Functions]] [[/Functions/]] [[Main Page]]",
+        );
+
+        assert!(html.contains(">Vehicle</a>"), "{html}");
+        assert!(
+            html.contains(r#"href="/wiki/Lua/Server/Vehicle/Functions.html">/Functions</a>"#),
+            "{html}"
+        );
+        assert!(
+            html.contains(r#"href="/wiki/Lua/Server/Vehicle/Functions.html">Functions</a>"#),
+            "{html}"
+        );
+        assert!(html.contains(r#"href="/wiki/Main_Page.html""#), "{html}");
+    }
+
+    #[test]
+    fn test_trailing_slash_policy() {
+        let render = |trailing_slash| {
+            let site = Site::new(
+                Config {
+                    clean_urls: true,
+                    trailing_slash,
+                    base_url: Some("https://jc-mp.com/".to_string()),
+                    ..Config::default()
+                },
+                ["Lua/Server"].map(String::from).into(),
+                Default::default(),
+            );
+            let body = render_wikitext(&site, MockLoader::new(), "Test", "[[Lua/Server]]");
+            let page = layout(
+                &site,
+                "Lua/Server",
+                vec![],
+                paxhtml::html! { <p>"Content"</p> },
+                &Default::default(),
+            )
+            .write_to_string()
+            .unwrap();
+            (body, page)
+        };
+
+        let (body, page) = render(true);
+        assert!(body.contains(r#"href="/wiki/Lua/Server/""#), "{body}");
+        assert!(
+            page.contains(r#"<link rel="canonical" href="https://jc-mp.com/wiki/Lua/Server/""#),
+            "{page}"
+        );
+
+        let (body, page) = render(false);
+        assert!(body.contains(r#"href="/wiki/Lua/Server""#), "{body}");
+        assert!(
+            page.contains(r#"<link rel="canonical" href="https://jc-mp.com/wiki/Lua/Server""#),
+            "{page}"
+        );
+    }
+
+    #[test]
+    fn test_block_templates_are_not_wrapped_in_paragraphs() {
+        let site = Site::new(Config::default(), Default::default(), Default::default());
+        let mut loader = MockLoader::new();
+        loader.add("Box", "{|\n| Boxed\n|}");
+        let html = render_wikitext(
+            &site,
+            loader,
+            "Test",
+            "Some {{color|red|colored}} text\n{{Box}}\nAfter the box\n\nNext paragraph",
+        );
+
+        assert!(
+            html.contains(r#"<p class="mb-4">Some <span style="color:red">colored</span> text"#),
+            "{html}"
+        );
+        assert!(html.contains("</p><table"), "{html}");
+        assert!(!html.contains("<p class=\"mb-4\"><table"), "{html}");
+        assert_eq!(html.matches("<p class=").count(), 3, "{html}");
+    }
+
+    #[test]
+    fn test_content_classes_are_filtered() {
+        let site = Site::new(Config::default(), Default::default(), Default::default());
+        let html = render_wikitext(
+            &site,
+            MockLoader::new(),
+            "Test",
+            r#"<div class="grid grid-cols-2 bg-[url(evil.png)]" id="layout"><span class="text-sm">A</span> B</div>"#,
+        );
+
+        assert!(
+            html.contains(r#"<div id="layout" class="grid grid-cols-2">"#),
+            "{html}"
+        );
+        assert!(html.contains(r#"<span class="text-sm">A</span>"#), "{html}");
+        assert!(!html.contains("evil"), "{html}");
+        assert_eq!(site.warnings.len(), 1);
+    }
+
+    #[test]
+    fn test_plain_tables_are_not_striped() {
+        let site = Site::new(Config::default(), Default::default(), Default::default());
+        let table = |class: &str| {
+            render_wikitext(
+                &site,
+                MockLoader::new(),
+                "Test",
+                &format!("{{| {class}\n|-\n| a || b\n|-\n| c || d\n|}}"),
+            )
+        };
+
+        let html = table(r#"class="plain""#);
+        assert!(!html.contains("bg-gray-50"), "{html}");
+        assert!(!html.contains("bg-white"), "{html}");
+        assert!(!html.contains("bg-gray-800"), "{html}");
+
+        let html = table(r#"class="wikitable""#);
+        assert!(html.contains("bg-gray-50"), "{html}");
+        assert!(html.contains("bg-gray-800"), "{html}");
+    }
+
+    #[test]
+    fn test_math_backends() {
+        let render = |math_backend, wikitext| {
+            let site = Site::new(
+                Config {
+                    math_backend,
+                    ..Config::default()
+                },
+                Default::default(),
+                Default::default(),
+            );
+            render_wikitext(&site, MockLoader::new(), "Test", wikitext)
+        };
+
+        let html = render(config::MathBackend::Katex, "Area: <math>\\pi r^2</math>");
+        assert!(
+            html.contains(r#"<span class="math math-inline">\(\pi r^2\)</span>"#),
+            "{html}"
+        );
+        let html = render(
+            config::MathBackend::Katex,
+            "<math display=\"block\">x^2 + y^2</math>",
+        );
+        assert!(html.contains("math-display"), "{html}");
+        assert!(html.contains(r"\[x^2 + y^2\]"), "{html}");
+
+        let html = render(config::MathBackend::Plain, "<math>x^2</math>");
+        assert!(html.contains(r#"<code class="math">x^2</code>"#), "{html}");
+
+        let html = render(config::MathBackend::MathMl, "Area: <math>\\pi r^2</math>");
+        assert!(
+            html.contains(
+                r#"display="inline" alttext="\pi r^2"><mrow><mi>π</mi><msup><mi>r</mi><mn>2</mn></msup></mrow></math>"#
+            ),
+            "{html}"
+        );
+        // Formulae the converter doesn't support are shown as TeX
+        let html = render(
+            config::MathBackend::MathMl,
+            "<math>\\begin{matrix} a \\end{matrix}</math>",
+        );
+        assert!(html.contains(r#"<code class="math">"#), "{html}");
+    }
+
+    #[test]
+    fn test_key_combination() {
+        let site = Site::new(Config::default(), Default::default(), Default::default());
+        let html = render_wikitext(
+            &site,
+            MockLoader::new(),
+            "Test",
+            "Copy with <kbd>Ctrl</kbd>+<kbd>C</kbd>, or <code>&#91;c&#93;</code>.",
+        );
+
+        assert_eq!(html.matches("<kbd class=").count(), 2, "{html}");
+        assert!(
+            html.contains(r#"Ctrl</kbd><span class="mx-0.5 text-gray-500">+</span><kbd"#),
+            "{html}"
+        );
+        assert!(html.contains("[c]</code>"), "{html}");
+    }
+
+    #[test]
+    fn test_colon_file_links_link_to_the_file() {
+        let dir = temp_dir("colon_file_links");
+        fs::write(dir.join("Map.png"), "").unwrap();
+        let site = Site::new(
+            Config {
+                media_directory: dir.to_string_lossy().to_string(),
+                ..Config::default()
+            },
+            Default::default(),
+            Default::default(),
+        );
+        let html = render_wikitext(
+            &site,
+            MockLoader::new(),
+            "Test",
+            "See [[:File:Map.png]] or [[Media:Server map.png|the server map]].",
+        );
+
+        assert!(
+            html.contains(r#"href="/media/Map.png">File:Map.png</a>"#),
+            "{html}"
+        );
+        assert!(
+            html.contains(r#"href="/media/Server_map.png">the server map</a>"#),
+            "{html}"
+        );
+        assert!(!html.contains("<img"), "{html}");
+        // Only the server map is missing from the media directory
+        assert_eq!(site.warnings.len(), 1);
+    }
+
+    #[test]
+    fn test_inline_code_breaks_long_words_when_enabled() {
+        let source = "Call <code>Player:GetVehicle</code> or <code class=\"math\">x</code>.";
+        let site = Site::new(Config::default(), Default::default(), Default::default());
+        let html = render_wikitext(&site, MockLoader::new(), "Test", source);
+        assert!(html.contains("<code>Player:GetVehicle</code>"), "{html}");
+
+        let site = Site::new(
+            Config {
+                break_long_words: true,
+                ..Config::default()
+            },
+            Default::default(),
+            Default::default(),
+        );
+        let html = render_wikitext(&site, MockLoader::new(), "Test", source);
+        assert!(
+            html.contains(r#"<code class="break-words">Player:GetVehicle</code>"#),
+            "{html}"
+        );
+        assert!(
+            html.contains(r#"<code class="math break-words">x</code>"#),
+            "{html}"
+        );
+    }
+
+    #[test]
+    fn test_terms_are_grouped_with_all_of_their_details() {
+        let site = Site::new(Config::default(), Default::default(), Default::default());
+        let html = render_wikitext(
+            &site,
+            MockLoader::new(),
+            "Test",
+            ";Speed\n:How fast the vehicle is going\n:In metres per second\n;Health\n:Between 0 and 1",
+        );
+
+        let groups = html
+            .split("<div>")
+            .skip(1)
+            .map(|group| {
+                let group = &group[..group.find("</div>").unwrap()];
+                (group.matches("<dt").count(), group.matches("<dd").count())
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(groups, [(1, 2), (1, 1)], "{html}");
+    }
+
+    #[test]
+    fn test_inline_annotations_are_styled() {
+        let site = Site::new(Config::default(), Default::default(), Default::default());
+        let html = render_wikitext(
+            &site,
+            MockLoader::new(),
+            "Test",
+            r#"<mark>text</mark>, <del title="Removed in 0.2">old</del> and <ins class="font-bold">new</ins>"#,
+        );
+
+        assert!(
+            html.contains(r#"<mark class="px-0.5 bg-yellow-200 rounded">text</mark>"#),
+            "{html}"
+        );
+        assert!(
+            html.contains(
+                r#"<del title="Removed in 0.2" class="line-through text-red-800 bg-red-50">old</del>"#
+            ),
+            "{html}"
+        );
+        assert!(
+            html.contains(
+                r#"<ins class="underline text-green-800 bg-green-50 font-bold">new</ins>"#
+            ),
+            "{html}"
+        );
+    }
+
+    #[test]
+    fn test_param_calls_render_a_parameter_table() {
+        let site = Site::new(Config::default(), Default::default(), Default::default());
+        let html = render_wikitext(
+            &site,
+            MockLoader::new(),
+            "Template:Infobox",
+            "Parameters:\n{{Params}}\n{{Param|name|The '''name''' shown|required=yes}}\n{{Param|image|An image}}\n{{Params}}",
+        );
+
+        assert_eq!(html.matches("<table").count(), 1, "{html}");
+        assert_eq!(html.matches("<tr class=\"bg-white\">").count(), 2, "{html}");
+        assert!(html.contains("<strong>name</strong> shown"), "{html}");
+        assert!(html.find(">name</td>") < html.find(">image</td>"), "{html}");
+
+        // A `{{Params}}` before the parameters still lists them where it is
+        let html = render_wikitext(
+            &site,
+            MockLoader::new(),
+            "Template:Infobox",
+            "Parameters:\n{{Params}}\n== Notes ==\n{{Param|name|The name}}\n{{Param|image|An image}}",
+        );
+        assert_eq!(html.matches("<tr class=\"bg-white\">").count(), 2, "{html}");
+        assert!(html.find("<table") < html.find("Notes"), "{html}");
+        assert!(!html.contains("<!--params"), "{html}");
+    }
+
+    #[test]
+    fn test_clear_resets_floats() {
+        let site = Site::new(Config::default(), Default::default(), Default::default());
+        for (wikitext, class) in [
+            ("{{Clear}}", "clear-both"),
+            ("{{-}}", "clear-both"),
+            ("{{clear|left}}", "clear-left"),
+        ] {
+            let html = render_wikitext(&site, MockLoader::new(), "Test", wikitext);
+            assert!(
+                html.contains(&format!(r#"<div class="{class}"></div>"#)),
+                "{html}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_admonitions_are_callouts() {
+        let site = Site::new(Config::default(), Default::default(), Default::default());
+        let html = render_wikitext(
+            &site,
+            MockLoader::new(),
+            "Test",
+            "{{Warning|be careful with '''this'''}}\n{{Admonition|trivia|Did you know?}}",
+        );
+
+        assert!(
+            html.contains(
+                r#"border-red-400 bg-red-50 text-red-900" role="note" aria-label="Warning""#
+            ),
+            "{html}"
+        );
+        assert!(
+            html.contains("be careful with <strong>this</strong>"),
+            "{html}"
+        );
+        assert!(
+            html.contains(
+                r#"border-gray-400 bg-gray-50 text-gray-900" role="note" aria-label="Trivia""#
+            ),
+            "{html}"
+        );
+        assert!(html.contains("Did you know?"), "{html}");
+    }
+
+    #[test]
+    fn test_image_captions_are_rendered_as_wikitext() {
+        let site = Site::new(
+            Config::default(),
+            ["Lua/Server"].map(String::from).into(),
+            Default::default(),
+        );
+        let html = render_wikitext(
+            &site,
+            MockLoader::new(),
+            "Test",
+            "[[File:Map.png|thumb|The '''[[Lua/Server|server]]''' map]]",
+        );
+
+        let caption = &html[html.find("<figcaption").unwrap()..html.find("</figcaption>").unwrap()];
+        assert!(
+            caption.contains(r#"href="/wiki/Lua/Server.html">server</a>"#),
+            "{html}"
+        );
+        assert!(caption.contains("<strong>"), "{html}");
+        assert!(html.contains(r#"alt="The server map""#), "{html}");
+    }
+
+    #[test]
+    fn test_template_namespace_links_route_to_its_directory() {
+        let site = Site::new(
+            Config::default(),
+            ["Template:Infobox"].map(String::from).into(),
+            Default::default(),
+        );
+        let html = render_wikitext(
+            &site,
+            MockLoader::new(),
+            "Test",
+            "See [[Template:Infobox]] and [[template:Infobox|the infobox]].",
+        );
+
+        assert_eq!(
+            html.matches(r#"href="/wiki/Template/Infobox.html""#)
+                .count(),
+            2,
+            "{html}"
+        );
+        assert!(!html.contains("text-red-600"), "{html}");
+    }
+
+    #[test]
+    fn test_namespaced_pages_and_category_pages() {
+        let dir = temp_dir("namespaces");
+        let src = dir.join("wiki");
+        fs::create_dir_all(src.join("Template")).unwrap();
+        fs::write(
+            src.join("Main_Page.wikitext"),
+            "See [[Template:Infobox]].\n[[Category:Guides]]",
+        )
+        .unwrap();
+        fs::write(src.join("Template/Infobox.wikitext"), "An infobox").unwrap();
+        let dst = dir.join("output").join(WIKI_DIRECTORY);
+
+        let site = generate_wiki(Config::default(), &cli::Args::default(), &src, &dst).unwrap();
+
+        assert!(site.page_titles.contains("Template:Infobox"));
+        assert!(dst.join("Template/Infobox.html").exists());
+        let category = fs::read_to_string(dst.join("Category/Guides.html")).unwrap();
+        assert!(
+            category.contains(r#"href="/wiki/Main_Page.html">Main Page</a>"#),
+            "{category}"
+        );
+        assert_eq!(site.warnings.len(), 0);
+    }
+
+    #[test]
+    fn test_links_to_moved_pages_lead_to_the_new_page() {
+        let site = Site::new(
+            Config {
+                moves: [("Old Guide".to_string(), "Lua/Guide".to_string())].into(),
+                ..Config::default()
+            },
+            ["Lua/Guide".to_string()].into(),
+            [("Guide".to_string(), "Old Guide".to_string())].into(),
+        );
+        let html = render_wikitext(
+            &site,
+            MockLoader::new(),
+            "Test",
+            "See [[Old_Guide#Setup|the guide]].",
+        );
+
+        assert!(
+            html.contains(r#"href="/wiki/Lua/Guide.html#setup">the guide</a>"#),
+            "{html}"
+        );
+        assert!(!html.contains("text-red-600"), "{html}");
+        // The link is reported so that it can be updated
+        assert_eq!(site.warnings.len(), 1);
+        // Redirects to the old title lead to the new page too
+        assert!(site.dangling_redirects().is_empty());
+    }
+
+    #[test]
+    fn test_color_template() {
+        let site = Site::new(Config::default(), Default::default(), Default::default());
+
+        let html = render_wikitext(&site, MockLoader::new(), "Test", "{{color|red|danger}}");
+        assert!(
+            html.contains(r#"<span style="color:red">danger</span>"#),
+            "{html}"
+        );
+
+        let html = render_wikitext(
+            &site,
+            MockLoader::new(),
+            "Test",
+            "{{color|#1E90FF|'''bold''' text}}",
+        );
+        assert!(html.contains(r#"<span style="color:#1E90FF">"#), "{html}");
+        assert!(html.contains("<strong>bold</strong>"), "{html}");
+
+        let html = render_wikitext(&site, MockLoader::new(), "Test", "{{color|notacolor|text}}");
+        assert!(!html.contains("<span"), "{html}");
+        assert_eq!(site.warnings.len(), 1);
+    }
+
+    #[test]
+    fn test_builtin_templates_inside_templates() {
+        let site = Site::new(Config::default(), Default::default(), Default::default());
+        let mut loader = MockLoader::new();
+        loader.add(
+            "Callout",
+            "{{Note|{{{1}}}}}{{#if:{{{hot|}}}|{{color|red|hot}}|{{color|blue|cold}}}}",
+        );
+
+        let html = render_wikitext(&site, loader, "Test", "{{Callout|read this|hot=yes}}");
+        assert!(html.contains(r#"aria-label="Note""#), "{html}");
+        assert!(html.contains("read this"), "{html}");
+        assert!(
+            html.contains(r#"<span style="color:red">hot</span>"#),
+            "{html}"
+        );
+        assert!(!html.contains("cold"), "{html}");
+        assert_eq!(site.warnings.len(), 0);
+    }
+
+    #[test]
+    fn test_wiki_templates_take_precedence_over_builtins() {
+        let site = Site::new(Config::default(), Default::default(), Default::default());
+        let mut loader = MockLoader::new();
+        loader.add("Note", "'''Note:''' {{{1}}}");
+
+        let html = render_wikitext(&site, loader, "Test", "{{Note|a}} and ''{{Tip|b}}''");
+        assert!(html.contains("<strong>Note:</strong> a"), "{html}");
+        assert!(!html.contains(r#"aria-label="Note""#), "{html}");
+        assert!(html.contains(r#"aria-label="Tip""#), "{html}");
+        assert_eq!(site.warnings.len(), 0);
+    }
+
+    #[test]
+    fn test_main_article_hatnote() {
+        let site = Site::new(
+            Config::default(),
+            ["Lua/Events", "Lua/Server/Events"].map(String::from).into(),
+            Default::default(),
+        );
+
+        let html = render_wikitext(&site, MockLoader::new(), "Lua", "{{Main|Lua/Events}}");
+        assert!(html.contains("Main article: "), "{html}");
+        assert!(html.contains(r#"href="/wiki/Lua/Events.html""#), "{html}");
+
+        let html = render_wikitext(
+            &site,
+            MockLoader::new(),
+            "Lua",
+            "{{See also|Lua/Events|Lua/Server/Events}}",
+        );
+        assert!(html.contains("See also: "), "{html}");
+        assert!(html.contains(r#"Lua/Events</a>, <a"#), "{html}");
+        assert!(
+            html.contains(r#"href="/wiki/Lua/Server/Events.html""#),
+            "{html}"
+        );
+        assert!(site.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_dangling_redirect_is_reported() {
+        let dir = temp_dir("dangling_redirect");
+        let src = dir.join("wiki");
+        fs::create_dir_all(&src).unwrap();
+        fs::write(src.join("Main_Page.wikitext"), "Welcome").unwrap();
+        fs::write(src.join("Old_Page.wikitext"), "#REDIRECT [[Deleted Page]]").unwrap();
+        // A chain that ends at a real page is fine
+        fs::write(
+            src.join("Older_Page.wikitext"),
+            "#REDIRECT [[Renamed Page]]",
+        )
+        .unwrap();
+        fs::write(src.join("Renamed_Page.wikitext"), "#REDIRECT [[Main Page]]").unwrap();
+
+        let site = generate_wiki(
+            Config::default(),
+            &cli::Args::default(),
+            &src,
+            &dir.join("output").join(WIKI_DIRECTORY),
+        )
+        .unwrap();
+
+        assert_eq!(site.dangling_redirects(), [("Old Page", "Deleted Page")]);
+        assert_eq!(site.warnings.len(), 1);
+
+        let args = cli::Args::default();
+        assert!(finish_build(&site, &args).is_ok());
+        let args = cli::Args {
+            strict: true,
+            ..cli::Args::default()
+        };
+        assert!(finish_build(&site, &args).is_err());
+    }
+
+    #[test]
+    fn test_profile_records_pages_and_templates() {
+        let dir = temp_dir("profile");
+        let src = dir.join("wiki");
+        fs::create_dir_all(&src).unwrap();
+        fs::write(src.join("Main_Page.wikitext"), "{{Note|a}} {{Note|b}}").unwrap();
+        fs::write(src.join("Note.wikitext"), "'''Note:''' {{{1}}}").unwrap();
+        fs::write(src.join("Other.wikitext"), "Hello").unwrap();
+        let dst = dir.join("output").join(WIKI_DIRECTORY);
+
+        let site = generate_wiki(Config::default(), &cli::Args::default(), &src, &dst).unwrap();
+        assert!(site.profile.is_none());
+
+        let args = cli::Args {
+            profile: true,
+            ..cli::Args::default()
+        };
+        let site = generate_wiki(Config::default(), &args, &src, &dst).unwrap();
+        let profile = site.profile.as_ref().unwrap();
+
+        let mut pages = profile
+            .slowest_pages(10)
+            .into_iter()
+            .map(|(title, _)| title)
+            .collect::<Vec<_>>();
+        pages.sort();
+        assert_eq!(pages, ["Main Page", "Note", "Other"]);
+
+        let templates = profile.most_expensive_templates(10);
+        assert_eq!(templates.len(), 1);
+        assert_eq!(templates[0].0, "note");
+        assert_eq!(templates[0].1.count, 2);
+    }
+
+    #[test]
+    fn test_pages_over_the_size_budget_are_reported() {
+        let dir = temp_dir("page_budget");
+        let src = dir.join("wiki");
+        fs::create_dir_all(&src).unwrap();
+        fs::write(src.join("Main_Page.wikitext"), "Welcome").unwrap();
+        fs::write(src.join("Huge.wikitext"), "Lorem ipsum. ".repeat(5000)).unwrap();
+
+        let config = Config {
+            max_page_bytes: Some(30_000),
+            ..Config::default()
+        };
+        let site = generate_wiki(
+            config,
+            &cli::Args::default(),
+            &src,
+            &dir.join("output").join(WIKI_DIRECTORY),
+        )
+        .unwrap();
+
+        let oversized = site.oversized_pages.borrow().clone();
+        assert_eq!(oversized.len(), 1, "{oversized:?}");
+        assert_eq!(oversized[0].0, "Huge");
+        assert!(oversized[0].1 > 30_000);
+        assert_eq!(site.warnings.len(), 1);
+        assert!(finish_build(&site, &cli::Args::default()).is_ok());
+        let args = cli::Args {
+            strict: true,
+            ..cli::Args::default()
+        };
+        assert!(finish_build(&site, &args).is_err());
+    }
+
+    #[test]
+    fn test_oversized_media_is_reported() {
+        let dir = temp_dir("oversized_media");
+        let src = dir.join("wiki");
+        fs::create_dir_all(&src).unwrap();
+        fs::write(src.join("Main_Page.wikitext"), "Welcome").unwrap();
+        let media_src = dir.join("media");
+        fs::create_dir_all(&media_src).unwrap();
+        fs::write(media_src.join("Huge.png"), vec![0; 2048]).unwrap();
+        fs::write(media_src.join("Small.png"), vec![0; 512]).unwrap();
+
+        let config = Config {
+            media_directory: media_src.to_string_lossy().to_string(),
+            max_media_bytes: Some(1024),
+            ..Config::default()
+        };
+        let site = generate_wiki(
+            config,
+            &cli::Args::default(),
+            &src,
+            &dir.join("output").join(WIKI_DIRECTORY),
+        )
+        .unwrap();
+
+        assert_eq!(site.oversized_media, [("Huge.png".to_string(), 2048)]);
+        assert_eq!(site.warnings.len(), 1);
+        assert!(finish_build(&site, &cli::Args::default()).is_ok());
+        let args = cli::Args {
+            strict: true,
+            ..cli::Args::default()
+        };
+        assert!(finish_build(&site, &args).is_err());
+    }
+
+    #[test]
+    fn test_unknown_code_language_policies() {
+        SYNTAX_HIGHLIGHTER.get_or_init(syntax::SyntaxHighlighter::default);
+        let wikitext = "<syntaxhighlight lang=\"klingon\">local x = 1</syntaxhighlight>";
+        let render = |policy| {
+            let site = Site::new(
+                Config {
+                    unknown_code_language: policy,
+                    ..Config::default()
+                },
+                Default::default(),
+                Default::default(),
+            );
+            let html = render_wikitext(&site, MockLoader::new(), "Test", wikitext);
+            (html, site.warnings.len())
+        };
+
+        let (html, warnings) = render(UnknownCodeLanguage::Plain);
+        assert!(html.contains("<code>local x = 1</code>"), "{html}");
+        assert_eq!(warnings, 0);
+
+        let (html, warnings) = render(UnknownCodeLanguage::Error);
+        assert!(html.contains("<code>local x = 1</code>"), "{html}");
+        assert_eq!(warnings, 1);
+
+        // Detected as Lua, so the code is split into highlighted spans
+        let (html, warnings) = render(UnknownCodeLanguage::Autodetect);
+        assert!(!html.contains("<code>local x = 1</code>"), "{html}");
+        assert!(html.contains("<span class="), "{html}");
+        assert_eq!(warnings, 0);
+    }
+
+    #[test]
+    fn test_external_link_rel_is_configurable() {
+        let site = Site::new(
+            Config {
+                external_link_rel: "noopener ugc".to_string(),
+                ..Config::default()
+            },
+            Default::default(),
+            Default::default(),
+        );
+
+        let html = render_wikitext(
+            &site,
+            MockLoader::new(),
+            "Test",
+            "See [https://example.com Example] or [[#Section]].",
+        );
+        assert!(html.contains(r#"rel="noopener ugc""#), "{html}");
+        assert_eq!(html.matches("rel=").count(), 1, "{html}");
+    }
+
+    #[test]
+    fn test_link_class_is_configurable() {
+        let site = Site::new(
+            Config {
+                link_class: "text-green-700 hover:text-green-900 underline".to_string(),
+                ..Config::default()
+            },
+            ["Lua/Server"].map(String::from).into(),
+            Default::default(),
+        );
+
+        let html = render_wikitext(
+            &site,
+            MockLoader::new(),
+            "Test",
+            "[[Lua/Server]], [[Missing]] and [https://example.com Example]",
+        );
+        assert!(
+            html.contains(r#"<a class="text-green-700 hover:text-green-900 underline" href="/wiki/Lua/Server.html""#),
+            "{html}"
+        );
+        assert!(
+            html.contains(
+                r#"<a class="underline text-red-600 hover:text-red-800" href="/wiki/Missing.html""#
+            ),
+            "{html}"
+        );
+        assert!(
+            html.contains(r#"class="text-green-700 hover:text-green-900 underline after:"#),
+            "{html}"
+        );
+        assert!(!html.contains("text-blue-600"), "{html}");
+    }
+
+    #[test]
+    fn test_responsive_references_use_columns_when_long() {
+        let site = Site::new(Config::default(), Default::default(), Default::default());
+
+        let many_notes = (1..=12)
+            .map(|i| format!("Claim {i}.<ref>Source {i}</ref>"))
+            .collect::<String>();
+        let html = render_wikitext(
+            &site,
+            MockLoader::new(),
+            "Test",
+            &format!("{many_notes}\n<references responsive />"),
+        );
+        assert!(html.contains("Source 12"), "{html}");
+        assert!(html.contains("md:columns-2"), "{html}");
+
+        let html = render_wikitext(
+            &site,
+            MockLoader::new(),
+            "Test",
+            "Claim.<ref>Source</ref>\n<references responsive />",
+        );
+        assert!(html.contains("Source"), "{html}");
+        assert!(!html.contains("md:columns-2"), "{html}");
+    }
+
+    #[test]
+    fn test_template_demo_renders_output_and_escaped_source() {
+        let site = Site::new(Config::default(), Default::default(), Default::default());
+        let mut loader = MockLoader::new();
+        loader.add("Greeting", "Hello, {{{1}}}!");
+        let html = render_wikitext(
+            &site,
+            loader,
+            "Test",
+            "{{TemplateDemo|Greeting|<b>World</b>}}",
+        );
+
+        assert!(html.contains("Hello, "), "{html}");
+        assert!(html.contains("<details"), "{html}");
+        assert!(html.contains("{{Greeting|"), "{html}");
+        assert!(html.contains("&lt;b&gt;World&lt;/b&gt;"), "{html}");
+        assert!(site.warnings.is_empty());
+
+        // A parameter `0` has nowhere to go
+        let mut loader = MockLoader::new();
+        loader.add("Greeting", "Hello, {{{1}}}!");
+        let html = render_wikitext(
+            &site,
+            loader,
+            "Test",
+            "{{TemplateDemo|Greeting|World|0=ignored}}",
+        );
+        assert!(html.contains("Hello, World!"), "{html}");
+        assert_eq!(site.warnings.len(), 1);
+    }
+
+    #[test]
+    fn test_level_one_heading_is_demoted_by_offset() {
+        let site = Site::new(
+            Config {
+                heading_level_offset: 1,
+                ..Config::default()
+            },
+            Default::default(),
+            Default::default(),
+        );
+        let html = render_wikitext(&site, MockLoader::new(), "Test", "= Overview =\n== Usage ==");
+
+        assert!(!html.contains("<h1"), "{html}");
+        assert!(html.contains("<h2"), "{html}");
+        assert!(html.contains("<h3"), "{html}");
+    }
+
+    #[test]
+    fn test_configured_heading_class_is_applied() {
+        let mut config = Config::default();
+        config
+            .heading_classes
+            .insert(2, "text-3xl font-black".to_string());
+        let site = Site::new(config, Default::default(), Default::default());
+        let html = render_wikitext(&site, MockLoader::new(), "Test", "== Usage ==");
+
+        assert!(
+            html.contains(r#"<h2 class="text-3xl font-black" id="usage""#),
+            "{html}"
+        );
+    }
+
+    #[test]
+    fn test_heading_ids_include_templated_text() {
+        let mut loader = MockLoader::new();
+        loader.add("Version", "0.2");
+        let site = Site::new(Config::default(), Default::default(), Default::default());
+        let html = render_wikitext(
+            &site,
+            loader,
+            "Test",
+            "== What's new in {{Version}}? ==\n== Usage ==\n== Usage ==\n== {{color|red|Removed}} ==",
+        );
+
+        assert!(html.contains(r#"id="whats-new-in-02""#), "{html}");
+        assert!(html.contains(r#"id="usage""#), "{html}");
+        assert!(html.contains(r#"id="usage-2""#), "{html}");
+        assert!(
+            html.contains(r##"href="#usage-2" aria-label="Link to this section">#</a></h2>"##),
+            "{html}"
+        );
+        assert!(
+            html.contains(r#"id="removed"><span style="color:red">Removed</span>"#),
+            "{html}"
+        );
+        assert_eq!(site.warnings.len(), 0);
+    }
+
+    #[test]
+    fn test_numbered_headings_keep_their_ids() {
+        let config = Config {
+            number_headings: true,
+            ..Config::default()
+        };
+        let site = Site::new(config, Default::default(), Default::default());
+        let html = render_wikitext(
+            &site,
+            MockLoader::new(),
+            "Test",
+            "== Usage ==\n=== Server ===\n=== Client ===\n== Examples ==",
+        );
+
+        assert!(html.contains(r#"id="usage">1 Usage"#), "{html}");
+        assert!(html.contains(r#"id="client">1.2 Client"#), "{html}");
+        assert!(html.contains(r#"id="examples">2 Examples"#), "{html}");
+    }
+
+    #[test]
+    fn test_anchor_prefix_applies_to_headings_and_links() {
+        let dir = temp_dir("anchor_prefix");
+        let src = dir.join("wiki");
+        fs::create_dir_all(&src).unwrap();
+        fs::write(
+            src.join("Main_Page.wikitext"),
+            "__FORCETOC__\n== Usage ==\nSee [[#Usage|usage]].",
+        )
+        .unwrap();
+        let dst = dir.join("output").join(WIKI_DIRECTORY);
+
+        let config = Config {
+            anchor_prefix: "sec-".to_string(),
+            ..Config::default()
+        };
+        generate_wiki(config, &cli::Args::default(), &src, &dst).unwrap();
+
+        let main_page = fs::read_to_string(dst.join("Main_Page.html")).unwrap();
+        assert!(main_page.contains(r#"id="sec-usage""#), "{main_page}");
+        let on_this_page = &main_page[main_page.find(r#"id="on-this-page""#).unwrap()..];
+        assert!(
+            on_this_page.contains(r##"href="#sec-usage""##),
+            "{main_page}"
+        );
+        let content = &main_page[..main_page.find(r#"id="on-this-page""#).unwrap()];
+        assert!(content.contains(r##"href="#sec-usage""##), "{main_page}");
+    }
+
+    #[test]
+    fn test_broken_link_fails_only_with_deny_warnings() {
+        let dir = temp_dir("deny_warnings");
+        let src = dir.join("wiki");
+        fs::create_dir_all(&src).unwrap();
+        fs::write(src.join("Main_Page.wikitext"), "See [[Missing Page]].").unwrap();
+
+        let site = generate_wiki(
+            Config::default(),
+            &cli::Args::default(),
+            &src,
+            &dir.join("output").join(WIKI_DIRECTORY),
+        )
+        .unwrap();
+
+        assert_eq!(site.warnings.len(), 1);
+        assert!(site.warnings.finish(false).is_ok());
+        assert!(site.warnings.finish(true).is_err());
+    }
+
+    #[test]
+    fn test_deny_broken_links_fails_only_on_broken_links() {
+        let dir = temp_dir("deny_broken_links");
+        let src = dir.join("wiki");
+        fs::create_dir_all(&src).unwrap();
+        fs::write(src.join("Main_Page.wikitext"), "See [[Old Page]].").unwrap();
+        fs::write(src.join("Old_Page.wikitext"), "#REDIRECT [[Main Page]]").unwrap();
+        let dst = dir.join("output").join(WIKI_DIRECTORY);
+        let args = cli::Args {
+            deny_broken_links: true,
+            quiet: true,
+            ..cli::Args::default()
+        };
+
+        // Links to redirects lead somewhere
+        let site = generate_wiki(Config::default(), &args, &src, &dst).unwrap();
+        assert!(finish_build(&site, &args).is_ok());
+
+        fs::write(src.join("Main_Page.wikitext"), "See [[Missing Page]].").unwrap();
+        let site = generate_wiki(Config::default(), &args, &src, &dst).unwrap();
+        assert!(finish_build(&site, &args).is_err());
+        assert!(finish_build(&site, &cli::Args::default()).is_ok());
+    }
+
+    #[test]
+    fn test_short_pages_are_stubs() {
+        let dir = temp_dir("stubs");
+        let src = dir.join("wiki");
+        fs::create_dir_all(&src).unwrap();
+        let long = "The vehicle API lets scripts spawn, move and remove vehicles. ".repeat(5);
+        fs::write(src.join("Main_Page.wikitext"), &long).unwrap();
+        fs::write(src.join("Short.wikitext"), "To be written.").unwrap();
+        fs::write(src.join("Marked.wikitext"), format!("{{{{Stub}}}}\n{long}")).unwrap();
+        let dst = dir.join("output").join(WIKI_DIRECTORY);
+
+        let config = Config {
+            stub_threshold: Some(100),
+            ..Config::default()
+        };
+        let site = generate_wiki(config, &cli::Args::default(), &src, &dst).unwrap();
+
+        let banner = "This page is a stub";
+        let main_page = fs::read_to_string(dst.join("Main_Page.html")).unwrap();
+        assert!(!main_page.contains(banner), "{main_page}");
+        let short = fs::read_to_string(dst.join("Short.html")).unwrap();
+        assert!(short.contains(banner), "{short}");
+        let marked = fs::read_to_string(dst.join("Marked.html")).unwrap();
+        assert!(marked.contains(banner), "{marked}");
+
+        assert_eq!(
+            site.stubs,
+            BTreeSet::from(["Marked".to_string(), "Short".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_link_report_json_schema() {
+        let dir = temp_dir("link_report");
+        let src = dir.join("wiki");
+        fs::create_dir_all(&src).unwrap();
+        fs::write(
+            src.join("Main_Page.wikitext"),
+            "See [[Missing Page]] and [[Guide]].",
+        )
+        .unwrap();
+        fs::write(src.join("Guide.wikitext"), "Back to [[Main Page]].").unwrap();
+
+        let site = generate_wiki(
+            Config::default(),
+            &cli::Args::default(),
+            &src,
+            &dir.join("output").join(WIKI_DIRECTORY),
+        )
+        .unwrap();
+        let report = serde_json::to_value(LinkReport::new(&site)).unwrap();
+
+        assert_eq!(
+            report,
+            serde_json::json!({
+                "issues": [
+                    { "source": "Main Page", "target": "Missing Page", "issue": "broken_link" }
+                ]
+            })
+        );
+    }
+}
@@ -0,0 +1,59 @@
+//! Rendering `<math>` formulae, written in TeX, with the configured backend
+
+use paxhtml::html;
+
+use crate::{config::MathBackend, mathml, page_context::PageContext};
+
+/// The version of KaTeX loaded for pages with formulae
+const KATEX_VERSION: &str = "0.16.11";
+
+/// Render the TeX formula `tex`, either inline or as a block of its own
+pub fn render(
+    backend: MathBackend,
+    tex: &str,
+    display: bool,
+    page_context: &PageContext,
+) -> paxhtml::Element {
+    let tex = tex.trim();
+    match backend {
+        // KaTeX's auto-render finds the formulae by their delimiters
+        MathBackend::Katex if display => html! {
+            <span class="math math-display block my-4">{format!("\\[{tex}\\]")}</span>
+        },
+        MathBackend::Katex => html! {
+            <span class="math math-inline">{format!("\\({tex}\\)")}</span>
+        },
+        MathBackend::MathMl => match mathml::convert(tex, display) {
+            Ok(html) => paxhtml::Element::Raw { html },
+            Err(e) => {
+                page_context.warn(format!("can't render <math>{tex}</math> as MathML: {e}"));
+                render(MathBackend::Plain, tex, display, page_context)
+            }
+        },
+        MathBackend::Plain if display => html! {
+            <code class="math block my-4">{tex.to_string()}</code>
+        },
+        MathBackend::Plain => html! { <code class="math">{tex.to_string()}</code> },
+    }
+}
+
+/// The stylesheets and scripts a page with formulae needs in its `<head>`
+pub fn head(backend: MathBackend) -> Vec<paxhtml::Element> {
+    match backend {
+        MathBackend::Katex => {
+            let base = format!("https://cdn.jsdelivr.net/npm/katex@{KATEX_VERSION}/dist");
+            vec![
+                html! { <link rel="stylesheet" href={format!("{base}/katex.min.css")} /> },
+                html! { <script defer src={format!("{base}/katex.min.js")}></script> },
+                html! {
+                    <script
+                        defer
+                        src={format!("{base}/contrib/auto-render.min.js")}
+                        onload="renderMathInElement(document.body)"
+                    ></script>
+                },
+            ]
+        }
+        MathBackend::MathMl | MathBackend::Plain => vec![],
+    }
+}
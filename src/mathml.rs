@@ -0,0 +1,441 @@
+//! Converting TeX formulae to MathML at build time, so that they render without any
+//! scripts. Only the commonly used subset of TeX is supported: letters, numbers and
+//! operators, scripts, groups, fractions, roots, text, Greek letters and the usual
+//! symbols and functions. Anything else, such as environments, is reported as an error.
+
+use crate::xml::escape;
+
+/// The MathML for the TeX formula `tex`, or a description of the TeX it doesn't support
+pub fn convert(tex: &str, display: bool) -> Result<String, String> {
+    let mut parser = Parser {
+        chars: tex.chars().collect(),
+        position: 0,
+    };
+    let row = parser.row(None)?;
+    let display = if display { "block" } else { "inline" };
+    Ok(format!(
+        r#"<math xmlns="http://www.w3.org/1998/Math/MathML" display="{display}" alttext="{}">{}</math>"#,
+        escape(tex),
+        mrow(row)
+    ))
+}
+
+/// The Greek letters, by their command
+const GREEK: &[(&str, char)] = &[
+    ("alpha", 'α'),
+    ("beta", 'β'),
+    ("gamma", 'γ'),
+    ("delta", 'δ'),
+    ("epsilon", 'ϵ'),
+    ("varepsilon", 'ε'),
+    ("zeta", 'ζ'),
+    ("eta", 'η'),
+    ("theta", 'θ'),
+    ("vartheta", 'ϑ'),
+    ("iota", 'ι'),
+    ("kappa", 'κ'),
+    ("lambda", 'λ'),
+    ("mu", 'μ'),
+    ("nu", 'ν'),
+    ("xi", 'ξ'),
+    ("pi", 'π'),
+    ("varpi", 'ϖ'),
+    ("rho", 'ρ'),
+    ("varrho", 'ϱ'),
+    ("sigma", 'σ'),
+    ("varsigma", 'ς'),
+    ("tau", 'τ'),
+    ("upsilon", 'υ'),
+    ("phi", 'ϕ'),
+    ("varphi", 'φ'),
+    ("chi", 'χ'),
+    ("psi", 'ψ'),
+    ("omega", 'ω'),
+    ("Gamma", 'Γ'),
+    ("Delta", 'Δ'),
+    ("Theta", 'Θ'),
+    ("Lambda", 'Λ'),
+    ("Xi", 'Ξ'),
+    ("Pi", 'Π'),
+    ("Sigma", 'Σ'),
+    ("Upsilon", 'Υ'),
+    ("Phi", 'Φ'),
+    ("Psi", 'Ψ'),
+    ("Omega", 'Ω'),
+];
+
+/// The symbols that are identifiers rather than operators, by their command
+const IDENTIFIERS: &[(&str, char)] = &[
+    ("infty", '∞'),
+    ("partial", '∂'),
+    ("nabla", '∇'),
+    ("emptyset", '∅'),
+    ("varnothing", '∅'),
+    ("hbar", 'ℏ'),
+    ("ell", 'ℓ'),
+];
+
+/// The operators, relations, arrows and delimiters, by their command
+const OPERATORS: &[(&str, char)] = &[
+    ("times", '×'),
+    ("cdot", '⋅'),
+    ("pm", '±'),
+    ("mp", '∓'),
+    ("div", '÷'),
+    ("ast", '∗'),
+    ("star", '⋆'),
+    ("circ", '∘'),
+    ("bullet", '∙'),
+    ("le", '≤'),
+    ("leq", '≤'),
+    ("ge", '≥'),
+    ("geq", '≥'),
+    ("ne", '≠'),
+    ("neq", '≠'),
+    ("approx", '≈'),
+    ("sim", '∼'),
+    ("simeq", '≃'),
+    ("equiv", '≡'),
+    ("propto", '∝'),
+    ("ll", '≪'),
+    ("gg", '≫'),
+    ("to", '→'),
+    ("rightarrow", '→'),
+    ("leftarrow", '←'),
+    ("gets", '←'),
+    ("leftrightarrow", '↔'),
+    ("Rightarrow", '⇒'),
+    ("Leftarrow", '⇐'),
+    ("Leftrightarrow", '⇔'),
+    ("implies", '⟹'),
+    ("iff", '⟺'),
+    ("mapsto", '↦'),
+    ("in", '∈'),
+    ("notin", '∉'),
+    ("ni", '∋'),
+    ("subset", '⊂'),
+    ("subseteq", '⊆'),
+    ("supset", '⊃'),
+    ("supseteq", '⊇'),
+    ("cup", '∪'),
+    ("cap", '∩'),
+    ("setminus", '∖'),
+    ("wedge", '∧'),
+    ("land", '∧'),
+    ("vee", '∨'),
+    ("lor", '∨'),
+    ("neg", '¬'),
+    ("lnot", '¬'),
+    ("forall", '∀'),
+    ("exists", '∃'),
+    ("sum", '∑'),
+    ("prod", '∏'),
+    ("int", '∫'),
+    ("oint", '∮'),
+    ("ldots", '…'),
+    ("dots", '…'),
+    ("cdots", '⋯'),
+    ("vdots", '⋮'),
+    ("ddots", '⋱'),
+    ("langle", '⟨'),
+    ("rangle", '⟩'),
+    ("lfloor", '⌊'),
+    ("rfloor", '⌋'),
+    ("lceil", '⌈'),
+    ("rceil", '⌉'),
+    ("mid", '∣'),
+    ("parallel", '∥'),
+    ("perp", '⊥'),
+    ("angle", '∠'),
+    ("prime", '′'),
+];
+
+/// The functions, which are set upright
+const FUNCTIONS: &[&str] = &[
+    "sin", "cos", "tan", "sec", "csc", "cot", "arcsin", "arccos", "arctan", "sinh", "cosh", "tanh",
+    "log", "ln", "lg", "exp", "lim", "max", "min", "sup", "inf", "det", "gcd", "deg", "dim", "ker",
+    "arg", "mod",
+];
+
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+}
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.position += 1;
+        }
+    }
+
+    /// The nodes up to `end` (which is consumed), or to the end of the formula
+    fn row(&mut self, end: Option<char>) -> Result<Vec<String>, String> {
+        let mut nodes = vec![];
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                None => {
+                    return match end {
+                        Some(end) => Err(format!("missing {end:?}")),
+                        None => Ok(nodes),
+                    };
+                }
+                Some(c) if Some(c) == end => {
+                    self.position += 1;
+                    return Ok(nodes);
+                }
+                Some('}') => return Err("unbalanced '}'".to_string()),
+                Some('^' | '_') => {
+                    let base = nodes.pop().unwrap_or_else(|| "<mrow></mrow>".to_string());
+                    nodes.push(self.scripts(base)?);
+                }
+                Some(_) => nodes.push(self.atom(false)?),
+            }
+        }
+    }
+
+    /// `base` with the subscript and superscript that follow it
+    fn scripts(&mut self, base: String) -> Result<String, String> {
+        let (mut sub, mut sup) = (None, None);
+        loop {
+            self.skip_whitespace();
+            let script = match self.peek() {
+                Some('_') => &mut sub,
+                Some('^') => &mut sup,
+                _ => break,
+            };
+            if script.is_some() {
+                return Err("double script".to_string());
+            }
+            self.position += 1;
+            *script = Some(self.argument()?);
+        }
+        Ok(match (sub, sup) {
+            (Some(sub), Some(sup)) => format!("<msubsup>{base}{sub}{sup}</msubsup>"),
+            (Some(sub), None) => format!("<msub>{base}{sub}</msub>"),
+            (None, Some(sup)) => format!("<msup>{base}{sup}</msup>"),
+            (None, None) => base,
+        })
+    }
+
+    /// The argument of a command or script: a group, or else a single character or
+    /// command
+    fn argument(&mut self) -> Result<String, String> {
+        self.skip_whitespace();
+        if self.peek() == Some('{') {
+            self.position += 1;
+            return Ok(mrow(self.row(Some('}'))?));
+        }
+        self.atom(true)
+    }
+
+    /// The text of a group, as written
+    fn text_argument(&mut self) -> Result<String, String> {
+        self.skip_whitespace();
+        if self.peek() != Some('{') {
+            return Err("missing '{'".to_string());
+        }
+        self.position += 1;
+        let start = self.position;
+        let mut depth = 0;
+        while let Some(c) = self.peek() {
+            self.position += 1;
+            match c {
+                '{' => depth += 1,
+                '}' if depth == 0 => {
+                    return Ok(self.chars[start..self.position - 1].iter().collect());
+                }
+                '}' => depth -= 1,
+                _ => {}
+            }
+        }
+        Err("missing '}'".to_string())
+    }
+
+    /// A single node. Numbers are read whole unless `single` is set, as a script
+    /// applies to only their first digit.
+    fn atom(&mut self, single: bool) -> Result<String, String> {
+        let Some(c) = self.peek() else {
+            return Err("missing argument".to_string());
+        };
+        self.position += 1;
+        Ok(match c {
+            '{' => mrow(self.row(Some('}'))?),
+            '\\' => return self.command(),
+            c if c.is_ascii_digit() || c == '.' => {
+                let mut number = c.to_string();
+                while !single
+                    && let Some(next) = self.peek()
+                    && (next.is_ascii_digit()
+                        || next == '.'
+                            && self
+                                .chars
+                                .get(self.position + 1)
+                                .is_some_and(char::is_ascii_digit))
+                {
+                    number.push(next);
+                    self.position += 1;
+                }
+                format!("<mn>{number}</mn>")
+            }
+            c if c.is_alphabetic() => format!("<mi>{c}</mi>"),
+            '-' => mo('−'),
+            '*' => mo('∗'),
+            '\'' => mo('′'),
+            '+' | '=' | '<' | '>' | '(' | ')' | '[' | ']' | ',' | ';' | ':' | '!' | '/' | '|'
+            | '?' => mo(c),
+            c => return Err(format!("unsupported character {c:?}")),
+        })
+    }
+
+    /// The command after a backslash
+    fn command(&mut self) -> Result<String, String> {
+        let start = self.position;
+        while self.peek().is_some_and(|c| c.is_ascii_alphabetic()) {
+            self.position += 1;
+        }
+        if self.position == start && self.peek().is_some() {
+            self.position += 1;
+        }
+        let name = self.chars[start..self.position].iter().collect::<String>();
+
+        let lookup = |table: &[(&str, char)]| {
+            table
+                .iter()
+                .find(|(command, _)| *command == name)
+                .map(|(_, c)| *c)
+        };
+        if let Some(letter) = lookup(GREEK) {
+            return Ok(if letter.is_uppercase() {
+                format!(r#"<mi mathvariant="normal">{letter}</mi>"#)
+            } else {
+                format!("<mi>{letter}</mi>")
+            });
+        }
+        if let Some(identifier) = lookup(IDENTIFIERS) {
+            return Ok(format!("<mi>{identifier}</mi>"));
+        }
+        if let Some(operator) = lookup(OPERATORS) {
+            return Ok(mo(operator));
+        }
+        if FUNCTIONS.contains(&name.as_str()) {
+            return Ok(format!("<mi>{name}</mi>"));
+        }
+
+        Ok(match name.as_str() {
+            "frac" | "dfrac" | "tfrac" => {
+                let (numerator, denominator) = (self.argument()?, self.argument()?);
+                format!("<mfrac>{numerator}{denominator}</mfrac>")
+            }
+            "sqrt" => {
+                self.skip_whitespace();
+                if self.peek() == Some('[') {
+                    self.position += 1;
+                    let index = mrow(self.row(Some(']'))?);
+                    format!("<mroot>{}{index}</mroot>", self.argument()?)
+                } else {
+                    format!("<msqrt>{}</msqrt>", self.argument()?)
+                }
+            }
+            "text" | "textrm" | "mbox" => {
+                format!("<mtext>{}</mtext>", escape(&self.text_argument()?))
+            }
+            "mathrm" | "operatorname" => format!(
+                r#"<mi mathvariant="normal">{}</mi>"#,
+                escape(&self.text_argument()?)
+            ),
+            // The delimiters stretch to fit in MathML anyway
+            "left" | "right" | "big" | "Big" | "bigg" | "Bigg" => {
+                self.skip_whitespace();
+                if self.peek() == Some('.') {
+                    self.position += 1;
+                    String::new()
+                } else {
+                    self.atom(true)?
+                }
+            }
+            "," | ":" | ";" => r#"<mspace width="0.2em" />"#.to_string(),
+            " " => r#"<mspace width="0.3em" />"#.to_string(),
+            "quad" => r#"<mspace width="1em" />"#.to_string(),
+            "qquad" => r#"<mspace width="2em" />"#.to_string(),
+            "!" => String::new(),
+            "{" | "}" => mo(name.chars().next().unwrap()),
+            "|" => mo('‖'),
+            "%" | "#" | "&" | "_" | "$" => format!("<mi>{}</mi>", escape(&name)),
+            _ => return Err(format!("unsupported command \\{name}")),
+        })
+    }
+}
+
+fn mo(c: char) -> String {
+    format!("<mo>{}</mo>", escape(&c.to_string()))
+}
+
+/// A single node for `nodes`, grouping them if there are several
+fn mrow(mut nodes: Vec<String>) -> String {
+    if nodes.len() == 1 {
+        nodes.pop().unwrap()
+    } else {
+        format!("<mrow>{}</mrow>", nodes.concat())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn body(tex: &str) -> String {
+        let mathml = convert(tex, false).unwrap();
+        let start = mathml.find('>').unwrap() + 1;
+        mathml[start..mathml.len() - "</math>".len()].to_string()
+    }
+
+    #[test]
+    fn test_convert() {
+        assert_eq!(
+            body(r"\pi r^2"),
+            "<mrow><mi>π</mi><msup><mi>r</mi><mn>2</mn></msup></mrow>"
+        );
+        assert_eq!(
+            body("x_{i+1}^23"),
+            "<mrow><msubsup><mi>x</mi><mrow><mi>i</mi><mo>+</mo><mn>1</mn></mrow><mn>2</mn></msubsup><mn>3</mn></mrow>"
+        );
+        assert_eq!(
+            body(r"\frac{a}{b} \le \sqrt[3]{x}"),
+            "<mrow><mfrac><mi>a</mi><mi>b</mi></mfrac><mo>≤</mo><mroot><mi>x</mi><mn>3</mn></mroot></mrow>"
+        );
+        assert_eq!(
+            body(r"\sin x - 1.5 \text{ m/s}"),
+            "<mrow><mi>sin</mi><mi>x</mi><mo>−</mo><mn>1.5</mn><mtext> m/s</mtext></mrow>"
+        );
+        assert_eq!(
+            body("a < b"),
+            "<mrow><mi>a</mi><mo>&lt;</mo><mi>b</mi></mrow>"
+        );
+    }
+
+    #[test]
+    fn test_display_and_source() {
+        let mathml = convert("a<b", true).unwrap();
+        assert!(mathml.contains(r#"display="block""#), "{mathml}");
+        assert!(mathml.contains(r#"alttext="a&lt;b""#), "{mathml}");
+    }
+
+    #[test]
+    fn test_unsupported_tex_is_an_error() {
+        assert_eq!(
+            convert(r"\begin{matrix} a \end{matrix}", false),
+            Err(r"unsupported command \begin".to_string())
+        );
+        assert_eq!(
+            convert("a & b", false),
+            Err("unsupported character '&'".to_string())
+        );
+        assert_eq!(convert("{a", false), Err("missing '}'".to_string()));
+        assert_eq!(convert("x^2^3", false), Err("double script".to_string()));
+    }
+}
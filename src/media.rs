@@ -0,0 +1,453 @@
+//! Images and other files referenced by `[[File:...]]` links, which are copied from the
+//! media directory into the output
+
+use std::{collections::BTreeSet, fs, path::Path};
+
+use crate::{config::Config, site::Site, warnings::Warnings};
+
+/// The directory in the output that media is copied to, and its URL
+pub const MEDIA_DIRECTORY: &str = "media";
+/// The directory within [`MEDIA_DIRECTORY`] that thumbnails are written to
+const THUMBNAIL_DIRECTORY: &str = "thumbs";
+
+/// What was found while copying the media
+#[derive(Debug, Default)]
+pub struct CopiedMedia {
+    /// The names of the files that have thumbnails
+    pub thumbnails: BTreeSet<String>,
+    /// The names and sizes of the files larger than `max_media_bytes`
+    pub oversized: Vec<(String, u64)>,
+}
+
+/// Copy the media in `src` to `dst`, generating thumbnails for large images if
+/// configured. Images that can't be read are copied without a thumbnail, with a
+/// warning.
+pub fn copy_media(
+    config: &Config,
+    warnings: &Warnings,
+    src: &Path,
+    dst: &Path,
+) -> anyhow::Result<CopiedMedia> {
+    let mut copied = CopiedMedia::default();
+    if !src.is_dir() {
+        return Ok(copied);
+    }
+    fs::create_dir_all(dst)?;
+
+    for entry in fs::read_dir(src)? {
+        let path = entry?.path();
+        if !path.is_file() {
+            continue;
+        }
+        let name = path.file_name().unwrap().to_string_lossy().to_string();
+        fs::copy(&path, dst.join(&name))?;
+        let size = fs::metadata(&path)?.len();
+
+        if config.max_media_bytes.is_some_and(|max| size > max) {
+            copied.oversized.push((name.clone(), size));
+        }
+        if let Some(max_width) = config.thumbnail_width
+            && is_image(&name)
+            && size >= config.thumbnail_min_bytes
+        {
+            match write_thumbnail(&path, &dst.join(THUMBNAIL_DIRECTORY).join(&name), max_width) {
+                Ok(true) => {
+                    copied.thumbnails.insert(name);
+                }
+                Ok(false) => {}
+                Err(e) => warnings.warn(format!("{e}; it has no thumbnail")),
+            }
+        }
+    }
+    copied.oversized.sort();
+
+    Ok(copied)
+}
+
+/// The names of the files with thumbnails in media already copied to `dst`
+pub fn existing_thumbnails(dst: &Path) -> BTreeSet<String> {
+    fs::read_dir(dst.join(THUMBNAIL_DIRECTORY))
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| Some(entry.ok()?.file_name().to_string_lossy().to_string()))
+        .collect()
+}
+
+fn is_image(name: &str) -> bool {
+    let extension = name.rsplit('.').next().unwrap_or_default().to_lowercase();
+    matches!(extension.as_str(), "png" | "jpg" | "jpeg" | "gif")
+}
+
+/// Write a copy of the image at `path` scaled down to `max_width`, returning whether
+/// one was needed
+fn write_thumbnail(path: &Path, thumbnail_path: &Path, max_width: u32) -> anyhow::Result<bool> {
+    let image = image::open(path)
+        .map_err(|e| anyhow::anyhow!("Failed to read image {}: {e}", path.display()))?;
+    if image.width() <= max_width {
+        return Ok(false);
+    }
+
+    fs::create_dir_all(thumbnail_path.parent().unwrap())?;
+    image
+        .resize(max_width, u32::MAX, image::imageops::FilterType::Lanczos3)
+        .save(thumbnail_path)
+        .map_err(|e| {
+            anyhow::anyhow!(
+                "Failed to write thumbnail {}: {e}",
+                thumbnail_path.display()
+            )
+        })?;
+    Ok(true)
+}
+
+/// The name of the file a `File:` or `Image:` link refers to, if `title` is one
+pub fn file_name(title: &str) -> Option<String> {
+    let (namespace, name) = title.split_once(':')?;
+    let namespace = namespace.trim();
+    (namespace.eq_ignore_ascii_case("file") || namespace.eq_ignore_ascii_case("image"))
+        .then(|| name.trim().replace(' ', "_"))
+}
+
+/// The name of the file that `title` links to without showing it, if it's a `Media:`
+/// link or a `File:` or `Image:` link starting with a colon (`[[:File:Map.png]]`)
+pub fn linked_file_name(title: &str) -> Option<String> {
+    let title = title.trim();
+    match title.strip_prefix(':') {
+        Some(title) => file_name(title),
+        None => {
+            let (namespace, name) = title.split_once(':')?;
+            namespace
+                .trim()
+                .eq_ignore_ascii_case("media")
+                .then(|| name.trim().replace(' ', "_"))
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Right,
+    Center,
+    None,
+}
+
+/// The options of a `[[File:Name|option|...|caption]]` link
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct FileOptions {
+    /// Whether the image is framed with its caption (`thumb` or `frame`)
+    pub framed: bool,
+    pub align: Option<Align>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub alt: Option<String>,
+    pub caption: Option<String>,
+}
+impl FileOptions {
+    /// Parse the pipe-separated options. The last option that isn't a keyword is the
+    /// caption; unknown keywords are ignored.
+    pub fn parse<'a>(options: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut result = Self::default();
+        for option in options {
+            let option = option.trim();
+            match option {
+                "thumb" | "thumbnail" | "frame" | "framed" => result.framed = true,
+                "left" => result.align = Some(Align::Left),
+                "right" => result.align = Some(Align::Right),
+                "center" | "centre" => result.align = Some(Align::Center),
+                "none" => result.align = Some(Align::None),
+                "frameless" | "border" | "upright" | "baseline" | "sub" | "super" | "top"
+                | "text-top" | "middle" | "bottom" | "text-bottom" => {}
+                _ => {
+                    if let Some((width, height)) = parse_size(option) {
+                        result.width = width;
+                        result.height = height;
+                    } else if let Some((key, value)) = option.split_once('=') {
+                        if key.trim() == "alt" {
+                            result.alt = Some(value.trim().to_string());
+                        }
+                    } else if !option.is_empty() {
+                        result.caption = Some(option.to_string());
+                    }
+                }
+            }
+        }
+        result
+    }
+}
+
+/// Split the options of a `[[File:...]]` link on the pipes between them, leaving the
+/// pipes in any links or templates within a caption alone
+pub fn split_options(text: &str) -> Vec<&str> {
+    let mut options = vec![];
+    let (mut depth, mut start, mut i) = (0usize, 0, 0);
+    let bytes = text.as_bytes();
+    while i < bytes.len() {
+        match &bytes[i..] {
+            [b'[', b'[', ..] | [b'{', b'{', ..] => {
+                depth += 1;
+                i += 2;
+            }
+            [b']', b']', ..] | [b'}', b'}', ..] => {
+                depth = depth.saturating_sub(1);
+                i += 2;
+            }
+            [b'|', ..] if depth == 0 => {
+                options.push(&text[start..i]);
+                i += 1;
+                start = i;
+            }
+            _ => i += 1,
+        }
+    }
+    options.push(&text[start..]);
+    options
+}
+
+/// Parse a size option: `120px`, `120x80px` or `x80px`
+fn parse_size(option: &str) -> Option<(Option<u32>, Option<u32>)> {
+    let size = option.strip_suffix("px")?.trim();
+    let (width, height) = size.split_once('x').unwrap_or((size, ""));
+    let parse = |s: &str| -> Option<Option<u32>> {
+        if s.is_empty() {
+            Some(None)
+        } else {
+            s.trim().parse().ok().map(Some)
+        }
+    };
+    Some((parse(width)?, parse(height)?))
+}
+
+/// Render a `[[File:...]]` link for the file `name` as an image linking to the full file.
+/// Framed images show `caption`, the rendered form of the caption in `options`.
+pub fn render_file_link(
+    site: &Site,
+    name: &str,
+    options: &FileOptions,
+    caption: Option<paxhtml::Element>,
+) -> paxhtml::Element {
+    use paxhtml::html;
+
+    let full_url = format!("/{MEDIA_DIRECTORY}/{name}");
+    let (width, height) = display_size(&site.config, options);
+    let use_thumbnail = site.thumbnails.contains(name)
+        && width.is_none_or(|width| site.config.thumbnail_width.is_some_and(|max| width <= max));
+    let src = if use_thumbnail {
+        format!("/{MEDIA_DIRECTORY}/{THUMBNAIL_DIRECTORY}/{name}")
+    } else {
+        full_url.clone()
+    };
+
+    let alt = options
+        .alt
+        .clone()
+        .or_else(|| options.caption.clone())
+        .unwrap_or_else(|| name.to_string());
+    let mut attributes = vec![];
+    for (name, value) in [("width", width), ("height", height)] {
+        if let Some(value) = value {
+            attributes.extend(
+                paxhtml::Attribute::parse_from_str(&format!("{name}=\"{value}\"")).unwrap(),
+            );
+        }
+    }
+    let image = html! {
+        <a href={full_url}>
+            <img class="max-w-full h-auto" src={src} alt={alt} loading="lazy" {attributes} />
+        </a>
+    };
+
+    let align = options.align.unwrap_or(if options.framed {
+        Align::Right
+    } else if site.config.center_images {
+        Align::Center
+    } else {
+        Align::None
+    });
+    let align_class = match align {
+        Align::Left => "float-left mr-4 mb-2",
+        Align::Right => "float-right ml-4 mb-2",
+        Align::Center => "mx-auto block",
+        Align::None => "",
+    };
+
+    if options.framed {
+        html! {
+            <figure class={format!("{align_class} w-fit border border-gray-300 bg-gray-50 p-1 text-sm")}>
+                {image}
+                #{caption.map(|caption| html! {
+                    <figcaption class="px-1 pt-1 text-gray-700">{caption}</figcaption>
+                })}
+            </figure>
+        }
+    } else if align == Align::None {
+        image
+    } else {
+        html! { <div class={format!("{align_class} w-fit")}>{image}</div> }
+    }
+}
+
+/// The width and height an image is shown at: the size it asks for, with framed images
+/// that don't ask for one getting the default width, clamped to the maximum width (with
+/// the height scaled to match)
+fn display_size(config: &Config, options: &FileOptions) -> (Option<u32>, Option<u32>) {
+    let (mut width, mut height) = (options.width, options.height);
+    if options.framed && width.is_none() && height.is_none() {
+        width = config.image_default_width;
+    }
+    if let (Some(max), Some(requested)) = (config.image_max_width, width)
+        && requested > max
+    {
+        height =
+            height.map(|height| (u64::from(height) * u64::from(max) / u64::from(requested)) as u32);
+        width = Some(max);
+    }
+    (width, height)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::temp_dir;
+
+    #[test]
+    fn test_parse_file_options() {
+        let options = FileOptions::parse(["thumb", "left", "120px", "upright", "A caption"]);
+        assert_eq!(
+            options,
+            FileOptions {
+                framed: true,
+                align: Some(Align::Left),
+                width: Some(120),
+                caption: Some("A caption".to_string()),
+                ..FileOptions::default()
+            }
+        );
+
+        let options = FileOptions::parse(["200x100px", "alt=Alt text", "link=Foo"]);
+        assert_eq!(options.width, Some(200));
+        assert_eq!(options.height, Some(100));
+        assert_eq!(options.alt.as_deref(), Some("Alt text"));
+        assert_eq!(options.caption, None);
+
+        assert_eq!(
+            split_options("thumb|See [[Lua/Server|the server]] and {{color|red|this}}"),
+            [
+                "thumb",
+                "See [[Lua/Server|the server]] and {{color|red|this}}"
+            ]
+        );
+
+        assert_eq!(
+            file_name("File: Win server step 2.png").as_deref(),
+            Some("Win_server_step_2.png")
+        );
+        assert_eq!(file_name("Lua/Server"), None);
+        assert_eq!(file_name(":File:Map.png"), None);
+        assert_eq!(
+            linked_file_name(":File:Map.png").as_deref(),
+            Some("Map.png")
+        );
+        assert_eq!(
+            linked_file_name("Media:Server map.png").as_deref(),
+            Some("Server_map.png")
+        );
+        assert_eq!(linked_file_name("File:Map.png"), None);
+    }
+
+    #[test]
+    fn test_oversized_images_are_clamped() {
+        let site = Site::new(
+            Config {
+                image_max_width: Some(800),
+                ..Config::default()
+            },
+            Default::default(),
+            Default::default(),
+        );
+        let render = |options: &[&str]| {
+            crate::test_util::render_to_string(render_file_link(
+                &site,
+                "Map.png",
+                &FileOptions::parse(options.iter().copied()),
+                None,
+            ))
+        };
+
+        let html = render(&["2000x1000px"]);
+        assert!(html.contains(r#"width="800""#), "{html}");
+        assert!(html.contains(r#"height="400""#), "{html}");
+        let html = render(&["thumb"]);
+        assert!(html.contains(r#"width="300""#), "{html}");
+        let html = render(&["120px"]);
+        assert!(html.contains(r#"width="120""#), "{html}");
+    }
+
+    #[test]
+    fn test_alignment_floats_figures() {
+        let site = Site::new(Config::default(), Default::default(), Default::default());
+        let render = |options: &[&str]| {
+            crate::test_util::render_to_string(render_file_link(
+                &site,
+                "Map.png",
+                &FileOptions::parse(options.iter().copied()),
+                None,
+            ))
+        };
+
+        let html = render(&["thumb", "left", "Caption"]);
+        assert!(html.contains(r#"<figure class="float-left "#), "{html}");
+        // Thumbnails float right unless told otherwise, like in MediaWiki
+        let html = render(&["thumb", "Caption"]);
+        assert!(html.contains(r#"<figure class="float-right "#), "{html}");
+        let html = render(&["center"]);
+        assert!(
+            html.contains(r#"<div class="mx-auto block w-fit">"#),
+            "{html}"
+        );
+        let html = render(&["thumb", "none", "Caption"]);
+        assert!(!html.contains("float-"), "{html}");
+    }
+
+    #[test]
+    fn test_large_image_gets_thumbnail() {
+        let dir = temp_dir("thumbnails");
+        let src = dir.join("media");
+        fs::create_dir_all(&src).unwrap();
+        image::RgbImage::from_pixel(800, 400, image::Rgb([200, 50, 50]))
+            .save(src.join("Large.png"))
+            .unwrap();
+        image::RgbImage::from_pixel(100, 50, image::Rgb([50, 200, 50]))
+            .save(src.join("Small.png"))
+            .unwrap();
+        fs::write(src.join("notes.txt"), "Not an image").unwrap();
+        fs::write(src.join("Corrupt.png"), "Not a PNG").unwrap();
+
+        let config = Config {
+            thumbnail_width: Some(200),
+            thumbnail_min_bytes: 0,
+            ..Config::default()
+        };
+        let dst = dir.join("output").join(MEDIA_DIRECTORY);
+        let warnings = Warnings::default();
+        let thumbnails = copy_media(&config, &warnings, &src, &dst)
+            .unwrap()
+            .thumbnails;
+
+        assert_eq!(thumbnails, BTreeSet::from(["Large.png".to_string()]));
+        assert_eq!(
+            image::image_dimensions(dst.join("thumbs/Large.png")).unwrap(),
+            (200, 100)
+        );
+        assert_eq!(
+            image::image_dimensions(dst.join("Large.png")).unwrap(),
+            (800, 400)
+        );
+        assert!(dst.join("Small.png").exists());
+        assert!(!dst.join("thumbs/Small.png").exists());
+        assert!(dst.join("notes.txt").exists());
+        // An image that can't be read is still copied
+        assert_eq!(fs::read(dst.join("Corrupt.png")).unwrap(), b"Not a PNG");
+        assert_eq!(warnings.len(), 1);
+    }
+}
@@ -0,0 +1,101 @@
+//! Namespaces: titles with a prefix such as `Template:` or `Help:`. The pages of a
+//! namespace live in its directory of the wiki, and are routed to the same directory of
+//! the output (`Template:Infobox` is `wiki/Template/Infobox.wikitext`).
+
+use std::collections::BTreeSet;
+
+use crate::{config::Config, site::Site};
+
+/// The namespace whose pages list the pages in them
+pub const CATEGORY_NAMESPACE: &str = "Category";
+
+/// The namespace of `title`, as configured, and the rest of the title, if it starts with
+/// one of the configured namespaces. Namespaces are matched case-insensitively, and a
+/// leading colon (`[[:Category:Guides]]`) is ignored.
+pub fn split<'a>(config: &'a Config, title: &'a str) -> Option<(&'a str, &'a str)> {
+    let (namespace, name) = title.trim().trim_start_matches(':').split_once(':')?;
+    let (namespace, _) = config
+        .namespaces
+        .iter()
+        .find(|(configured, _)| configured.eq_ignore_ascii_case(namespace.trim()))?;
+    let name = name.trim();
+    (!name.is_empty()).then_some((namespace.as_str(), name))
+}
+
+/// The path of `title` in the wiki and output directories, without an extension (e.g.
+/// `Template/Infobox` for `Template:Infobox`)
+pub fn title_path(config: &Config, title: &str) -> String {
+    match split(config, title) {
+        Some((namespace, name)) => format!(
+            "{}/{}",
+            config.namespaces[namespace],
+            crate::slug::title_slug(name)
+        ),
+        None => crate::slug::title_slug(title),
+    }
+}
+
+/// The title of the page at `path` in the wiki directory: `Template:Infobox` for
+/// `Template/Infobox` when `Template` is the directory of a namespace, or else the path
+/// itself
+pub fn title_from_path(config: &Config, path: &str) -> String {
+    let namespace = path.split_once('/').and_then(|(directory, name)| {
+        config
+            .namespaces
+            .iter()
+            .find(|(_, namespace_directory)| *namespace_directory == directory)
+            .map(|(namespace, _)| format!("{namespace}:{name}"))
+    });
+    namespace.unwrap_or_else(|| path.to_string())
+}
+
+/// The content of the page of a category that doesn't have a page of its own: a list of
+/// the pages in it
+pub fn render_category(site: &Site, members: &BTreeSet<String>) -> paxhtml::Element {
+    use paxhtml::html;
+
+    html! {
+        <>
+            <p>"Pages in this category:"</p>
+            <ul class="list-disc list-inside">
+                #{members.iter().map(|title| html! {
+                    <li>
+                        <a class={site.config.link_class.clone()} href={crate::page_url(&site.config, title)}>{title.clone()}</a>
+                    </li>
+                })}
+            </ul>
+        </>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_namespaced_titles_map_to_directories() {
+        let config = Config::default();
+
+        assert_eq!(
+            split(&config, "template:Infobox"),
+            Some(("Template", "Infobox"))
+        );
+        assert_eq!(
+            split(&config, ":Category:Guides"),
+            Some(("Category", "Guides"))
+        );
+        assert_eq!(split(&config, "Lua/Server"), None);
+        assert_eq!(split(&config, "Special:RecentChanges"), None);
+
+        assert_eq!(
+            title_path(&config, "Template:Vehicle Info"),
+            "Template/Vehicle_Info"
+        );
+        assert_eq!(title_path(&config, "Lua/Server"), "Lua/Server");
+        assert_eq!(
+            title_from_path(&config, "Template/Infobox"),
+            "Template:Infobox"
+        );
+        assert_eq!(title_from_path(&config, "Lua/Server"), "Lua/Server");
+    }
+}
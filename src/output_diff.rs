@@ -0,0 +1,136 @@
+//! `--diff`: which files a build added, changed or removed compared to the previous
+//! build in the same directory, for checking that a change only affected the pages it
+//! was meant to
+
+use std::{collections::BTreeMap, path::Path};
+
+use crate::content_hash::bytes_hash;
+
+/// The content hashes of the files in a directory, by their path relative to it
+#[derive(Debug, Default)]
+pub struct Snapshot {
+    hashes: BTreeMap<String, String>,
+}
+impl Snapshot {
+    /// Hash every file in `dir`. A directory that doesn't exist has no files.
+    pub fn take(dir: &Path) -> anyhow::Result<Self> {
+        fn scan(
+            root: &Path,
+            dir: &Path,
+            hashes: &mut BTreeMap<String, String>,
+        ) -> anyhow::Result<()> {
+            for entry in std::fs::read_dir(dir)? {
+                let path = entry?.path();
+                if path.is_dir() {
+                    scan(root, &path, hashes)?;
+                    continue;
+                }
+                let relative = path
+                    .strip_prefix(root)?
+                    .to_string_lossy()
+                    .replace('\\', "/");
+                hashes.insert(relative, bytes_hash(&std::fs::read(&path)?));
+            }
+            Ok(())
+        }
+
+        let mut hashes = BTreeMap::new();
+        if dir.is_dir() {
+            scan(dir, dir, &mut hashes)?;
+        }
+        Ok(Self { hashes })
+    }
+}
+
+/// The files that differ between two snapshots of a directory
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct OutputDiff {
+    pub added: Vec<String>,
+    pub changed: Vec<String>,
+    pub removed: Vec<String>,
+}
+impl OutputDiff {
+    pub fn new(before: &Snapshot, after: &Snapshot) -> Self {
+        let mut diff = Self::default();
+        for (path, hash) in &after.hashes {
+            match before.hashes.get(path) {
+                None => diff.added.push(path.clone()),
+                Some(previous) if previous != hash => diff.changed.push(path.clone()),
+                Some(_) => {}
+            }
+        }
+        diff.removed = before
+            .hashes
+            .keys()
+            .filter(|path| !after.hashes.contains_key(*path))
+            .cloned()
+            .collect();
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.changed.is_empty() && self.removed.is_empty()
+    }
+
+    /// Print the files that differ in the output directory `dir`, one per line
+    pub fn print(&self, dir: &Path) {
+        if self.is_empty() {
+            println!("No files changed in {}", dir.display());
+            return;
+        }
+        println!(
+            "{} added, {} changed and {} removed file(s) in {}:",
+            self.added.len(),
+            self.changed.len(),
+            self.removed.len(),
+            dir.display()
+        );
+        for (status, paths) in [
+            ("added", &self.added),
+            ("changed", &self.changed),
+            ("removed", &self.removed),
+        ] {
+            for path in paths {
+                println!("  {status:<8}{path}");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::temp_dir;
+
+    #[test]
+    fn test_only_modified_files_are_reported() {
+        let dir = temp_dir("output_diff");
+        std::fs::create_dir_all(dir.join("wiki")).unwrap();
+        std::fs::write(dir.join("wiki/Main_Page.html"), "Welcome").unwrap();
+        std::fs::write(dir.join("wiki/Vehicle.html"), "Vehicles").unwrap();
+        std::fs::write(dir.join("wiki/Old.html"), "Old").unwrap();
+        let before = Snapshot::take(&dir).unwrap();
+
+        std::fs::write(dir.join("wiki/Vehicle.html"), "Vehicles, updated").unwrap();
+        std::fs::write(dir.join("wiki/Main_Page.html"), "Welcome").unwrap();
+        std::fs::remove_file(dir.join("wiki/Old.html")).unwrap();
+        std::fs::write(dir.join("wiki/New.html"), "New").unwrap();
+        let after = Snapshot::take(&dir).unwrap();
+
+        assert_eq!(
+            OutputDiff::new(&before, &after),
+            OutputDiff {
+                added: vec!["wiki/New.html".to_string()],
+                changed: vec!["wiki/Vehicle.html".to_string()],
+                removed: vec!["wiki/Old.html".to_string()],
+            }
+        );
+        assert!(OutputDiff::new(&after, &after).is_empty());
+        assert!(
+            Snapshot::take(&dir.join("missing"))
+                .unwrap()
+                .hashes
+                .is_empty()
+        );
+    }
+}
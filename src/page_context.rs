@@ -1,6 +1,11 @@
-use std::path::PathBuf;
+use std::{cell::RefCell, path::PathBuf};
 
-pub struct PageContext {
+use crate::{
+    footnotes::Footnotes, heading_numbers::SectionNumbers, param_docs::ParamDocs, site::Site,
+    toc::Toc,
+};
+
+pub struct PageContext<'a> {
     /// The path to the input file
     pub input_path: PathBuf,
     /// The title of the page
@@ -10,8 +15,38 @@ pub struct PageContext {
     pub route_path: paxhtml::RoutePath,
     /// The last part of the title of the page, without the extension
     pub sub_page_name: String,
+    /// The site the page belongs to
+    pub site: &'a Site,
+    /// State accumulated while rendering the page
+    pub state: RefCell<PageState>,
+}
+
+/// State accumulated while rendering a page
+#[derive(Debug, Default)]
+pub struct PageState {
+    pub footnotes: Footnotes,
+    /// The template parameters described by `{{Param}}` that haven't been rendered yet
+    pub param_docs: ParamDocs,
+    /// Whether the page contains `<math>`, and so needs the math backend's assets
+    pub has_math: bool,
+    /// Whether the page has a table long enough to be collapsed, and so needs the script
+    /// that collapses it
+    pub has_collapsed_table: bool,
+    /// The categories the page is in, from its `[[Category:...]]` links, in the order
+    /// they first appear
+    pub categories: Vec<String>,
+    /// The page's headings, in order
+    pub toc: Toc,
+    /// The section numbers of the page's headings, when they're numbered
+    pub section_numbers: SectionNumbers,
+}
+impl PageContext<'_> {
+    /// Record a warning about this page
+    pub fn warn(&self, message: impl std::fmt::Display) {
+        self.site.warnings.warn(format!("{self}: {message}"));
+    }
 }
-impl std::fmt::Display for PageContext {
+impl std::fmt::Display for PageContext<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{} (from {})", self.title, self.input_path.display())
     }
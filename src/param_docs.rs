@@ -0,0 +1,104 @@
+//! The parameter tables of template documentation pages, collected from `{{Param}}`
+//! calls and rendered by `{{Params}}` (or at the end of the page)
+
+/// The parameters described on a page, and the `{{Params}}` tables they're listed in
+#[derive(Debug, Default)]
+pub struct ParamDocs {
+    /// The parameters described since the last `{{Params}}`
+    params: Vec<ParamDoc>,
+    /// The parameters listed by each `{{Params}}` so far
+    tables: Vec<Vec<ParamDoc>>,
+}
+
+#[derive(Debug)]
+struct ParamDoc {
+    name: String,
+    description: paxhtml::Element,
+    required: bool,
+}
+
+impl ParamDocs {
+    pub fn is_empty(&self) -> bool {
+        self.params.is_empty()
+    }
+
+    /// Whether the page has a `{{Params}}`, and so needs [`ParamDocs::fill`]
+    pub fn has_tables(&self) -> bool {
+        !self.tables.is_empty()
+    }
+
+    /// Record a `{{Param|name|description|required=yes}}`
+    pub fn add(&mut self, name: &str, description: paxhtml::Element, required: bool) {
+        self.params.push(ParamDoc {
+            name: name.trim().to_string(),
+            description,
+            required,
+        });
+    }
+
+    /// Mark where a `{{Params}}` table goes. It lists the parameters described since the
+    /// previous one, and is rendered by [`ParamDocs::fill`] once the page is converted.
+    pub fn placeholder(&mut self) -> paxhtml::Element {
+        self.tables.push(std::mem::take(&mut self.params));
+        paxhtml::Element::Raw {
+            html: marker(self.tables.len() - 1),
+        }
+    }
+
+    /// Replace the placeholders in the page's `html` with their tables. Parameters
+    /// described after the last `{{Params}}` are listed by it.
+    pub fn fill(&mut self, html: &str) -> anyhow::Result<String> {
+        if let Some(last) = self.tables.last_mut() {
+            last.append(&mut self.params);
+        }
+        let mut html = html.to_string();
+        for (index, params) in std::mem::take(&mut self.tables).into_iter().enumerate() {
+            let table = if params.is_empty() {
+                String::new()
+            } else {
+                paxhtml::Document::new([render(params)]).write_to_string()?
+            };
+            html = html.replace(&marker(index), &table);
+        }
+        Ok(html)
+    }
+
+    /// Render the pending parameters as a table, clearing them
+    pub fn render(&mut self) -> paxhtml::Element {
+        render(std::mem::take(&mut self.params))
+    }
+}
+
+/// The placeholder for the `index`th `{{Params}}` table on a page
+fn marker(index: usize) -> String {
+    format!("<!--params-{index}-->")
+}
+
+/// The table listing `params`
+fn render(params: Vec<ParamDoc>) -> paxhtml::Element {
+    use paxhtml::html;
+
+    html! {
+        <table class="min-w-full my-4 divide-y divide-gray-200 border border-gray-300">
+            <thead class="bg-gray-800 text-white">
+                <tr>
+                    <th class="px-4 py-2 text-left" scope="col">"Parameter"</th>
+                    <th class="px-4 py-2 text-left" scope="col">"Description"</th>
+                    <th class="px-4 py-2 text-left" scope="col">"Required"</th>
+                </tr>
+            </thead>
+            <tbody class="divide-y divide-gray-200">
+                #{params.into_iter().map(|param| {
+                    let required = if param.required { "Yes" } else { "No" };
+                    html! {
+                        <tr class="bg-white">
+                            <td class="px-4 py-2 font-mono">{param.name}</td>
+                            <td class="px-4 py-2">{param.description}</td>
+                            <td class="px-4 py-2">{required}</td>
+                        </tr>
+                    }
+                })}
+            </tbody>
+        </table>
+    }
+}
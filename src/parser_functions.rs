@@ -0,0 +1,260 @@
+//! The parser functions (`{{#len:...}}`, `{{#ifeq:...}}`, ...), operating on arguments
+//! that have already been expanded
+
+use crate::wikitext;
+
+/// `{{#len:text}}`: the number of characters in `text`
+pub fn len(text: &str) -> String {
+    text.chars().count().to_string()
+}
+
+/// `{{#sub:text|start|length}}`: the characters of `text` from the zero-based `start`,
+/// counted from the end if negative. A positive `length` takes that many characters, a
+/// negative one leaves that many off the end, and zero or none takes the rest. Indices
+/// outside of `text` are clamped to it.
+pub fn sub(text: &str, start: &str, length: &str) -> String {
+    let chars = text.chars().collect::<Vec<_>>();
+    let count = chars.len() as i64;
+    let start = start.trim().parse::<i64>().unwrap_or(0);
+    let length = length.trim().parse::<i64>().unwrap_or(0);
+
+    let start = if start < 0 {
+        (count + start).max(0)
+    } else {
+        start.min(count)
+    };
+    let end = match length {
+        0 => count,
+        length if length < 0 => (count + length).max(start),
+        length => start.saturating_add(length).min(count),
+    };
+    chars[start as usize..end as usize].iter().collect()
+}
+
+/// `{{#replace:text|search|replacement}}`: `text` with every occurrence of `search`
+/// replaced. As in MediaWiki, an empty `search` searches for a space.
+pub fn replace(text: &str, search: &str, replacement: &str) -> String {
+    let search = if search.is_empty() { " " } else { search };
+    text.replace(search, replacement)
+}
+
+/// `{{#explode:text|delimiter|position|limit}}`: the zero-based `position`th piece of
+/// `text` split on `delimiter`, counted from the end if negative. An empty `delimiter`
+/// splits on spaces, and a `limit` caps the number of pieces, with the last holding the
+/// rest of the text. Positions outside of the pieces give nothing.
+pub fn explode(text: &str, delimiter: &str, position: &str, limit: &str) -> String {
+    let delimiter = if delimiter.is_empty() { " " } else { delimiter };
+    let pieces = match limit.trim().parse::<usize>() {
+        Ok(limit) if limit > 0 => text.splitn(limit, delimiter).collect::<Vec<_>>(),
+        _ => text.split(delimiter).collect(),
+    };
+
+    let position = position.trim().parse::<i64>().unwrap_or(0);
+    let index = if position < 0 {
+        pieces.len() as i64 + position
+    } else {
+        position
+    };
+    usize::try_from(index)
+        .ok()
+        .and_then(|index| pieces.get(index))
+        .map_or_else(String::new, |piece| piece.to_string())
+}
+
+/// `{{#if:test|...}}`: whether `test` is truthy, which any text other than whitespace is
+pub fn truthy(test: &str) -> bool {
+    !test.trim().is_empty()
+}
+
+/// `{{#ifeq:a|b|...}}`: whether `a` and `b` are equal, compared as numbers if both are
+/// finite numbers and as text otherwise. Words like `nan` and `inf` parse as numbers,
+/// but aren't treated as them.
+pub fn ifeq(a: &str, b: &str) -> bool {
+    match (a.parse::<f64>(), b.parse::<f64>()) {
+        (Ok(a), Ok(b)) if a.is_finite() && b.is_finite() => a == b,
+        _ => a == b,
+    }
+}
+
+/// `{{#switch:value|case=result|...}}`: the result of the first case that is `value`,
+/// compared case-sensitively. Cases without a result (`|a|b=result`) fall through to
+/// the next result. Without a match, the result is that of `#default`, or the last
+/// argument if it has no `=`, or else nothing.
+///
+/// The cases are as written: `expand` gives the text of a case's key, so only the keys
+/// up to the match are expanded, and the result is returned for the caller to expand.
+pub fn switch<'a>(
+    value: &str,
+    cases: &'a [String],
+    mut expand: impl FnMut(&str) -> String,
+) -> &'a str {
+    let value = value.trim();
+    let mut matched = false;
+    let mut default = None;
+    for (i, case) in cases.iter().enumerate() {
+        match case.split_once('=') {
+            Some((key, result)) => {
+                if matched {
+                    return result.trim();
+                }
+                if key.trim() == "#default" {
+                    default = Some(result.trim());
+                } else if expand(key).trim() == value {
+                    return result.trim();
+                }
+            }
+            None => {
+                matched = matched || expand(case).trim() == value;
+                if i + 1 == cases.len() {
+                    default = Some(case.trim());
+                }
+            }
+        }
+    }
+    default.unwrap_or_default()
+}
+
+/// The content of the section `name` in the wikitext `source`: everything between
+/// `<section begin=name />` and `<section end=name />`. A section marked more than once
+/// has its parts joined, and the markers of sections within it are removed. `None` if
+/// there's no such section.
+pub fn labeled_section(source: &str, name: &str) -> Option<String> {
+    let mut content: Option<String> = None;
+    let mut begin = None;
+    for (start, end) in section_markers(source) {
+        let attributes = source[start + "<section".len()..end - 1].trim_end_matches('/');
+        for (key, value) in wikitext::tag_attributes(attributes) {
+            if value.trim() != name {
+                continue;
+            }
+            if key.eq_ignore_ascii_case("begin") && begin.is_none() {
+                begin = Some(end);
+            } else if key.eq_ignore_ascii_case("end")
+                && let Some(content_start) = begin.take()
+            {
+                content
+                    .get_or_insert_default()
+                    .push_str(&source[content_start..start]);
+            }
+        }
+    }
+
+    let content = content?;
+    let mut stripped = String::new();
+    let mut last = 0;
+    for (start, end) in section_markers(&content) {
+        stripped.push_str(&content[last..start]);
+        last = end;
+    }
+    stripped.push_str(&content[last..]);
+    Some(stripped)
+}
+
+/// The spans of the `<section ... />` markers in `text`
+fn section_markers(text: &str) -> Vec<(usize, usize)> {
+    let mut markers = vec![];
+    let mut offset = 0;
+    while let Some(start) = text[offset..].find("<section").map(|i| offset + i) {
+        let Some(end) = text[start..].find('>').map(|i| start + i + 1) else {
+            break;
+        };
+        markers.push((start, end));
+        offset = end;
+    }
+    markers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_len() {
+        assert_eq!(len("Vehicle"), "7");
+        assert_eq!(len("Überfahrt"), "9");
+        assert_eq!(len(""), "0");
+    }
+
+    #[test]
+    fn test_sub() {
+        assert_eq!(sub("Lua/Server", "0", "3"), "Lua");
+        assert_eq!(sub("Lua/Server", "4", ""), "Server");
+        assert_eq!(sub("Lua/Server", "-6", ""), "Server");
+        assert_eq!(sub("Lua/Server", "-6", "3"), "Ser");
+        assert_eq!(sub("Lua/Server", "0", "-7"), "Lua");
+
+        // Out of range indices are clamped
+        assert_eq!(sub("Lua", "10", "2"), "");
+        assert_eq!(sub("Lua", "-10", "2"), "Lu");
+        assert_eq!(sub("Lua", "1", "-10"), "");
+    }
+
+    #[test]
+    fn test_ifeq() {
+        assert!(ifeq("developer", "developer"));
+        assert!(!ifeq("developer", "Developer"));
+        assert!(ifeq("01", "1.0"));
+        assert!(!ifeq("1", "1x"));
+        assert!(ifeq("nan", "nan"));
+        assert!(!ifeq("inf", "infinity"));
+        assert!(!ifeq("1e999", "inf"));
+    }
+
+    #[test]
+    fn test_switch() {
+        let cases = ["car = Wheels", "boat | plane = Other", "#default = Unknown"]
+            .iter()
+            .flat_map(|case| case.split('|'))
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+        let switch = |value: &str, cases: &[String]| switch(value, cases, str::to_string);
+        assert_eq!(switch("car", &cases), "Wheels");
+        assert_eq!(switch(" boat ", &cases), "Other");
+        assert_eq!(switch("plane", &cases), "Other");
+        assert_eq!(switch("Car", &cases), "Unknown");
+
+        let cases = ["a = 1".to_string(), "Fallback".to_string()];
+        assert_eq!(switch("b", &cases), "Fallback");
+        assert_eq!(switch("b", &cases[..1]), "");
+    }
+
+    #[test]
+    fn test_labeled_section() {
+        let source = "a <section begin=x />b <section begin=y/>c<section end=y /> d<section end=x /> e <section begin=x />f<section end=x />";
+        assert_eq!(labeled_section(source, "x").as_deref(), Some("b c df"));
+        assert_eq!(labeled_section(source, "y").as_deref(), Some("c"));
+        assert_eq!(labeled_section(source, "z"), None);
+        assert_eq!(
+            labeled_section(
+                r#"<section begin="two words" />g<section end="two words" />"#,
+                "two words"
+            )
+            .as_deref(),
+            Some("g")
+        );
+    }
+
+    #[test]
+    fn test_replace() {
+        assert_eq!(replace("a-b-c", "-", "/"), "a/b/c");
+        assert_eq!(replace("Lua Server", "", "_"), "Lua_Server");
+        assert_eq!(replace("Vehicle", "x", "y"), "Vehicle");
+        assert_eq!(replace("a-b", "-", ""), "ab");
+    }
+
+    #[test]
+    fn test_explode() {
+        assert_eq!(explode("Lua/Server/Vehicle", "/", "1", ""), "Server");
+        assert_eq!(explode("Lua/Server/Vehicle", "/", "-1", ""), "Vehicle");
+        assert_eq!(explode("Lua/Server/Vehicle", "/", "-3", ""), "Lua");
+        assert_eq!(
+            explode("Lua/Server/Vehicle", "/", "1", "2"),
+            "Server/Vehicle"
+        );
+        assert_eq!(explode("And if you", "", "2", ""), "you");
+
+        // Out of range positions give nothing
+        assert_eq!(explode("Lua/Server", "/", "5", ""), "");
+        assert_eq!(explode("Lua/Server", "/", "-5", ""), "");
+    }
+}
@@ -0,0 +1,201 @@
+//! The print document: every page of the wiki in one HTML file, with a cover, a table
+//! of contents, and each page starting on a new sheet, for printing or converting to a
+//! PDF for offline reading
+
+use std::{collections::HashMap, path::Path, process::Command};
+
+use crate::site::Site;
+
+/// The print document, in the output root
+pub const PRINT_DOCUMENT: &str = "print.html";
+/// The PDF that `pdf_command` converts the print document to, in the output root
+pub const PDF_DOCUMENT: &str = "wiki.pdf";
+
+/// The styles of the print document. Each page's section starts a new sheet, and code
+/// blocks wrap since a sheet can't be scrolled.
+const PRINT_STYLE: &str = "@page { margin: 2cm; }
+.print-toc, .print-page { break-before: page; page-break-before: always; }
+.print-page h2, .print-page h3 { break-after: avoid; page-break-after: avoid; }
+.print-page pre { white-space: pre-wrap; overflow: visible; }
+.print-page table, .print-page figure { break-inside: avoid; page-break-inside: avoid; }";
+
+/// A rendered page in the print document
+pub struct PrintPage {
+    pub title: String,
+    /// The URL the page has on the site, which links to it are rewritten from
+    pub url: String,
+    /// The page's rendered content, without the site's layout
+    pub body: String,
+}
+
+/// The `id` of the section holding the page `title` in the print document
+pub fn section_id(title: &str) -> String {
+    format!("page-{}", crate::slug::title_slug(title))
+}
+
+/// `html` with the value of each `attribute` (including its `="`) replaced by `f`
+fn rewrite_attribute(html: &str, attribute: &str, f: impl Fn(&str) -> String) -> String {
+    let mut result = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(start) = rest.find(attribute) {
+        let (before, after) = rest.split_at(start + attribute.len());
+        result.push_str(before);
+        let end = after.find('"').unwrap_or(after.len());
+        result.push_str(&f(&after[..end]));
+        rest = &after[end..];
+    }
+    result.push_str(rest);
+    result
+}
+
+/// The body of the page with the section `section` in the print document. The `id`s in
+/// it are prefixed with `section`, as they're only unique within a page, and links to
+/// pages with a section in the print document point at that section instead.
+fn rewrite_body(html: &str, section: &str, sections: &HashMap<&str, String>) -> String {
+    let html = rewrite_attribute(html, " id=\"", |id| format!("{section}-{id}"));
+    rewrite_attribute(&html, "href=\"", |url| {
+        let (path, fragment) = url.split_once('#').unwrap_or((url, ""));
+        let section = match path {
+            "" => section,
+            path => match sections.get(path) {
+                Some(section) => section,
+                None => return url.to_string(),
+            },
+        };
+        match fragment {
+            "" => format!("#{section}"),
+            fragment => format!("#{section}-{fragment}"),
+        }
+    })
+}
+
+/// The print document for `pages`, in order
+pub fn document(site: &Site, site_name: &str, pages: &[PrintPage]) -> anyhow::Result<String> {
+    use paxhtml::html;
+
+    let sections = pages
+        .iter()
+        .map(|page| (page.url.as_str(), section_id(&page.title)))
+        .collect::<HashMap<_, _>>();
+
+    let document = paxhtml::Document::new([
+        paxhtml::builder::doctype(["html".into()]),
+        html! {
+            <html lang={site.config.language.clone()}>
+            <head>
+                <meta charset="UTF-8" />
+                <title>{site_name.to_string()}</title>
+                <link href="style/tailwind.css" rel="stylesheet" />
+                <link href="style/syntax.css" rel="stylesheet" />
+                <style>{paxhtml::Element::Raw { html: PRINT_STYLE.to_string() }}</style>
+            </head>
+            <body class="bg-white">
+                <section class="print-cover flex flex-col justify-center min-h-screen text-center">
+                    <h1 class="text-5xl font-bold mb-6">{site_name.to_string()}</h1>
+                    <p class="text-gray-600">{format!("Built on {}", site.build_date)}</p>
+                </section>
+                <nav class="print-toc">
+                    <h1 class="text-3xl font-bold mb-6">"Contents"</h1>
+                    <ol class="list-decimal list-inside space-y-1">
+                        #{pages.iter().map(|page| html! {
+                            <li>
+                                <a class={site.config.link_class.clone()} href={format!("#{}", sections[page.url.as_str()])}>{page.title.clone()}</a>
+                            </li>
+                        })}
+                    </ol>
+                </nav>
+                #{pages.iter().map(|page| html! {
+                    <section class="print-page" id={sections[page.url.as_str()].clone()}>
+                        <h1 class="text-3xl font-bold border-b-2 border-gray-300 pb-2 mb-6">{page.title.clone()}</h1>
+                        <div class="space-y-4">
+                            {paxhtml::Element::Raw { html: rewrite_body(&page.body, &sections[page.url.as_str()], &sections) }}
+                        </div>
+                    </section>
+                })}
+            </body>
+            </html>
+        },
+    ]);
+    Ok(document.write_to_string()?)
+}
+
+/// Write the print document for the pages rendered so far to the output root `dst`,
+/// starting with the main page, and convert it to a PDF with `pdf_command` if it's set
+pub fn write(site: &Site, site_name: &str, dst: &Path) -> anyhow::Result<()> {
+    let mut pages = site
+        .print_pages
+        .borrow()
+        .iter()
+        .map(|(title, body)| PrintPage {
+            title: title.clone(),
+            url: crate::page_url(&site.config, title),
+            body: body.clone(),
+        })
+        .collect::<Vec<_>>();
+    pages.sort_by(|a, b| {
+        (a.title != site.config.main_page, &a.title)
+            .cmp(&(b.title != site.config.main_page, &b.title))
+    });
+
+    let input = dst.join(PRINT_DOCUMENT);
+    std::fs::write(&input, document(site, site_name, &pages)?)?;
+
+    let Some((program, args)) = site.config.pdf_command.split_first() else {
+        return Ok(());
+    };
+    let output = dst.join(PDF_DOCUMENT);
+    let args = args.iter().map(|arg| {
+        arg.replace("{input}", &input.to_string_lossy())
+            .replace("{output}", &output.to_string_lossy())
+    });
+    let status = Command::new(program)
+        .args(args)
+        .status()
+        .map_err(|e| anyhow::anyhow!("Failed to run {program} to generate {PDF_DOCUMENT}: {e}"))?;
+    if !status.success() {
+        anyhow::bail!("{program} failed to generate {PDF_DOCUMENT} ({status})");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    #[test]
+    fn test_print_document_has_a_section_per_page() {
+        let site = Site::new(Config::default(), Default::default(), Default::default());
+        let pages = [
+            PrintPage {
+                title: "Main Page".to_string(),
+                url: "/wiki/Main_Page.html".to_string(),
+                body: r#"<p>See <a href="/wiki/Vehicle.html#Models">the models</a> or <a href="https://example.com/">elsewhere</a>.</p>"#.to_string(),
+            },
+            PrintPage {
+                title: "Vehicle".to_string(),
+                url: "/wiki/Vehicle.html".to_string(),
+                body: r##"<h2 id="Models">Models</h2><p><a href="#Models">Models</a> <a href="/wiki/Missing.html">Missing</a></p>"##.to_string(),
+            },
+        ];
+        let html = document(&site, "JC2-MP Documentation", &pages).unwrap();
+
+        assert!(html.contains("break-before: page"), "{html}");
+        assert_eq!(html.matches(r#"class="print-page""#).count(), 2, "{html}");
+        assert!(html.contains(r#"class="print-toc""#), "{html}");
+        assert!(html.contains(r#"id="page-Vehicle""#), "{html}");
+        // Links between pages in the document stay within it, and heading ids are
+        // prefixed with their page's section so they don't collide
+        assert!(html.contains(r#"id="page-Vehicle-Models""#), "{html}");
+        assert!(
+            html.contains(r##"href="#page-Vehicle-Models">the models</a>"##),
+            "{html}"
+        );
+        assert!(
+            html.contains(r##"href="#page-Vehicle-Models">Models</a>"##),
+            "{html}"
+        );
+        assert!(html.contains(r#"href="https://example.com/""#), "{html}");
+        assert!(html.contains(r#"href="/wiki/Missing.html""#), "{html}");
+    }
+}
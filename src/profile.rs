@@ -0,0 +1,68 @@
+//! Timing of page rendering and template instantiation, collected with `--profile` to
+//! find slow pages
+
+use std::{cell::RefCell, collections::HashMap, time::Duration};
+
+/// How many of the slowest pages and templates are printed
+pub const PROFILE_TOP_N: usize = 10;
+
+/// The time spent instantiating one template, including any templates it uses
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TemplateTiming {
+    pub count: usize,
+    pub total: Duration,
+}
+
+#[derive(Debug, Default)]
+pub struct Profile {
+    pages: RefCell<Vec<(String, Duration)>>,
+    templates: RefCell<HashMap<String, TemplateTiming>>,
+}
+impl Profile {
+    pub fn record_page(&self, title: &str, duration: Duration) {
+        self.pages.borrow_mut().push((title.to_string(), duration));
+    }
+
+    pub fn record_template(&self, name: &str, duration: Duration) {
+        let mut templates = self.templates.borrow_mut();
+        let timing = templates.entry(name.to_string()).or_default();
+        timing.count += 1;
+        timing.total += duration;
+    }
+
+    /// The `n` pages that took longest to render, slowest first
+    pub fn slowest_pages(&self, n: usize) -> Vec<(String, Duration)> {
+        let mut pages = self.pages.borrow().clone();
+        pages.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        pages.truncate(n);
+        pages
+    }
+
+    /// The `n` templates that took longest to instantiate in total, most expensive first
+    pub fn most_expensive_templates(&self, n: usize) -> Vec<(String, TemplateTiming)> {
+        let mut templates = self
+            .templates
+            .borrow()
+            .iter()
+            .map(|(name, timing)| (name.clone(), *timing))
+            .collect::<Vec<_>>();
+        templates.sort_by(|a, b| b.1.total.cmp(&a.1.total).then_with(|| a.0.cmp(&b.0)));
+        templates.truncate(n);
+        templates
+    }
+
+    /// Print the slowest pages and templates
+    pub fn print(&self, n: usize) {
+        eprintln!("Slowest pages:");
+        for (title, duration) in self.slowest_pages(n) {
+            eprintln!("  {:>10.2?}  {title}", duration);
+        }
+        eprintln!("Most expensive templates:");
+        for (name, timing) in self.most_expensive_templates(n) {
+            eprintln!(
+                "  {:>10.2?}  {name} ({} instantiation(s))",
+                timing.total, timing.count
+            );
+        }
+    }
+}
@@ -0,0 +1,152 @@
+//! The `Special/RecentChanges` page, listing the pages that were modified most recently
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+    time::UNIX_EPOCH,
+};
+
+use crate::{build_date::BuildDate, site::Site};
+
+/// The title of the page listing the recent changes
+pub const RECENT_CHANGES: &str = "Special/RecentChanges";
+/// The prefix of the titles of generated pages, which aren't listed as changes
+pub const SPECIAL_PREFIX: &str = "Special/";
+
+/// The `count` most recently modified of the pages given as `(title, path)`, with the
+/// time they were last modified in seconds since the Unix epoch, newest first.
+///
+/// The times come from the git history of `src` where there is one, and from the
+/// filesystem otherwise.
+pub fn recent_changes<'a>(
+    src: &Path,
+    pages: impl IntoIterator<Item = (&'a str, &'a Path)>,
+    count: usize,
+) -> Vec<(String, i64)> {
+    let commit_times = git_commit_times(src);
+
+    let mut changes = pages
+        .into_iter()
+        .filter(|(title, _)| !title.starts_with(SPECIAL_PREFIX))
+        .filter_map(|(title, path)| {
+            let time = fs::canonicalize(path)
+                .ok()
+                .and_then(|path| commit_times.get(&path).copied())
+                .or_else(|| modified_time(path))?;
+            Some((title.to_string(), time))
+        })
+        .collect::<Vec<_>>();
+    changes.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    changes.truncate(count);
+    changes
+}
+
+/// The time of the last commit to touch each file in `src`, keyed by canonical path.
+/// Empty if `src` isn't in a git repository.
+fn git_commit_times(src: &Path) -> HashMap<PathBuf, i64> {
+    let Ok(output) = Command::new("git")
+        .arg("-C")
+        .arg(src)
+        .args([
+            "log",
+            "--format=format:%x00%ct",
+            "--name-only",
+            "--relative",
+            "--",
+            ".",
+        ])
+        .output()
+    else {
+        return HashMap::new();
+    };
+    if !output.status.success() {
+        return HashMap::new();
+    }
+
+    // Commits are listed newest first, so the first time seen for a file is its latest
+    let mut times = HashMap::new();
+    let mut time = None;
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        if let Some(timestamp) = line.strip_prefix('\0') {
+            time = timestamp.trim().parse::<i64>().ok();
+        } else if let Some(time) = time
+            && !line.is_empty()
+            && let Ok(path) = fs::canonicalize(src.join(line))
+        {
+            times.entry(path).or_insert(time);
+        }
+    }
+    times
+}
+
+fn modified_time(path: &Path) -> Option<i64> {
+    let modified = fs::metadata(path).ok()?.modified().ok()?;
+    Some(modified.duration_since(UNIX_EPOCH).ok()?.as_secs() as i64)
+}
+
+/// The content of the recent changes page
+pub fn render(site: &Site, changes: &[(String, i64)]) -> paxhtml::Element {
+    use paxhtml::html;
+
+    html! {
+        <ul class="space-y-1">
+            #{changes.iter().map(|(title, time)| html! {
+                <li>
+                    <span class="mr-4 font-mono text-sm text-gray-500">
+                        {BuildDate::from_unix_seconds(*time).to_string()}
+                    </span>
+                    <a class={site.config.link_class.clone()} href={crate::page_url(&site.config, title)}>
+                        {title.clone()}
+                    </a>
+                </li>
+            })}
+        </ul>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use super::*;
+    use crate::test_util::temp_dir;
+
+    #[test]
+    fn test_recent_changes_are_newest_first() {
+        let dir = temp_dir("recent_changes");
+        let day = Duration::from_secs(86_400);
+        let write = |name: &str, age_in_days: u32| {
+            let path = dir.join(name);
+            fs::write(&path, name).unwrap();
+            fs::File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(SystemTime::now() - day * age_in_days)
+                .unwrap();
+            path
+        };
+        let old = write("Old.wikitext", 30);
+        let new = write("New.wikitext", 1);
+        let middle = write("Middle.wikitext", 7);
+        let special = write("Special.wikitext", 0);
+
+        let pages = [
+            ("Old", old.as_path()),
+            ("New", new.as_path()),
+            ("Middle", middle.as_path()),
+            ("Special/Something", special.as_path()),
+        ];
+        let titles = |count| {
+            recent_changes(&dir, pages, count)
+                .into_iter()
+                .map(|(title, _)| title)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(titles(10), ["New", "Middle", "Old"]);
+        assert_eq!(titles(2), ["New", "Middle"]);
+    }
+}
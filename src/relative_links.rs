@@ -0,0 +1,94 @@
+//! Rewriting the root-relative URLs in a rendered page (`/wiki/...`) relative to the
+//! page, so that the site works when opened from the filesystem or served from a
+//! subdirectory
+
+/// The text before the URLs that are rewritten: links, assets, the search index, and the
+/// targets of redirect pages
+const URL_PREFIXES: [&str; 4] = ["href=\"", "src=\"", "data-index=\"", "; url="];
+
+/// `html`, the page at the URL path `page_path` (e.g. `/wiki/Lua/Server.html`), with the
+/// root-relative URLs in it made relative to the page
+pub fn relativize(html: &str, page_path: &str) -> String {
+    let find =
+        |prefix: &str, from: usize| html[from..].find(prefix).map(|i| from + i + prefix.len());
+    let mut next = URL_PREFIXES.map(|prefix| find(prefix, 0));
+
+    let mut output = String::with_capacity(html.len());
+    let mut copied = 0;
+    while let Some(start) = next.iter().flatten().min().copied() {
+        let end = html[start..].find('"').map_or(html.len(), |i| start + i);
+        let url = &html[start..end];
+        if url.starts_with('/') && !url.starts_with("//") {
+            output.push_str(&html[copied..start]);
+            output.push_str(&relative_url(url, page_path));
+            copied = end;
+        }
+        for (prefix, next) in URL_PREFIXES.iter().zip(&mut next) {
+            if next.is_some_and(|next| next <= end) {
+                *next = find(prefix, end);
+            }
+        }
+    }
+    output.push_str(&html[copied..]);
+    output
+}
+
+/// The root-relative `url` relative to the page at `page_path`, keeping its fragment and
+/// query. URLs of directories point at their `index.html`, as there's no server to.
+pub fn relative_url(url: &str, page_path: &str) -> String {
+    let (path, suffix) = url.find(['#', '?']).map_or((url, ""), |i| url.split_at(i));
+
+    let mut page_directories = page_path
+        .trim_start_matches('/')
+        .split('/')
+        .collect::<Vec<_>>();
+    page_directories.pop();
+    let mut target = path.trim_start_matches('/').split('/').collect::<Vec<_>>();
+    if target.last().is_some_and(|last| last.is_empty()) {
+        target.pop();
+        target.push("index.html");
+    }
+
+    let common = page_directories
+        .iter()
+        .zip(&target[..target.len() - 1])
+        .take_while(|(a, b)| a == b)
+        .count();
+    let mut relative = "../".repeat(page_directories.len() - common);
+    relative.push_str(&target[common..].join("/"));
+    relative.push_str(suffix);
+    relative
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_urls_are_made_relative_to_the_page() {
+        let page = "/wiki/Lua/Server.html";
+        assert_eq!(relative_url("/wiki/Lua/Client.html", page), "Client.html");
+        assert_eq!(
+            relative_url("/wiki/Lua/Server.html#Events", page),
+            "Server.html#Events"
+        );
+        assert_eq!(
+            relative_url("/wiki/Main_Page.html", page),
+            "../Main_Page.html"
+        );
+        assert_eq!(
+            relative_url("/style/tailwind.css", page),
+            "../../style/tailwind.css"
+        );
+        assert_eq!(relative_url("/", page), "../../index.html");
+        assert_eq!(relative_url("/wiki/Lua/Client/", page), "Client/index.html");
+
+        assert_eq!(
+            relativize(
+                r#"<a href="/wiki/Lua/Client.html">a</a><a href="https://jc-mp.com/">b</a><script src="/js/toc.js"></script>"#,
+                page
+            ),
+            r#"<a href="Client.html">a</a><a href="https://jc-mp.com/">b</a><script src="../../js/toc.js"></script>"#
+        );
+    }
+}
@@ -0,0 +1,55 @@
+//! The `robots.txt` written to the output root, with the crawl rules from the
+//! configuration and a pointer to the sitemap
+
+use std::{fmt::Write as _, path::Path};
+
+use crate::config::Config;
+
+/// The file name of the sitemap, relative to the output root
+pub const SITEMAP_FILE: &str = "sitemap.xml";
+
+/// The contents of `robots.txt`
+pub fn robots_txt(config: &Config) -> String {
+    let mut robots = "User-agent: *\n".to_string();
+    for path in &config.robots_allow {
+        writeln!(robots, "Allow: {path}").unwrap();
+    }
+    for path in &config.robots_disallow {
+        writeln!(robots, "Disallow: {path}").unwrap();
+    }
+    // Crawlers require the sitemap's URL to be absolute
+    let base_url = config.site_url().trim_end_matches('/');
+    writeln!(robots, "\nSitemap: {base_url}/{SITEMAP_FILE}").unwrap();
+    robots
+}
+
+/// Write `robots.txt` to the output root `dst`
+pub fn write(config: &Config, dst: &Path) -> anyhow::Result<()> {
+    std::fs::write(dst.join("robots.txt"), robots_txt(config))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_robots_txt_references_sitemap() {
+        let config = Config {
+            base_url: Some("https://jc-mp.com/".to_string()),
+            ..Config::default()
+        };
+        let robots = robots_txt(&config);
+        assert!(
+            robots.contains("Sitemap: https://jc-mp.com/sitemap.xml"),
+            "{robots}"
+        );
+        assert!(robots.contains("Disallow: /wiki/Special/"), "{robots}");
+
+        let robots = robots_txt(&Config::default());
+        assert!(
+            robots.contains(&format!("Sitemap: {}/sitemap.xml", crate::BASE_URL)),
+            "{robots}"
+        );
+    }
+}
@@ -0,0 +1,89 @@
+//! The site's search: an index of the text of every page, which a script in the
+//! navigation bar fetches and filters as the reader types
+
+use std::{collections::BTreeMap, path::Path};
+
+use serde::Serialize;
+use wikitext_simplified::WikitextSimplifiedNode as WSN;
+
+use crate::wikitext;
+
+/// The search index, in the output root
+pub const SEARCH_INDEX: &str = "search-index.json";
+/// The script that searches the index, relative to the static directory
+pub const SEARCH_SCRIPT: &str = "js/search.js";
+/// The most text of a page that is indexed, in bytes, to keep the index small
+pub const MAX_TEXT_BYTES: usize = 2048;
+
+/// A page in the search index
+#[derive(Debug, Serialize)]
+pub struct SearchEntry {
+    pub title: String,
+    pub text: String,
+}
+
+/// The text of a page for the search index: its text on one line, leaving out templates
+/// and tables, and cut off after [`MAX_TEXT_BYTES`]
+pub fn page_text(nodes: &[WSN]) -> String {
+    fn strip(nodes: &mut Vec<WSN>) {
+        nodes.retain(|node| !matches!(node, WSN::Template { .. } | WSN::Table { .. }));
+        for node in nodes {
+            for children in wikitext::children_mut(node) {
+                strip(children);
+            }
+        }
+    }
+
+    let mut nodes = nodes.to_vec();
+    strip(&mut nodes);
+    let mut text = wikitext::plain_text(&nodes)
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    if text.len() > MAX_TEXT_BYTES {
+        let end = (0..=MAX_TEXT_BYTES)
+            .rev()
+            .find(|&end| text.is_char_boundary(end))
+            .unwrap();
+        text.truncate(end);
+    }
+    text
+}
+
+/// Write the search index, mapping the URL of each page to its entry, to the output
+/// root `dst`. The URLs are relative to the index, so that they work wherever the site
+/// is served from.
+pub fn write(entries: &BTreeMap<String, SearchEntry>, dst: &Path) -> anyhow::Result<()> {
+    std::fs::write(dst.join(SEARCH_INDEX), serde_json::to_string(entries)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_page_text_skips_templates_and_tables() {
+        let source = "{{Infobox|name=Vehicle}}
+A '''vehicle'''   can be
+driven.
+{|
+| Table cell
+|}
+== Usage ==
+See [[Lua/Server|the server docs]].";
+        let pwt_configuration = wikitext_simplified::wikitext_util::wikipedia_pwt_configuration();
+        let nodes =
+            wikitext_simplified::parse_and_simplify_wikitext(source, &pwt_configuration).unwrap();
+
+        assert_eq!(
+            page_text(&nodes),
+            "A vehicle can be driven. Usage See the server docs."
+        );
+
+        let long = "word ".repeat(1000);
+        let nodes =
+            wikitext_simplified::parse_and_simplify_wikitext(&long, &pwt_configuration).unwrap();
+        assert!(page_text(&nodes).len() <= MAX_TEXT_BYTES);
+    }
+}
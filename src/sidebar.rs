@@ -0,0 +1,146 @@
+use std::collections::BTreeMap;
+
+use crate::{config::Config, page_url};
+
+/// A node in the sidebar tree, keyed by title component in its parent
+#[derive(Debug, Default)]
+struct SidebarNode {
+    children: BTreeMap<String, SidebarNode>,
+}
+
+/// The tree of all pages in the wiki, built once and rendered for every page
+#[derive(Debug, Default)]
+pub struct SidebarTree {
+    root: SidebarNode,
+}
+impl SidebarTree {
+    pub fn new<'a>(titles: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut root = SidebarNode::default();
+        for title in titles {
+            let mut node = &mut root;
+            for component in title.split('/') {
+                node = node.children.entry(component.to_string()).or_default();
+            }
+        }
+        Self { root }
+    }
+
+    /// Render the tree with branches expanded up to the configured depth.
+    ///
+    /// Deeper branches are collapsed behind a toggle, except for those leading to
+    /// `active_title`, which are always expanded so the current page is visible.
+    pub fn render(&self, config: &Config, active_title: &str) -> paxhtml::Element {
+        paxhtml::html! {
+            <nav class="text-sm">
+                {render_children(config, &self.root, "", active_title, 0)}
+            </nav>
+        }
+    }
+}
+
+fn render_children(
+    config: &Config,
+    node: &SidebarNode,
+    parent_title: &str,
+    active_title: &str,
+    depth: usize,
+) -> paxhtml::Element {
+    use paxhtml::html;
+
+    let class = if depth == 0 {
+        "space-y-1"
+    } else {
+        "ml-3 pl-2 border-l border-gray-200 space-y-1"
+    };
+
+    html! {
+        <ul class={class}>
+            #{node.children.iter().map(|(name, child)| {
+                let title = if parent_title.is_empty() {
+                    name.clone()
+                } else {
+                    format!("{parent_title}/{name}")
+                };
+
+                let link_class = if title == active_title {
+                    "font-semibold text-gray-900"
+                } else {
+                    config.link_class.as_str()
+                };
+                let link = html! {
+                    <a class={link_class} href={page_url(config, &title)}>{name}</a>
+                };
+
+                if child.children.is_empty() {
+                    return html! { <li>{link}</li> };
+                }
+
+                let expanded =
+                    depth < config.sidebar_max_depth || is_on_active_path(&title, active_title);
+                let attributes = if expanded {
+                    paxhtml::Attribute::parse_from_str("open").unwrap()
+                } else {
+                    vec![]
+                };
+                html! {
+                    <li>
+                        <details {attributes}>
+                            <summary class="cursor-pointer">{link}</summary>
+                            {render_children(config, child, &title, active_title, depth + 1)}
+                        </details>
+                    </li>
+                }
+            })}
+        </ul>
+    }
+}
+
+/// Whether `title` is the active page or one of its ancestors
+fn is_on_active_path(title: &str, active_title: &str) -> bool {
+    active_title == title
+        || active_title
+            .strip_prefix(title)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::render_to_string;
+
+    fn tree() -> SidebarTree {
+        SidebarTree::new([
+            "Lua",
+            "Lua/Server",
+            "Lua/Server/Vehicle",
+            "Lua/Server/Vehicle/Functions/SetPosition",
+            "Main Page",
+        ])
+    }
+
+    fn config() -> Config {
+        Config {
+            sidebar_max_depth: 1,
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn test_branch_below_max_depth_is_collapsed() {
+        let html = render_to_string(tree().render(&config(), "Main Page"));
+
+        // Lua, Lua/Server, Lua/Server/Vehicle and Lua/Server/Vehicle/Functions are branches,
+        // but only the top-level one is within the expanded depth
+        assert_eq!(html.matches("<details").count(), 4);
+        assert_eq!(html.matches("<details open").count(), 1);
+    }
+
+    #[test]
+    fn test_active_path_is_always_expanded() {
+        let html = render_to_string(
+            tree().render(&config(), "Lua/Server/Vehicle/Functions/SetPosition"),
+        );
+
+        assert_eq!(html.matches("<details open").count(), 4);
+    }
+}
@@ -0,0 +1,226 @@
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+};
+
+use crate::{
+    build_date::BuildDate, config::Config, profile::Profile, sidebar::SidebarTree,
+    warnings::Warnings,
+};
+
+/// What a build produced, for the summary printed at the end of it
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct BuildSummary {
+    /// The content pages rendered, excluding redirects
+    pub pages: usize,
+    /// The redirect pages written
+    pub redirects: usize,
+    /// The templates (and transcluded pages) loaded
+    pub templates: usize,
+}
+
+/// Everything known about the wiki as a whole, gathered before any page is rendered
+pub struct Site {
+    pub config: Config,
+    /// The titles of all content pages (excluding redirects), e.g. `Lua/Server/Vehicle`
+    pub page_titles: BTreeSet<String>,
+    /// The titles of all redirect pages, mapped to their targets
+    pub redirects: BTreeMap<String, String>,
+    pub sidebar: SidebarTree,
+    /// The translations of each page that has any, keyed by the untranslated page's
+    /// title and then by language code
+    pub translations: BTreeMap<String, BTreeMap<String, String>>,
+    /// The names of the media files that have thumbnails
+    pub thumbnails: BTreeSet<String>,
+    /// The names and sizes of the media files over the configured size limit
+    pub oversized_media: Vec<(String, u64)>,
+    /// The titles and rendered sizes of the pages over the configured size budget
+    pub oversized_pages: RefCell<Vec<(String, u64)>>,
+    /// The titles of the pages in each category, filled in as pages are rendered
+    pub category_members: RefCell<BTreeMap<String, BTreeSet<String>>>,
+    /// The rendered content of each page, for the print document, if it's enabled
+    pub print_pages: RefCell<Vec<(String, String)>>,
+    /// The URLs of the pages written so far, other than redirects, for the sitemap
+    pub sitemap_urls: RefCell<BTreeSet<String>>,
+    /// The titles of the pages that are marked as stubs or too short
+    pub stubs: BTreeSet<String>,
+    /// The HTML added to the end of every content page, from `after_content_file`
+    pub after_content: Option<String>,
+    /// The date the date magic words resolve to
+    pub build_date: BuildDate,
+    /// What was built, filled in once the build is done
+    pub summary: BuildSummary,
+    pub warnings: Warnings,
+    /// Render timings, collected only when profiling
+    pub profile: Option<Profile>,
+    /// Every internal link rendered so far, as `(source title, target title)`
+    pub links: RefCell<Vec<(String, String)>>,
+    /// The normalized titles of everything that can be linked to: pages, redirects,
+    /// and the directories that get index pages
+    link_targets: HashSet<String>,
+    /// The targets of the redirects, keyed by the redirect's normalized title
+    redirect_targets: HashMap<String, String>,
+}
+impl Site {
+    pub fn new(
+        config: Config,
+        page_titles: BTreeSet<String>,
+        redirects: BTreeMap<String, String>,
+    ) -> Self {
+        let sidebar = SidebarTree::new(page_titles.iter().map(|s| s.as_str()));
+
+        let mut translations: BTreeMap<String, BTreeMap<String, String>> = BTreeMap::new();
+        for title in &page_titles {
+            if let Some((original, language)) = title.rsplit_once('/')
+                && config.translation_languages.iter().any(|l| l == language)
+                && page_titles.contains(original)
+            {
+                translations
+                    .entry(original.to_string())
+                    .or_default()
+                    .insert(language.to_string(), title.clone());
+            }
+        }
+
+        let mut link_targets = HashSet::new();
+        for title in page_titles.iter().chain(redirects.keys()) {
+            let title = normalize_title(title);
+            let mut directory = title.as_str();
+            while let Some((parent, _)) = directory.rsplit_once('/') {
+                link_targets.insert(parent.to_string());
+                directory = parent;
+            }
+            link_targets.insert(title);
+        }
+
+        let redirect_targets = redirects
+            .iter()
+            .map(|(title, target)| {
+                let target = moved_title(&config, target).unwrap_or_else(|| target.clone());
+                (normalize_title(title), target)
+            })
+            .collect();
+
+        Self {
+            build_date: config.build_date.unwrap_or_else(BuildDate::now),
+            config,
+            page_titles,
+            redirects,
+            sidebar,
+            translations,
+            thumbnails: BTreeSet::new(),
+            oversized_media: vec![],
+            oversized_pages: RefCell::default(),
+            category_members: RefCell::default(),
+            print_pages: RefCell::default(),
+            sitemap_urls: RefCell::default(),
+            stubs: BTreeSet::new(),
+            after_content: None,
+            summary: BuildSummary::default(),
+            warnings: Warnings::default(),
+            profile: None,
+            links: RefCell::default(),
+            link_targets,
+            redirect_targets,
+        }
+    }
+
+    /// The language of the page with the given title, and every version of that page
+    /// (including itself) as `(language, title)`. Pages without translations have no
+    /// versions.
+    pub fn page_languages(&self, title: &str) -> (&str, Vec<(&str, &str)>) {
+        let (original, language) = match title.rsplit_once('/') {
+            Some((original, language))
+                if self
+                    .translations
+                    .get(original)
+                    .is_some_and(|t| t.contains_key(language)) =>
+            {
+                (original, language)
+            }
+            _ => (title, self.config.language.as_str()),
+        };
+
+        let Some((original, translations)) = self.translations.get_key_value(original) else {
+            return (language, vec![]);
+        };
+        let versions = std::iter::once((self.config.language.as_str(), original.as_str()))
+            .chain(
+                translations
+                    .iter()
+                    .map(|(language, title)| (language.as_str(), title.as_str())),
+            )
+            .collect();
+        (language, versions)
+    }
+
+    /// Follow any redirects from `title` to the page they end at, returning its normalized
+    /// title. Returns `None` if the page doesn't exist or the redirects loop.
+    pub fn resolve_redirects(&self, title: &str) -> Option<String> {
+        let without_fragment =
+            |title: &str| normalize_title(title.split('#').next().unwrap_or_default());
+
+        let mut title = without_fragment(title);
+        let mut seen = HashSet::new();
+        while let Some(target) = self.redirect_targets.get(&title) {
+            if !seen.insert(title) {
+                return None;
+            }
+            title = without_fragment(target);
+        }
+        self.link_targets.contains(&title).then_some(title)
+    }
+
+    /// The redirects, as `(title, target)`, that don't end at an existing page
+    pub fn dangling_redirects(&self) -> Vec<(&str, &str)> {
+        self.redirects
+            .iter()
+            .filter(|(title, _)| self.resolve_redirects(title).is_none())
+            .map(|(title, target)| (title.as_str(), target.as_str()))
+            .collect()
+    }
+
+    /// The new title of `title` (with its `#fragment`, if any) if the page has moved
+    pub fn moved_title(&self, title: &str) -> Option<String> {
+        moved_title(&self.config, title)
+    }
+
+    /// Record a link from the page `source` to `target`, for the link report
+    pub fn record_link(&self, source: &str, target: &str) {
+        self.links
+            .borrow_mut()
+            .push((source.to_string(), target.to_string()));
+    }
+
+    /// Whether a link to `title` (which may include a `#fragment`) leads to a page
+    pub fn link_target_exists(&self, title: &str) -> bool {
+        let title = title.split('#').next().unwrap_or_default();
+        // Links to a section on the same page
+        if title.is_empty() {
+            return true;
+        }
+        self.link_targets.contains(&normalize_title(title))
+    }
+}
+
+/// The new title of `title` according to the configured moves, keeping its `#fragment`
+fn moved_title(config: &Config, title: &str) -> Option<String> {
+    let (page, fragment) = title
+        .split_once('#')
+        .map_or((title, None), |(page, fragment)| (page, Some(fragment)));
+    let page = normalize_title(page);
+    let (_, new_title) = config
+        .moves
+        .iter()
+        .find(|(old_title, _)| normalize_title(old_title) == page)?;
+    Some(match fragment {
+        Some(fragment) => format!("{new_title}#{fragment}"),
+        None => new_title.clone(),
+    })
+}
+
+/// Normalize a title for comparison, as titles are matched case-insensitively and
+/// spaces and underscores are interchangeable
+pub fn normalize_title(title: &str) -> String {
+    title.trim().replace("_", " ").to_lowercase()
+}
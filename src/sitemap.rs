@@ -0,0 +1,24 @@
+//! `sitemap.xml`: the URL of every page of the site, for search engines
+
+use std::{collections::BTreeSet, fmt::Write as _, path::Path};
+
+use crate::{robots::SITEMAP_FILE, xml::escape};
+
+/// The contents of the sitemap listing `urls`, which are made absolute with `base_url`
+pub fn sitemap_xml(base_url: &str, urls: &BTreeSet<String>) -> String {
+    let base_url = base_url.trim_end_matches('/');
+    let mut sitemap = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n".to_string();
+    sitemap.push_str("<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n");
+    for url in urls {
+        let loc = escape(&format!("{base_url}{url}"));
+        writeln!(sitemap, "  <url><loc>{loc}</loc></url>").unwrap();
+    }
+    sitemap.push_str("</urlset>\n");
+    sitemap
+}
+
+/// Write the sitemap listing `urls` to the output root `dst`
+pub fn write(base_url: &str, urls: &BTreeSet<String>, dst: &Path) -> anyhow::Result<()> {
+    std::fs::write(dst.join(SITEMAP_FILE), sitemap_xml(base_url, urls))?;
+    Ok(())
+}
@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 use syntect::{
     highlighting::ThemeSet,
     html::{ClassStyle, ClassedHTMLGenerator, css_for_theme_with_class_style},
@@ -5,16 +7,25 @@ use syntect::{
     util::LinesWithEndings,
 };
 
+/// The syntaxes and themes used to highlight code. Loading them is slow, so this is
+/// created once, before any page is rendered, and is cheap to clone for sharing.
+#[derive(Clone)]
 pub struct SyntaxHighlighter {
-    pub syntax_set: SyntaxSet,
-    pub theme_set: ThemeSet,
+    pub syntax_set: Arc<SyntaxSet>,
+    pub theme_set: Arc<ThemeSet>,
 }
 
 impl Default for SyntaxHighlighter {
     fn default() -> Self {
+        // The syntaxes take the longest to load, so the themes are loaded alongside them
+        let (syntax_set, theme_set) = std::thread::scope(|scope| {
+            let theme_set = scope.spawn(ThemeSet::load_defaults);
+            let syntax_set = SyntaxSet::load_defaults_newlines();
+            (syntax_set, theme_set.join().unwrap())
+        });
         Self {
-            syntax_set: SyntaxSet::load_defaults_newlines(),
-            theme_set: ThemeSet::load_defaults(),
+            syntax_set: Arc::new(syntax_set),
+            theme_set: Arc::new(theme_set),
         }
     }
 }
@@ -29,23 +40,45 @@ impl SyntaxHighlighter {
             .unwrap()
     }
 
-    pub fn lookup_language(&self, language: Option<&str>) -> &SyntaxReference {
-        language
-            .and_then(|l| self.syntax_set.find_syntax_by_token(l))
-            .unwrap_or_else(|| {
-                // Default to Lua if no language is specified
-                self.syntax_set
-                    .find_syntax_by_extension("lua")
-                    .unwrap_or_else(|| self.syntax_set.find_syntax_plain_text())
-            })
+    /// The syntax for a language name or file extension (e.g. `lua`), if it's known
+    pub fn find_language(&self, language: &str) -> Option<&SyntaxReference> {
+        self.syntax_set.find_syntax_by_token(language)
+    }
+
+    /// Guess the syntax of `code` from its first line (e.g. a shebang) or, failing
+    /// that, from telltale constructs of the languages that appear on the wiki
+    pub fn detect_language(&self, code: &str) -> Option<&SyntaxReference> {
+        if let Some(syntax) = self
+            .syntax_set
+            .find_syntax_by_first_line(code.lines().next().unwrap_or_default())
+        {
+            return Some(syntax);
+        }
+
+        let trimmed = code.trim_start();
+        let token = if trimmed.starts_with('<') {
+            "xml"
+        } else if trimmed.starts_with('{') && code.contains("\":") {
+            "json"
+        } else if code.contains("#include") || code.contains("std::") {
+            "cpp"
+        } else if code.contains("local ")
+            || code.contains("function ")
+            || code.contains(":Subscribe(")
+            || code.contains("--")
+        {
+            "lua"
+        } else {
+            return None;
+        };
+        self.find_language(token)
     }
 
     pub fn highlight_code(
         &self,
-        language: Option<&str>,
+        syntax: &SyntaxReference,
         code: &str,
     ) -> Result<paxhtml::Element, syntect::Error> {
-        let syntax = self.lookup_language(language);
         let mut html_generator = ClassedHTMLGenerator::new_with_class_style(
             syntax,
             &self.syntax_set,
@@ -59,3 +92,25 @@ impl SyntaxHighlighter {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_highlighter_is_usable_once_initialized() {
+        let highlighter = SyntaxHighlighter::default();
+        assert!(!highlighter.theme_css().is_empty());
+
+        // Clones share the loaded sets rather than loading them again
+        let shared = highlighter.clone();
+        assert!(Arc::ptr_eq(&highlighter.syntax_set, &shared.syntax_set));
+
+        let lua = shared.find_language("lua").unwrap();
+        let paxhtml::Element::Raw { html } = shared.highlight_code(lua, "local x = 1\n").unwrap()
+        else {
+            panic!("Expected raw HTML");
+        };
+        assert!(html.contains("<span"), "{html}");
+    }
+}
@@ -0,0 +1,123 @@
+//! A directory the Tailwind CLI is kept in between builds, so that CI and sandboxed
+//! builds can reuse it instead of relying on where `paxhtml_tailwind` puts its download
+
+use std::path::{Path, PathBuf};
+
+/// The environment variable that sets the cache directory, if `--tailwind-cache` isn't
+/// passed
+pub const CACHE_DIR_VAR: &str = "TAILWIND_CACHE_DIR";
+
+/// Whether the Tailwind binary was already in the cache
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheStatus {
+    Hit,
+    Populated,
+}
+
+/// The cache directory passed on the command line, or else set in the environment
+pub fn cache_dir(arg: Option<&Path>) -> Option<PathBuf> {
+    arg.map(Path::to_path_buf).or_else(|| {
+        std::env::var_os(CACHE_DIR_VAR)
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+    })
+}
+
+/// The path of the Tailwind binary for `version` in the cache directory `dir`, calling
+/// `download` to fetch it there if it isn't already
+pub fn locate(
+    dir: &Path,
+    version: &str,
+    download: impl FnOnce(&Path) -> anyhow::Result<()>,
+) -> anyhow::Result<(PathBuf, CacheStatus)> {
+    let name = format!("tailwindcss-{version}{}", std::env::consts::EXE_SUFFIX);
+    let binary = dir.join(&name);
+    if binary.is_file() {
+        return Ok((binary, CacheStatus::Hit));
+    }
+    std::fs::create_dir_all(dir)?;
+    // Download next to the binary and move it into place, so that an interrupted
+    // download isn't mistaken for a cached binary by the next build
+    let partial = dir.join(format!("{name}.partial"));
+    download(&partial)?;
+    std::fs::rename(&partial, &binary)?;
+    Ok((binary, CacheStatus::Populated))
+}
+
+/// The platforms Tailwind publishes a standalone CLI for, as the OS and architecture
+/// Rust reports
+const SUPPORTED_PLATFORMS: &[(&str, &str)] = &[
+    ("linux", "x86_64"),
+    ("linux", "aarch64"),
+    ("macos", "x86_64"),
+    ("macos", "aarch64"),
+    ("windows", "x86_64"),
+];
+
+/// Fail with a clear error on platforms there's no Tailwind CLI for, rather than
+/// downloading one that can't run
+pub fn check_platform() -> anyhow::Result<()> {
+    let (os, arch) = (std::env::consts::OS, std::env::consts::ARCH);
+    if !SUPPORTED_PLATFORMS.contains(&(os, arch)) {
+        anyhow::bail!("Tailwind doesn't provide a CLI for {os} on {arch}");
+    }
+    Ok(())
+}
+
+/// Tailwind `version`, kept in the cache directory `dir` if there is one. The binary is
+/// downloaded by `paxhtml_tailwind` either way, so the CSS is generated the same way
+/// whether or not it came from the cache.
+pub fn tailwind(dir: Option<&Path>, version: &str) -> anyhow::Result<paxhtml_tailwind::Tailwind> {
+    check_platform()?;
+    let Some(dir) = dir else {
+        return Ok(paxhtml_tailwind::Tailwind::download(version, true)?);
+    };
+
+    let (binary, status) = locate(dir, version, |path| {
+        println!("Downloading Tailwind {version} into {}", dir.display());
+        let downloaded = paxhtml_tailwind::Tailwind::download(version, true)?;
+        std::fs::copy(downloaded.path(), path)?;
+        Ok(())
+    })?;
+    if status == CacheStatus::Hit {
+        println!("Using cached Tailwind {version} from {}", binary.display());
+    }
+    Ok(paxhtml_tailwind::Tailwind::new(binary))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+    use crate::test_util::temp_dir;
+
+    #[test]
+    fn test_second_build_reuses_the_cached_binary() {
+        let dir = temp_dir("tailwind_cache");
+        let downloads = Cell::new(0);
+        let download = |path: &Path| -> anyhow::Result<()> {
+            downloads.set(downloads.get() + 1);
+            std::fs::write(path, "binary")?;
+            Ok(())
+        };
+
+        let (first, status) = locate(&dir, "v4.0.0", download).unwrap();
+        assert_eq!(status, CacheStatus::Populated);
+        let (second, status) = locate(&dir, "v4.0.0", download).unwrap();
+        assert_eq!(status, CacheStatus::Hit);
+        assert_eq!(first, second);
+        assert_eq!(downloads.get(), 1);
+
+        // Another version is downloaded alongside it
+        let (_, status) = locate(&dir, "v4.1.0", download).unwrap();
+        assert_eq!(status, CacheStatus::Populated);
+        assert_eq!(downloads.get(), 2);
+
+        // A failed download leaves nothing behind to be mistaken for the binary
+        let failed = locate(&dir, "v4.2.0", |_| anyhow::bail!("offline"));
+        assert!(failed.is_err());
+        let (_, status) = locate(&dir, "v4.2.0", download).unwrap();
+        assert_eq!(status, CacheStatus::Populated);
+    }
+}
@@ -1,30 +1,43 @@
 use std::{
     collections::HashMap,
     path::{Path, PathBuf},
+    time::Instant,
 };
 
 use wikitext_simplified::{TemplateParameter, WikitextSimplifiedNode, parse_wiki_text_2};
 
-use crate::page_context::PageContext;
+use crate::{inclusion, page_context::PageContext, parser_functions, template_data::TemplateData};
+
+/// The magic word for the configured build profile
+const BUILD_PROFILE: &str = "BUILDPROFILE";
 
 /// Trait for loading wikitext template files
 pub trait TemplateLoader {
     fn load(&self, name: &str) -> anyhow::Result<String>;
 }
 
+/// Whether `path` is a page or template, going by whether its extension is one of
+/// `extensions`
+pub fn is_source_file(path: &Path, extensions: &[String]) -> bool {
+    path.extension()
+        .is_some_and(|e| extensions.iter().any(|extension| e == extension.as_str()))
+}
+
 /// File system based template loader
 pub struct FileSystemLoader {
     lookup: HashMap<String, PathBuf>,
 }
 
 impl FileSystemLoader {
-    pub fn new(root: impl Into<PathBuf>) -> anyhow::Result<Self> {
+    /// Index the files in `root` with one of `extensions` (e.g. `wikitext`)
+    pub fn new(root: impl Into<PathBuf>, extensions: &[String]) -> anyhow::Result<Self> {
         let root = root.into();
         let mut lookup = HashMap::new();
 
         fn scan_dir(
             root: &Path,
             path: &Path,
+            extensions: &[String],
             lookup: &mut HashMap<String, PathBuf>,
         ) -> anyhow::Result<()> {
             for entry in std::fs::read_dir(path)? {
@@ -32,10 +45,8 @@ impl FileSystemLoader {
                 let entry_path = entry.path();
 
                 if entry_path.is_dir() {
-                    scan_dir(root, &entry_path, lookup)?;
-                } else if entry_path.is_file()
-                    && entry_path.extension().is_some_and(|e| e == "wikitext")
-                {
+                    scan_dir(root, &entry_path, extensions, lookup)?;
+                } else if entry_path.is_file() && is_source_file(&entry_path, extensions) {
                     let key = entry_path
                         .strip_prefix(root)?
                         .with_extension("")
@@ -50,7 +61,7 @@ impl FileSystemLoader {
             Ok(())
         }
 
-        scan_dir(&root, &root, &mut lookup)?;
+        scan_dir(&root, &root, extensions, &mut lookup)?;
 
         Ok(Self { lookup })
     }
@@ -63,14 +74,17 @@ impl TemplateLoader for FileSystemLoader {
             .lookup
             .get(&key)
             .ok_or_else(|| anyhow::anyhow!("Template not found: {} -> {}", name, key))?;
-        std::fs::read_to_string(path).map_err(|e| {
+        let content = std::fs::read_to_string(path).map_err(|e| {
             anyhow::anyhow!(
                 "Failed to load template {} from {}: {}",
                 name,
                 path.display(),
                 e
             )
-        })
+        })?;
+        // Transcluded pages may have frontmatter, which only applies to the page itself
+        let (_, content) = crate::frontmatter::split(&content)?;
+        Ok(content.to_string())
     }
 }
 
@@ -78,6 +92,12 @@ pub struct Templates<'a> {
     pwt_configuration: &'a parse_wiki_text_2::Configuration,
     loader: Box<dyn TemplateLoader + 'a>,
     templates: HashMap<String, WikitextSimplifiedNode>,
+    /// The parameters declared by the templates loaded so far, keyed like `templates`
+    declarations: HashMap<String, TemplateData>,
+    /// Whether calls are checked against the templates' declared parameters
+    check_parameters: bool,
+    /// The keys of the templates currently being instantiated, used to detect cycles
+    instantiation_stack: Vec<String>,
 }
 impl<'a> Templates<'a> {
     pub fn new(
@@ -88,9 +108,32 @@ impl<'a> Templates<'a> {
             pwt_configuration,
             loader: Box::new(loader),
             templates: HashMap::new(),
+            declarations: HashMap::new(),
+            check_parameters: false,
+            instantiation_stack: vec![],
         })
     }
 
+    /// The number of templates (and transcluded pages) loaded so far
+    pub fn loaded_count(&self) -> usize {
+        self.templates.len()
+    }
+
+    /// Warn about calls that pass parameters a template doesn't declare, or leave out
+    /// ones it requires. Templates without declarations aren't checked.
+    pub fn set_check_parameters(&mut self, check_parameters: bool) {
+        self.check_parameters = check_parameters;
+    }
+
+    /// Whether `name` is converted as a built-in template: one the wiki doesn't have a
+    /// template of its own for
+    pub fn is_builtin(&self, name: &str) -> bool {
+        let key = name.to_lowercase().replace(" ", "_");
+        crate::is_builtin_template(name)
+            && !self.templates.contains_key(&key)
+            && self.loader.load(name).is_err()
+    }
+
     /// Reparse text content in table cells that contains wikitext markup
     fn reparse_table_cells(
         &mut self,
@@ -104,6 +147,20 @@ impl<'a> Templates<'a> {
             WSN::Table { rows, .. } => {
                 for row in rows {
                     for cell in &mut row.cells {
+                        // A nested table would lose its structure if the cell was
+                        // roundtripped through wikitext, as a table has to start a line.
+                        // Its own cells are reparsed instead.
+                        if cell
+                            .content
+                            .iter()
+                            .any(|node| matches!(node, WSN::Table { .. }))
+                        {
+                            for child in &mut cell.content {
+                                self.reparse_table_cells(child, pwt_configuration, page_context);
+                            }
+                            continue;
+                        }
+
                         let cell_wikitext = WSN::Fragment {
                             children: cell.content.clone(),
                         }
@@ -158,6 +215,12 @@ impl<'a> Templates<'a> {
 
         if !self.templates.contains_key(&key) {
             let content = self.loader.load(name)?;
+            let (content, declaration) = TemplateData::extract(&content)
+                .map_err(|e| anyhow::anyhow!("Failed to load template {name}: {e}"))?;
+            if let Some(declaration) = declaration {
+                self.declarations.insert(key.clone(), declaration);
+            }
+            let content = inclusion::transcluded(&content);
             let simplified =
                 wikitext_simplified::parse_and_simplify_wikitext(&content, self.pwt_configuration)
                     .map_err(|e| {
@@ -195,7 +258,67 @@ impl<'a> Templates<'a> {
                         text: page_context.sub_page_name.to_string(),
                     };
                 }
-                self.get(name).unwrap().clone()
+                if name.trim() == BUILD_PROFILE {
+                    return WSN::Text {
+                        text: page_context.site.config.build_profile.clone(),
+                    };
+                }
+                if let Some(text) = page_context.site.build_date.magic_word(name.trim()) {
+                    return WSN::Text { text };
+                }
+                if let Some(result) =
+                    self.parser_function(pwt_configuration, name, parameters, page_context)
+                {
+                    return result;
+                }
+                // Built-in templates are converted with the rest of the page
+                if self.is_builtin(name) {
+                    return WSN::Template {
+                        name: name.to_string(),
+                        parameters: parameters.to_vec(),
+                    };
+                }
+
+                // A leading colon transcludes a page rather than a template; as pages and
+                // templates share a loader, the only difference is the prefix
+                let name = name.strip_prefix(':').unwrap_or(name);
+
+                let key = name.to_lowercase().replace(" ", "_");
+                if self.instantiation_stack.contains(&key) {
+                    return WSN::Text {
+                        text: format!("Template loop detected: {name}"),
+                    };
+                }
+
+                let template = match self.get(name) {
+                    Ok(template) => template.clone(),
+                    Err(e) => {
+                        page_context.warn(e);
+                        return WSN::Text {
+                            text: format!(r#"<span class="text-red-600">{{{{{name}}}}}</span>"#),
+                        };
+                    }
+                };
+                if self.check_parameters
+                    && let Some(declaration) = self.declarations.get(&key)
+                {
+                    for problem in declaration.problems(parameters) {
+                        page_context.warn(format!("{{{{{name}}}}}: {problem}"));
+                    }
+                }
+                self.instantiation_stack.push(key);
+                let started = page_context.site.profile.as_ref().map(|_| Instant::now());
+                let result = self.instantiate(
+                    pwt_configuration,
+                    TemplateToInstantiate::Node(template),
+                    parameters,
+                    page_context,
+                );
+                let key = self.instantiation_stack.pop().unwrap();
+                if let (Some(profile), Some(started)) = (&page_context.site.profile, started) {
+                    profile.record_template(&key, started.elapsed());
+                }
+                return result;
             }
             TemplateToInstantiate::Node(node) => node,
         };
@@ -203,10 +326,11 @@ impl<'a> Templates<'a> {
         // Check if we're done
         let mut further_instantiation_required = false;
         template.visit(&mut |node| {
-            further_instantiation_required |= matches!(
-                node,
-                WSN::TemplateParameterUse { .. } | WSN::Template { .. }
-            );
+            further_instantiation_required |= match node {
+                WSN::TemplateParameterUse { .. } => true,
+                WSN::Template { name, .. } => !self.is_builtin(name),
+                _ => false,
+            };
         });
         if !further_instantiation_required {
             return template;
@@ -219,10 +343,30 @@ impl<'a> Templates<'a> {
                     name,
                     parameters: template_params,
                 } => {
+                    // The arguments of parser functions are expanded by the function, and
+                    // those of built-in templates when the page is converted, so the
+                    // parameters of this template have to be substituted into them first
+                    let (name, template_params) = if name.trim_start().starts_with('#')
+                        || self.is_builtin(name)
+                    {
+                        let substitute = |text: &str| {
+                            substitute_parameters(text, parameters, pwt_configuration, page_context)
+                        };
+                        let template_params = template_params
+                            .iter()
+                            .map(|p| TemplateParameter {
+                                name: p.name.clone(),
+                                value: substitute(&p.value),
+                            })
+                            .collect::<Vec<_>>();
+                        (substitute(name), template_params)
+                    } else {
+                        (name.clone(), template_params.clone())
+                    };
                     let result = self.instantiate(
                         pwt_configuration,
-                        TemplateToInstantiate::Name(name),
-                        template_params,
+                        TemplateToInstantiate::Name(&name),
+                        &template_params,
                         page_context,
                     );
                     // Flatten single-child fragments to avoid nested structures
@@ -234,28 +378,7 @@ impl<'a> Templates<'a> {
                     }
                 }
                 WSN::TemplateParameterUse { name, default } => {
-                    let parameter = parameters
-                        .iter()
-                        .find(|p| p.name == *name)
-                        .map(|p| p.value.clone())
-                        .or_else(|| {
-                            name.eq_ignore_ascii_case("subpagename")
-                                .then(|| page_context.sub_page_name.to_string())
-                        });
-                    if let Some(parameter) = parameter {
-                        WSN::Text { text: parameter }
-                    } else if let Some(default) = default {
-                        WSN::Text {
-                            text: WSN::Fragment {
-                                children: default.clone(),
-                            }
-                            .to_wikitext(),
-                        }
-                    } else {
-                        WSN::Text {
-                            text: "".to_string(),
-                        }
-                    }
+                    resolve_parameter_use(name, default.as_deref(), parameters, page_context)
                 }
                 _ => node.clone(),
             });
@@ -315,43 +438,233 @@ impl<'a> Templates<'a> {
     }
 }
 
-#[derive(Clone, Debug)]
-pub enum TemplateToInstantiate<'a> {
-    Name(&'a str),
-    Node(WikitextSimplifiedNode),
-}
+impl Templates<'_> {
+    /// Evaluate `name` as a parser function (`#name:first argument`) called with
+    /// `parameters` as the rest of its arguments. `None` if it isn't one we support.
+    fn parser_function(
+        &mut self,
+        pwt_configuration: &parse_wiki_text_2::Configuration,
+        name: &str,
+        parameters: &[TemplateParameter],
+        page_context: &PageContext,
+    ) -> Option<WikitextSimplifiedNode> {
+        let (function, first) = name.trim().strip_prefix('#')?.split_once(':')?;
+        let function = function.trim().to_lowercase();
+        if !matches!(
+            function.as_str(),
+            "len" | "sub" | "replace" | "explode" | "if" | "ifeq" | "switch" | "lst"
+        ) {
+            return None;
+        }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        // Named arguments are passed to parser functions as written. Arguments are only
+        // expanded when they're used, so the branches a conditional doesn't take are
+        // never instantiated.
+        //
+        // Positional arguments are named after their position once parsed, so a `#switch`
+        // case with a numeric key (`1=One`) looks like one. It's only taken to be
+        // positional if it has the next position and either the switch has cases with
+        // other keys or it repeats an earlier name (a trailing default after `1=...`).
+        let is_number = |name: &str| name.chars().all(|c| c.is_ascii_digit());
+        let other_keys = parameters
+            .iter()
+            .any(|p| !is_number(&p.name) && p.name.trim() != "#default");
+        let mut positions = 0;
+        let mut arguments = vec![first.to_string()];
+        for (i, p) in parameters.iter().enumerate() {
+            let positional = is_number(&p.name)
+                && (function != "switch"
+                    || (p.name == (positions + 1).to_string()
+                        && (other_keys || parameters[..i].iter().any(|q| q.name == p.name))));
+            if positional {
+                positions += 1;
+                arguments.push(p.value.clone());
+            } else {
+                arguments.push(format!("{}={}", p.name, p.value));
+            }
+        }
+        let argument = |templates: &mut Self, index: usize| {
+            arguments.get(index).map_or_else(String::new, |argument| {
+                templates.expand(pwt_configuration, argument, page_context)
+            })
+        };
+
+        let text = match function.as_str() {
+            "len" => parser_functions::len(&argument(self, 0)),
+            "sub" => {
+                let text = argument(self, 0);
+                let (start, length) = (argument(self, 1), argument(self, 2));
+                parser_functions::sub(&text, &start, &length)
+            }
+            "replace" => {
+                let text = argument(self, 0);
+                let (search, replacement) = (argument(self, 1), argument(self, 2));
+                parser_functions::replace(&text, &search, &replacement)
+            }
+            "explode" => {
+                let (text, delimiter) = (argument(self, 0), argument(self, 1));
+                let (position, limit) = (argument(self, 2), argument(self, 3));
+                parser_functions::explode(&text, &delimiter, &position, &limit)
+            }
+            "lst" => {
+                let (page, section) = (argument(self, 0), argument(self, 1));
+                // Sections are tracked like templates, so that a section that includes
+                // itself is caught
+                let key = format!("{}#{section}", page.to_lowercase().replace(" ", "_"));
+                match self.loader.load(&page) {
+                    Ok(_) if self.instantiation_stack.contains(&key) => {
+                        page_context.warn(format!("section {section:?} of {page} includes itself"));
+                        String::new()
+                    }
+                    Ok(source) => match parser_functions::labeled_section(&source, &section) {
+                        Some(content) => {
+                            self.instantiation_stack.push(key);
+                            let content = self.expand(pwt_configuration, &content, page_context);
+                            self.instantiation_stack.pop();
+                            content
+                        }
+                        None => {
+                            page_context.warn(format!("no section {section:?} in {page}"));
+                            String::new()
+                        }
+                    },
+                    Err(e) => {
+                        page_context.warn(e);
+                        String::new()
+                    }
+                }
+            }
+            "if" => {
+                let branch = if parser_functions::truthy(&argument(self, 0)) {
+                    1
+                } else {
+                    2
+                };
+                argument(self, branch)
+            }
+            "ifeq" => {
+                let (a, b) = (argument(self, 0), argument(self, 1));
+                argument(self, if parser_functions::ifeq(&a, &b) { 2 } else { 3 })
+            }
+            "switch" => {
+                let value = argument(self, 0);
+                let cases = arguments.get(1..).unwrap_or_default();
+                let result = parser_functions::switch(&value, cases, |key| {
+                    self.expand(pwt_configuration, key, page_context)
+                });
+                self.expand(pwt_configuration, result, page_context)
+            }
+            _ => unreachable!(),
+        };
+        // The result may be markup (e.g. a branch of `#ifeq`), so it is parsed again
+        Some(
+            wikitext_simplified::parse_and_simplify_wikitext(&text, pwt_configuration)
+                .map(|children| WikitextSimplifiedNode::Fragment { children })
+                .unwrap_or(WikitextSimplifiedNode::Text { text }),
+        )
+    }
 
-    /// In-memory template loader for testing
-    struct MockLoader {
-        templates: HashMap<String, String>,
+    /// Fully instantiate the wikitext `text`, returning it as trimmed wikitext
+    fn expand(
+        &mut self,
+        pwt_configuration: &parse_wiki_text_2::Configuration,
+        text: &str,
+        page_context: &PageContext,
+    ) -> String {
+        let Ok(nodes) = wikitext_simplified::parse_and_simplify_wikitext(text, pwt_configuration)
+        else {
+            return text.trim().to_string();
+        };
+        self.instantiate(
+            pwt_configuration,
+            TemplateToInstantiate::Node(WikitextSimplifiedNode::Fragment { children: nodes }),
+            &[],
+            page_context,
+        )
+        .to_wikitext()
+        .trim()
+        .to_string()
     }
+}
 
-    impl MockLoader {
-        fn new() -> Self {
-            Self {
-                templates: HashMap::new(),
-            }
-        }
+/// Replace the parameter uses in the wikitext `text` with their values in `parameters`
+fn substitute_parameters(
+    text: &str,
+    parameters: &[TemplateParameter],
+    pwt_configuration: &parse_wiki_text_2::Configuration,
+    page_context: &PageContext,
+) -> String {
+    use WikitextSimplifiedNode as WSN;
 
-        fn add(&mut self, name: &str, content: &str) {
-            let key = name.to_lowercase().replace(" ", "_");
-            self.templates.insert(key, content.to_string());
+    let Ok(children) = wikitext_simplified::parse_and_simplify_wikitext(text, pwt_configuration)
+    else {
+        return text.to_string();
+    };
+    let mut node = WSN::Fragment { children };
+    node.visit_and_replace_mut(&mut |node| match node {
+        WSN::TemplateParameterUse { name, default } => {
+            resolve_parameter_use(name, default.as_deref(), parameters, page_context)
         }
+        _ => node.clone(),
+    });
+    node.to_wikitext()
+}
+
+/// Resolve a use of the parameter `name` against `parameters`, falling back to its
+/// default. Parameter uses nested within the default (`{{{a|{{{b|x}}}}}}`) are resolved
+/// against the same parameters.
+fn resolve_parameter_use(
+    name: &str,
+    default: Option<&[WikitextSimplifiedNode]>,
+    parameters: &[TemplateParameter],
+    page_context: &PageContext,
+) -> WikitextSimplifiedNode {
+    use WikitextSimplifiedNode as WSN;
+
+    // As in MediaWiki, whitespace around parameter names is insignificant on both the
+    // use and the call side, but the values are left as they are
+    let name = name.trim();
+    let parameter = parameters
+        .iter()
+        .find(|p| p.name.trim() == name)
+        .map(|p| p.value.clone())
+        .or_else(|| {
+            name.eq_ignore_ascii_case("subpagename")
+                .then(|| page_context.sub_page_name.to_string())
+        });
+    if let Some(parameter) = parameter {
+        return WSN::Text { text: parameter };
     }
+    let Some(default) = default else {
+        return WSN::Text {
+            text: "".to_string(),
+        };
+    };
 
-    impl TemplateLoader for MockLoader {
-        fn load(&self, name: &str) -> anyhow::Result<String> {
-            let key = name.to_lowercase().replace(" ", "_");
-            self.templates
-                .get(&key)
-                .cloned()
-                .ok_or_else(|| anyhow::anyhow!("Template not found: {}", name))
+    let mut default = WSN::Fragment {
+        children: default.to_vec(),
+    };
+    default.visit_and_replace_mut(&mut |node| match node {
+        WSN::TemplateParameterUse { name, default } => {
+            resolve_parameter_use(name, default.as_deref(), parameters, page_context)
         }
+        _ => node.clone(),
+    });
+    WSN::Text {
+        text: default.to_wikitext(),
     }
+}
+
+#[derive(Clone, Debug)]
+pub enum TemplateToInstantiate<'a> {
+    Name(&'a str),
+    Node(WikitextSimplifiedNode),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::Config, site::Site, test_util::MockLoader};
 
     #[test]
     fn test_nested_table_template_instantiation() {
@@ -382,11 +695,14 @@ mod tests {
         let pwt_configuration = wikitext_simplified::wikitext_util::wikipedia_pwt_configuration();
         let mut templates = Templates::new(loader, &pwt_configuration).unwrap();
 
+        let site = Site::new(Config::default(), Default::default(), Default::default());
         let page_context = PageContext {
             input_path: PathBuf::from("Test.wikitext"),
             title: "Test".to_string(),
             route_path: paxhtml::RoutePath::new(std::iter::empty(), Some("test.html".to_string())),
             sub_page_name: "Test".to_string(),
+            site: &site,
+            state: Default::default(),
         };
 
         // Instantiate the table template
@@ -462,6 +778,314 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_declared_parameters_are_checked() {
+        let mut loader = MockLoader::new();
+        loader.add(
+            "Infobox",
+            r#"'''{{{name}}}'''<templatedata>{"params": {"name": {"required": true}}}</templatedata>"#,
+        );
+
+        let pwt_configuration = wikitext_simplified::wikitext_util::wikipedia_pwt_configuration();
+        let mut templates = Templates::new(loader, &pwt_configuration).unwrap();
+        templates.set_check_parameters(true);
+        let site = Site::new(Config::default(), Default::default(), Default::default());
+        let page_context = crate::test_util::page_context(&site, "Test");
+        let mut call = |name: &str| {
+            templates
+                .instantiate(
+                    &pwt_configuration,
+                    TemplateToInstantiate::Name("Infobox"),
+                    &[TemplateParameter {
+                        name: name.to_string(),
+                        value: "Vehicle".to_string(),
+                    }],
+                    &page_context,
+                )
+                .to_wikitext()
+        };
+
+        let result = call("name");
+        assert!(result.contains("Vehicle"), "{result}");
+        assert!(!result.contains("templatedata"), "{result}");
+        assert!(site.warnings.is_empty());
+
+        // The typo is unknown, and leaves the required parameter missing
+        call("nmae");
+        assert_eq!(site.warnings.len(), 2);
+    }
+
+    #[test]
+    fn test_page_transclusion() {
+        let mut loader = MockLoader::new();
+        loader.add("Shared Notes", "These notes are shared.");
+        loader.add("Guide", "Before. {{:Shared Notes}} After.");
+
+        let pwt_configuration = wikitext_simplified::wikitext_util::wikipedia_pwt_configuration();
+        let mut templates = Templates::new(loader, &pwt_configuration).unwrap();
+
+        let site = Site::new(Config::default(), Default::default(), Default::default());
+        let page_context = crate::test_util::page_context(&site, "Test");
+
+        let result = templates
+            .instantiate(
+                &pwt_configuration,
+                TemplateToInstantiate::Name(":Guide"),
+                &[],
+                &page_context,
+            )
+            .to_wikitext();
+
+        assert!(result.contains("Before."), "{result}");
+        assert!(result.contains("These notes are shared."), "{result}");
+        assert!(result.contains("After."), "{result}");
+    }
+
+    #[test]
+    fn test_date_magic_words_in_nested_templates() {
+        let mut loader = MockLoader::new();
+        loader.add("Copyright", "(c) {{CURRENTYEAR}} JC2-MP");
+        loader.add(
+            "Footer",
+            "{{Copyright}}, built {{CURRENTDAY}} {{CURRENTMONTHNAME}}",
+        );
+
+        let pwt_configuration = wikitext_simplified::wikitext_util::wikipedia_pwt_configuration();
+        let mut templates = Templates::new(loader, &pwt_configuration).unwrap();
+
+        let site = Site::new(
+            Config {
+                build_date: Some("2014-03-09".to_string().try_into().unwrap()),
+                ..Config::default()
+            },
+            Default::default(),
+            Default::default(),
+        );
+        let page_context = crate::test_util::page_context(&site, "Test");
+
+        let result = templates
+            .instantiate(
+                &pwt_configuration,
+                TemplateToInstantiate::Name("Footer"),
+                &[],
+                &page_context,
+            )
+            .to_wikitext();
+
+        assert!(result.contains("(c) 2014 JC2-MP"), "{result}");
+        assert!(result.contains("built 9 March"), "{result}");
+    }
+
+    #[test]
+    fn test_nested_parameter_default_is_resolved() {
+        let mut loader = MockLoader::new();
+        loader.add("Nested", "{{{a|{{{b|x}}}}}}");
+        loader.add(
+            "NestedTable",
+            "{| class=\"wikitable\"\n|-\n| {{{a|{{{b|x}}}}}}\n|}",
+        );
+
+        let pwt_configuration = wikitext_simplified::wikitext_util::wikipedia_pwt_configuration();
+        let mut templates = Templates::new(loader, &pwt_configuration).unwrap();
+
+        let site = Site::new(Config::default(), Default::default(), Default::default());
+        let page_context = crate::test_util::page_context(&site, "Test");
+        let parameters = [TemplateParameter {
+            name: "b".to_string(),
+            value: "from b".to_string(),
+        }];
+
+        for name in ["Nested", "NestedTable"] {
+            let result = templates
+                .instantiate(
+                    &pwt_configuration,
+                    TemplateToInstantiate::Name(name),
+                    &parameters,
+                    &page_context,
+                )
+                .to_wikitext();
+            assert!(result.contains("from b"), "{name}: {result}");
+            assert!(!result.contains("{{{"), "{name}: {result}");
+
+            let result = templates
+                .instantiate(
+                    &pwt_configuration,
+                    TemplateToInstantiate::Name(name),
+                    &[],
+                    &page_context,
+                )
+                .to_wikitext();
+            assert!(result.contains('x'), "{name}: {result}");
+            assert!(!result.contains("{{{"), "{name}: {result}");
+        }
+    }
+
+    #[test]
+    fn test_parameter_names_are_trimmed() {
+        let mut loader = MockLoader::new();
+        loader.add("Spaced", "[{{{ x }}}]");
+        loader.add("Unspaced", "[{{{x}}}]");
+
+        let pwt_configuration = wikitext_simplified::wikitext_util::wikipedia_pwt_configuration();
+        let mut templates = Templates::new(loader, &pwt_configuration).unwrap();
+
+        let site = Site::new(Config::default(), Default::default(), Default::default());
+        let page_context = crate::test_util::page_context(&site, "Test");
+
+        for (template, parameter_name) in [("Spaced", "x"), ("Unspaced", " x ")] {
+            let parameters = [TemplateParameter {
+                name: parameter_name.to_string(),
+                value: "a value".to_string(),
+            }];
+            let result = templates
+                .instantiate(
+                    &pwt_configuration,
+                    TemplateToInstantiate::Name(template),
+                    &parameters,
+                    &page_context,
+                )
+                .to_wikitext();
+            assert!(result.contains("[a value]"), "{template}: {result}");
+        }
+    }
+
+    #[test]
+    fn test_string_parser_functions() {
+        let mut loader = MockLoader::new();
+        loader.add("Abbreviation", "{{#sub:{{{1}}}|0|3}}");
+        loader.add(
+            "Summary",
+            "{{#len:{{{1}}}}} characters, ending in {{#sub:{{{1}}}|-3}}",
+        );
+
+        let pwt_configuration = wikitext_simplified::wikitext_util::wikipedia_pwt_configuration();
+        let mut templates = Templates::new(loader, &pwt_configuration).unwrap();
+
+        let site = Site::new(Config::default(), Default::default(), Default::default());
+        let page_context = crate::test_util::page_context(&site, "Test");
+        let parameters = [TemplateParameter {
+            name: "1".to_string(),
+            value: "Vehicle".to_string(),
+        }];
+
+        let mut instantiate = |name| {
+            templates
+                .instantiate(
+                    &pwt_configuration,
+                    TemplateToInstantiate::Name(name),
+                    &parameters,
+                    &page_context,
+                )
+                .to_wikitext()
+        };
+        let result = instantiate("Abbreviation");
+        assert_eq!(result.trim(), "Veh");
+        let result = instantiate("Summary");
+        assert!(result.contains("7 characters, ending in cle"), "{result}");
+    }
+
+    #[test]
+    fn test_labeled_section_transclusion() {
+        let mut loader = MockLoader::new();
+        loader.add(
+            "Vehicle Notes",
+            "Intro. <section begin=usage />Spawn vehicles with '''care'''.<section end=usage /> Outro.",
+        );
+        loader.add("Guide", "Usage: {{#lst:Vehicle Notes|usage}}");
+        loader.add("Broken", "{{#lst:Vehicle Notes|missing}}");
+        loader.add(
+            "Loop",
+            "<section begin=a />Again {{#lst:Loop|a}}<section end=a />",
+        );
+
+        let pwt_configuration = wikitext_simplified::wikitext_util::wikipedia_pwt_configuration();
+        let mut templates = Templates::new(loader, &pwt_configuration).unwrap();
+
+        let site = Site::new(Config::default(), Default::default(), Default::default());
+        let page_context = crate::test_util::page_context(&site, "Test");
+
+        let result = templates
+            .instantiate(
+                &pwt_configuration,
+                TemplateToInstantiate::Name(":Guide"),
+                &[],
+                &page_context,
+            )
+            .to_wikitext();
+        assert!(
+            result.contains("Usage: Spawn vehicles with '''care'''."),
+            "{result}"
+        );
+        assert!(!result.contains("Intro"), "{result}");
+        assert!(!result.contains("Outro"), "{result}");
+        assert!(site.warnings.is_empty());
+
+        let result = templates
+            .instantiate(
+                &pwt_configuration,
+                TemplateToInstantiate::Name(":Broken"),
+                &[],
+                &page_context,
+            )
+            .to_wikitext();
+        assert!(result.trim().is_empty(), "{result}");
+        assert_eq!(site.warnings.len(), 1);
+
+        let result = templates
+            .instantiate(
+                &pwt_configuration,
+                TemplateToInstantiate::Name("#lst:Loop"),
+                &[TemplateParameter {
+                    name: "1".to_string(),
+                    value: "a".to_string(),
+                }],
+                &page_context,
+            )
+            .to_wikitext();
+        assert_eq!(result.trim(), "Again");
+        assert_eq!(site.warnings.len(), 2);
+    }
+
+    #[test]
+    fn test_transclusion_cycle_is_detected() {
+        let mut loader = MockLoader::new();
+        loader.add("Ping", "Ping {{:Pong}}");
+        loader.add("Pong", "Pong {{:Ping}}");
+
+        let pwt_configuration = wikitext_simplified::wikitext_util::wikipedia_pwt_configuration();
+        let mut templates = Templates::new(loader, &pwt_configuration).unwrap();
+
+        let site = Site::new(Config::default(), Default::default(), Default::default());
+        let page_context = crate::test_util::page_context(&site, "Test");
+
+        let result = templates
+            .instantiate(
+                &pwt_configuration,
+                TemplateToInstantiate::Name(":Ping"),
+                &[],
+                &page_context,
+            )
+            .to_wikitext();
+
+        assert!(result.contains("Template loop detected: Ping"), "{result}");
+    }
+
+    #[test]
+    fn test_templates_load_with_configured_extensions() {
+        let dir = crate::test_util::temp_dir("template_extensions");
+        std::fs::create_dir_all(dir.join("Lua")).unwrap();
+        std::fs::write(dir.join("Lua/Greeting.wiki"), "Hello").unwrap();
+        std::fs::write(dir.join("Notes.txt"), "Not a template").unwrap();
+
+        let wikitext_only = FileSystemLoader::new(&dir, &["wikitext".to_string()]).unwrap();
+        assert!(wikitext_only.load("Lua/Greeting").is_err());
+
+        let loader =
+            FileSystemLoader::new(&dir, &["wikitext".to_string(), "wiki".to_string()]).unwrap();
+        assert_eq!(loader.load("Lua/Greeting").unwrap(), "Hello");
+        assert!(loader.load("Notes").is_err());
+    }
+
     #[test]
     fn test_non_table_template_uses_roundtrip() {
         // Verify that non-table templates still use the wikitext roundtrip
@@ -475,11 +1099,14 @@ mod tests {
         let pwt_configuration = wikitext_simplified::wikitext_util::wikipedia_pwt_configuration();
         let mut templates = Templates::new(loader, &pwt_configuration).unwrap();
 
+        let site = Site::new(Config::default(), Default::default(), Default::default());
         let page_context = PageContext {
             input_path: PathBuf::from("Test.wikitext"),
             title: "Test".to_string(),
             route_path: paxhtml::RoutePath::new(std::iter::empty(), Some("test.html".to_string())),
             sub_page_name: "Test".to_string(),
+            site: &site,
+            state: Default::default(),
         };
 
         // Instantiate the template
@@ -506,4 +1133,132 @@ mod tests {
             _ => panic!("Expected Bold or Fragment with Bold node, got {:?}", result),
         }
     }
+
+    #[test]
+    fn test_if_parser_function_picks_a_branch() {
+        let mut loader = MockLoader::new();
+        loader.add(
+            "Given",
+            "{{#if:{{{1}}}|'''Given:''' {{{1}}}|Nothing given}}",
+        );
+        loader.add("ThenOnly", "({{#if:{{{1}}}|yes}})");
+        loader.add("Guarded", "{{#if:{{{1}}}|{{Missing}}|fine}}");
+
+        let pwt_configuration = wikitext_simplified::wikitext_util::wikipedia_pwt_configuration();
+        let mut templates = Templates::new(loader, &pwt_configuration).unwrap();
+
+        let site = Site::new(Config::default(), Default::default(), Default::default());
+        let page_context = crate::test_util::page_context(&site, "Test");
+
+        let mut instantiate = |name, value: &str| {
+            let parameters = [TemplateParameter {
+                name: "1".to_string(),
+                value: value.to_string(),
+            }];
+            templates
+                .instantiate(
+                    &pwt_configuration,
+                    TemplateToInstantiate::Name(name),
+                    &parameters,
+                    &page_context,
+                )
+                .to_wikitext()
+        };
+
+        // The branch taken is markup, and is parsed as such
+        let result = instantiate("Given", "Vehicle");
+        assert_eq!(result.trim(), "'''Given:''' Vehicle");
+        // Whitespace is falsy
+        let result = instantiate("Given", "  ");
+        assert_eq!(result.trim(), "Nothing given");
+        // A missing branch is empty
+        let result = instantiate("ThenOnly", "");
+        assert_eq!(result.trim(), "()");
+        let result = instantiate("ThenOnly", "x");
+        assert_eq!(result.trim(), "(yes)");
+        // The branch that isn't taken isn't instantiated
+        let result = instantiate("Guarded", "");
+        assert_eq!(result.trim(), "fine");
+        assert!(site.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_switch_parser_function_falls_through_to_results_and_default() {
+        let mut loader = MockLoader::new();
+        loader.add(
+            "VehicleKind",
+            "{{#switch: {{{1}}} | car | truck = Land | boat = Sea | plane = {{Missing}} | #default = Unknown }}",
+        );
+        loader.add("NoDefault", "({{#switch: {{{1}}} | car = Land }})");
+        loader.add("Number", "({{#switch: {{{1}}} | 1 = One | 2 = Two }})");
+        loader.add(
+            "NumberOrOther",
+            "({{#switch: {{{1}}} | 1 = One | 2 = Two | #default = Other }})",
+        );
+
+        let pwt_configuration = wikitext_simplified::wikitext_util::wikipedia_pwt_configuration();
+        let mut templates = Templates::new(loader, &pwt_configuration).unwrap();
+
+        let site = Site::new(Config::default(), Default::default(), Default::default());
+        let page_context = crate::test_util::page_context(&site, "Test");
+
+        let mut instantiate = |name, value: &str| {
+            let parameters = [TemplateParameter {
+                name: "1".to_string(),
+                value: value.to_string(),
+            }];
+            templates
+                .instantiate(
+                    &pwt_configuration,
+                    TemplateToInstantiate::Name(name),
+                    &parameters,
+                    &page_context,
+                )
+                .to_wikitext()
+        };
+
+        assert_eq!(instantiate("VehicleKind", "car").trim(), "Land");
+        assert_eq!(instantiate("VehicleKind", "truck").trim(), "Land");
+        assert_eq!(instantiate("VehicleKind", "boat").trim(), "Sea");
+        assert_eq!(instantiate("VehicleKind", "Boat").trim(), "Unknown");
+        assert_eq!(instantiate("NoDefault", "boat").trim(), "()");
+        // Numeric keys are cases, not positional arguments
+        assert_eq!(instantiate("Number", "1").trim(), "(One)");
+        assert_eq!(instantiate("Number", "2").trim(), "(Two)");
+        assert_eq!(instantiate("Number", "3").trim(), "()");
+        assert_eq!(instantiate("NumberOrOther", "1").trim(), "(One)");
+        assert_eq!(instantiate("NumberOrOther", "3").trim(), "(Other)");
+        assert!(site.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_noinclude_documentation_is_not_transcluded() {
+        let mut loader = MockLoader::new();
+        loader.add(
+            "Vehicle",
+            "'''{{{1}}}'''<noinclude>
+== Usage ==
+Shows a vehicle's name in bold.</noinclude><includeonly> (vehicle)</includeonly>",
+        );
+
+        let pwt_configuration = wikitext_simplified::wikitext_util::wikipedia_pwt_configuration();
+        let mut templates = Templates::new(loader, &pwt_configuration).unwrap();
+        let site = Site::new(Config::default(), Default::default(), Default::default());
+        let page_context = crate::test_util::page_context(&site, "Test");
+
+        let result = templates
+            .instantiate(
+                &pwt_configuration,
+                TemplateToInstantiate::Name("Vehicle"),
+                &[TemplateParameter {
+                    name: "1".to_string(),
+                    value: "Buzzard".to_string(),
+                }],
+                &page_context,
+            )
+            .to_wikitext();
+        assert_eq!(result.trim(), "'''Buzzard''' (vehicle)");
+        assert!(!result.contains("Usage"), "{result}");
+        assert!(!result.contains("noinclude"), "{result}");
+    }
 }
@@ -0,0 +1,105 @@
+//! The parameters a template declares in a `<templatedata>` block, which calls to it are
+//! checked against with `--check`
+
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+use wikitext_simplified::TemplateParameter;
+
+const OPEN_TAG: &str = "<templatedata>";
+const CLOSE_TAG: &str = "</templatedata>";
+
+/// A template's declared parameters, in the JSON format of MediaWiki's TemplateData:
+/// `{"params": {"name": {"required": true, "aliases": ["title"]}}}`
+#[derive(Debug, Default, Deserialize)]
+pub struct TemplateData {
+    #[serde(default)]
+    params: BTreeMap<String, ParamDeclaration>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ParamDeclaration {
+    required: bool,
+    aliases: Vec<String>,
+}
+
+impl TemplateData {
+    /// Split the `<templatedata>` block out of a template's `source`, returning the source
+    /// without it and the declaration it contains, if any
+    pub fn extract(source: &str) -> anyhow::Result<(String, Option<Self>)> {
+        let Some(start) = source.find(OPEN_TAG) else {
+            return Ok((source.to_string(), None));
+        };
+        let Some(end) = source[start..].find(CLOSE_TAG).map(|i| start + i) else {
+            anyhow::bail!("Unclosed {OPEN_TAG} block");
+        };
+        let data = serde_json::from_str(&source[start + OPEN_TAG.len()..end])
+            .map_err(|e| anyhow::anyhow!("Invalid {OPEN_TAG} block: {e}"))?;
+        let remaining = format!("{}{}", &source[..start], &source[end + CLOSE_TAG.len()..]);
+        Ok((remaining, Some(data)))
+    }
+
+    /// The problems with calling the template with `parameters`: parameters it doesn't
+    /// declare, and required parameters that are missing
+    pub fn problems(&self, parameters: &[TemplateParameter]) -> Vec<String> {
+        let declared = |name: &str| {
+            self.params.iter().find(|(declared, declaration)| {
+                declared.as_str() == name || declaration.aliases.iter().any(|a| a == name)
+            })
+        };
+
+        let mut problems = vec![];
+        for parameter in parameters {
+            let name = parameter.name.trim();
+            if declared(name).is_none() {
+                problems.push(format!("unknown parameter `{name}`"));
+            }
+        }
+        for (name, declaration) in &self.params {
+            let passed = parameters.iter().any(|parameter| {
+                declared(parameter.name.trim()).is_some_and(|(declared, _)| declared == name)
+            });
+            if declaration.required && !passed {
+                problems.push(format!("missing required parameter `{name}`"));
+            }
+        }
+        problems
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_calls_are_checked_against_declared_parameters() {
+        let (source, data) = TemplateData::extract(
+            r#"'''{{{name}}}''' {{{1|}}}<templatedata>
+{"params": {"name": {"required": true, "aliases": ["title"]}, "1": {}}}
+</templatedata>"#,
+        )
+        .unwrap();
+        assert_eq!(source, "'''{{{name}}}''' {{{1|}}}");
+        let data = data.unwrap();
+
+        let parameter = |name: &str| TemplateParameter {
+            name: name.to_string(),
+            value: "Vehicle".to_string(),
+        };
+        assert!(
+            data.problems(&[parameter("name"), parameter("1")])
+                .is_empty()
+        );
+        assert!(data.problems(&[parameter("title")]).is_empty());
+        assert_eq!(
+            data.problems(&[parameter("nmae")]),
+            [
+                "unknown parameter `nmae`",
+                "missing required parameter `name`"
+            ]
+        );
+
+        assert!(TemplateData::extract("No declaration").unwrap().1.is_none());
+    }
+}
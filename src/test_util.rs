@@ -0,0 +1,87 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use crate::{
+    page_context::PageContext,
+    site::Site,
+    template::{TemplateLoader, Templates},
+};
+
+/// In-memory template loader for testing
+#[derive(Default)]
+pub struct MockLoader {
+    templates: HashMap<String, String>,
+}
+
+impl MockLoader {
+    pub fn new() -> Self {
+        Self {
+            templates: HashMap::new(),
+        }
+    }
+
+    pub fn add(&mut self, name: &str, content: &str) {
+        let key = name.to_lowercase().replace(" ", "_");
+        self.templates.insert(key, content.to_string());
+    }
+}
+
+impl TemplateLoader for MockLoader {
+    fn load(&self, name: &str) -> anyhow::Result<String> {
+        let key = name.to_lowercase().replace(" ", "_");
+        self.templates
+            .get(&key)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Template not found: {}", name))
+    }
+}
+
+/// Create the context for a page with the given title
+pub fn page_context<'a>(site: &'a Site, title: &str) -> PageContext<'a> {
+    let sub_page_name = title.rsplit('/').next().unwrap().replace(" ", "_");
+    PageContext {
+        input_path: PathBuf::from(format!("{}.wikitext", title.replace(" ", "_"))),
+        title: title.to_string(),
+        route_path: crate::page_title_to_route_path(&site.config, title),
+        sub_page_name,
+        site,
+        state: Default::default(),
+    }
+}
+
+/// Render the body of a page with the given title and wikitext to HTML
+pub fn render_wikitext(site: &Site, loader: MockLoader, title: &str, wikitext: &str) -> String {
+    let pwt_configuration = wikitext_simplified::wikitext_util::wikipedia_pwt_configuration();
+    let mut templates = Templates::new(loader, &pwt_configuration).unwrap();
+    let page_context = page_context(site, title);
+
+    let nodes =
+        wikitext_simplified::parse_and_simplify_wikitext(wikitext, &pwt_configuration).unwrap();
+    let html = render_to_string(paxhtml::Element::from_iter(crate::convert_page_content(
+        &mut templates,
+        &pwt_configuration,
+        &nodes,
+        &page_context,
+    )));
+    page_context
+        .state
+        .borrow_mut()
+        .param_docs
+        .fill(&html)
+        .unwrap()
+}
+
+/// Render an element to its HTML string for assertions
+pub fn render_to_string(element: paxhtml::Element) -> String {
+    paxhtml::Document::new([element]).write_to_string().unwrap()
+}
+
+/// Create an empty, uniquely-named directory under the system temporary directory
+pub fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "jc2mp-website-generator-{name}-{}",
+        std::process::id()
+    ));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
@@ -0,0 +1,202 @@
+//! The headings of a page, collected while rendering it, and the sticky "On this page"
+//! list of them shown beside long pages
+
+use crate::config::HeadingIds;
+
+/// The script that highlights the section being read in the "On this page" list,
+/// relative to the static directory
+pub const SCROLL_SPY_SCRIPT: &str = "js/toc.js";
+
+/// A heading on a page
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TocEntry {
+    /// The level of the heading as written in the wikitext (`==` is 2)
+    pub level: u8,
+    pub text: String,
+    /// The `id` of the rendered heading
+    pub id: String,
+}
+
+/// Whether a page's headings are listed, as set by the behavior switches in its
+/// wikitext
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TocSwitch {
+    /// Listed if there are enough of them
+    #[default]
+    Auto,
+    /// Listed however many there are (`__FORCETOC__` or `__TOC__`)
+    Force,
+    /// Never listed (`__NOTOC__`)
+    Hide,
+}
+impl TocSwitch {
+    const SWITCHES: [(&str, TocSwitch); 3] = [
+        ("__NOTOC__", TocSwitch::Hide),
+        ("__FORCETOC__", TocSwitch::Force),
+        ("__TOC__", TocSwitch::Force),
+    ];
+
+    /// Remove the switches from the wikitext `source`, returning it without them and the
+    /// switch they set. `__NOTOC__` wins over the others.
+    pub fn extract(source: &str) -> (String, Self) {
+        let mut source = source.to_string();
+        let mut switch = TocSwitch::Auto;
+        for (marker, marker_switch) in Self::SWITCHES {
+            if source.contains(marker) {
+                source = source.replace(marker, "");
+                if switch == TocSwitch::Auto {
+                    switch = marker_switch;
+                }
+            }
+        }
+        (source, switch)
+    }
+}
+
+/// A page's headings, and whether they're listed
+#[derive(Debug, Clone, Default)]
+pub struct Toc {
+    pub entries: Vec<TocEntry>,
+    pub switch: TocSwitch,
+}
+
+/// The `id` of a heading with the text `text`, in the style `style`, after `prefix`.
+/// Headings that would share an `id` with one in `existing` get a numeric suffix
+/// (`Usage_2`, or `usage-2` for slugs).
+pub fn heading_id(text: &str, style: HeadingIds, prefix: &str, existing: &[TocEntry]) -> String {
+    let (id, separator) = match style {
+        // As in MediaWiki: its words joined with underscores
+        HeadingIds::MediaWiki => (
+            text.split_whitespace()
+                .collect::<Vec<_>>()
+                .join("_")
+                .replace('"', ""),
+            '_',
+        ),
+        HeadingIds::Slug => {
+            let slug = text
+                .to_lowercase()
+                .chars()
+                .filter_map(|c| match c {
+                    c if c.is_alphanumeric() || c == '_' => Some(c),
+                    c if c.is_whitespace() || c == '-' => Some(' '),
+                    _ => None,
+                })
+                .collect::<String>();
+            let slug = slug.split_whitespace().collect::<Vec<_>>().join("-");
+            // Headings of only punctuation still need an `id`
+            (
+                if slug.is_empty() {
+                    "section".to_string()
+                } else {
+                    slug
+                },
+                '-',
+            )
+        }
+    };
+    let id = format!("{prefix}{id}");
+    let taken = |id: &str| existing.iter().any(|entry| entry.id == id);
+    if !taken(&id) {
+        return id;
+    }
+    (2..)
+        .map(|n| format!("{id}{separator}{n}"))
+        .find(|id| !taken(id))
+        .unwrap()
+}
+
+/// The "On this page" list of the headings in `toc`, if it's enabled and there are
+/// enough of them or the page forces it. Only the top two levels of headings are listed.
+pub fn render_sidebar(config: &crate::config::Config, toc: &Toc) -> Option<paxhtml::Element> {
+    use paxhtml::html;
+
+    let entries = &toc.entries;
+    let listed = match toc.switch {
+        TocSwitch::Auto => entries.len() >= config.toc_min_headings,
+        TocSwitch::Force => true,
+        TocSwitch::Hide => false,
+    };
+    if !config.on_this_page || !listed {
+        return None;
+    }
+    let top_level = entries.iter().map(|entry| entry.level).min()?;
+    let items = entries
+        .iter()
+        .filter(|entry| entry.level <= top_level + 1)
+        .map(|entry| {
+            let indent = if entry.level > top_level { "pl-3" } else { "" };
+            html! {
+                <li class={indent}>
+                    <a class="block py-0.5 text-gray-600 hover:text-gray-900" href={format!("#{}", entry.id)}>
+                        {entry.text.clone()}
+                    </a>
+                </li>
+            }
+        })
+        .collect::<Vec<_>>();
+
+    Some(html! {
+        <nav id="on-this-page" class="hidden xl:block w-56 shrink-0 self-start sticky top-4 bg-white p-4 rounded-lg shadow-sm text-sm" aria-label="On this page">
+            <p class="mb-2 font-semibold text-gray-900">"On this page"</p>
+            <ul class="space-y-1">#{items}</ul>
+        </nav>
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_duplicate_headings_get_distinct_ids() {
+        let mut entries: Vec<TocEntry> = vec![];
+        for text in ["Usage", "Example code", "Usage", "Usage"] {
+            let id = heading_id(text, HeadingIds::MediaWiki, "", &entries);
+            entries.push(TocEntry {
+                level: 2,
+                text: text.to_string(),
+                id,
+            });
+        }
+        let ids = entries
+            .iter()
+            .map(|entry| entry.id.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, ["Usage", "Example_code", "Usage_2", "Usage_3"]);
+    }
+
+    #[test]
+    fn test_slug_ids_are_lowercase_and_hyphenated() {
+        let mut entries: Vec<TocEntry> = vec![];
+        for text in ["Example code", "What's new?", "Usage", "Usage", "???"] {
+            let id = heading_id(text, HeadingIds::Slug, "", &entries);
+            entries.push(TocEntry {
+                level: 2,
+                text: text.to_string(),
+                id,
+            });
+        }
+        let ids = entries
+            .iter()
+            .map(|entry| entry.id.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ids,
+            ["example-code", "whats-new", "usage", "usage-2", "section"]
+        );
+    }
+
+    #[test]
+    fn test_notoc_wins_over_forcetoc() {
+        assert_eq!(
+            TocSwitch::extract("__FORCETOC__\n== Usage =="),
+            ("\n== Usage ==".to_string(), TocSwitch::Force)
+        );
+        assert_eq!(
+            TocSwitch::extract("__FORCETOC__ __NOTOC__").1,
+            TocSwitch::Hide
+        );
+        assert_eq!(TocSwitch::extract("== Usage ==").1, TocSwitch::Auto);
+    }
+}
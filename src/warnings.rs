@@ -0,0 +1,36 @@
+use std::cell::RefCell;
+
+/// Problems found while generating the site that don't stop generation
+#[derive(Debug, Default)]
+pub struct Warnings {
+    warnings: RefCell<Vec<String>>,
+}
+impl Warnings {
+    pub fn warn(&self, message: impl Into<String>) {
+        self.warnings.borrow_mut().push(message.into());
+    }
+
+    pub fn len(&self) -> usize {
+        self.warnings.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.warnings.borrow().is_empty()
+    }
+
+    /// Print every warning, then fail if there were any and `deny_warnings` is set
+    pub fn finish(&self, deny_warnings: bool) -> anyhow::Result<()> {
+        for warning in self.warnings.borrow().iter() {
+            eprintln!("warning: {warning}");
+        }
+
+        if deny_warnings && !self.is_empty() {
+            anyhow::bail!(
+                "{} warning(s) were emitted and --deny-warnings is set",
+                self.len()
+            );
+        }
+
+        Ok(())
+    }
+}
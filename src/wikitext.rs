@@ -0,0 +1,390 @@
+//! Helpers for working with the simplified wikitext tree
+
+use wikitext_simplified::WikitextSimplifiedNode as WSN;
+
+/// The lists of child nodes directly contained by `node`, for walking the tree mutably
+pub fn children_mut(node: &mut WSN) -> Vec<&mut Vec<WSN>> {
+    match node {
+        WSN::Fragment { children }
+        | WSN::Heading { children, .. }
+        | WSN::Bold { children }
+        | WSN::Italic { children }
+        | WSN::Blockquote { children }
+        | WSN::Superscript { children }
+        | WSN::Subscript { children }
+        | WSN::Small { children }
+        | WSN::Preformatted { children }
+        | WSN::Tag { children, .. } => vec![children],
+        WSN::Table { captions, rows, .. } => captions
+            .iter_mut()
+            .map(|caption| &mut caption.content)
+            .chain(
+                rows.iter_mut()
+                    .flat_map(|row| row.cells.iter_mut().map(|cell| &mut cell.content)),
+            )
+            .collect(),
+        WSN::OrderedList { items } => items.iter_mut().map(|item| &mut item.content).collect(),
+        WSN::UnorderedList { items } => items.iter_mut().map(|item| &mut item.content).collect(),
+        WSN::DefinitionList { items } => items.iter_mut().map(|item| &mut item.content).collect(),
+        _ => vec![],
+    }
+}
+
+/// The lists of child nodes directly contained by `node`
+pub fn children(node: &WSN) -> Vec<&Vec<WSN>> {
+    match node {
+        WSN::Fragment { children }
+        | WSN::Heading { children, .. }
+        | WSN::Bold { children }
+        | WSN::Italic { children }
+        | WSN::Blockquote { children }
+        | WSN::Superscript { children }
+        | WSN::Subscript { children }
+        | WSN::Small { children }
+        | WSN::Preformatted { children }
+        | WSN::Tag { children, .. } => vec![children],
+        WSN::Table { captions, rows, .. } => captions
+            .iter()
+            .map(|caption| &caption.content)
+            .chain(
+                rows.iter()
+                    .flat_map(|row| row.cells.iter().map(|cell| &cell.content)),
+            )
+            .collect(),
+        WSN::OrderedList { items } => items.iter().map(|item| &item.content).collect(),
+        WSN::UnorderedList { items } => items.iter().map(|item| &item.content).collect(),
+        WSN::DefinitionList { items } => items.iter().map(|item| &item.content).collect(),
+        _ => vec![],
+    }
+}
+
+/// Whether `node` contains code or literal text, which content transformations should skip
+pub fn is_code(node: &WSN) -> bool {
+    match node {
+        WSN::Preformatted { .. } => true,
+        WSN::Tag { name, .. } => matches!(
+            name.as_str(),
+            "code" | "pre" | "nowiki" | "syntaxhighlight" | "source" | "tt"
+        ),
+        _ => false,
+    }
+}
+
+/// Whether `node` renders as block-level content, which can't be placed inside a
+/// paragraph. A fragment (such as an instantiated template) is block-level if anything
+/// in it is.
+pub fn is_block(node: &WSN) -> bool {
+    match node {
+        WSN::Heading { .. }
+        | WSN::Table { .. }
+        | WSN::OrderedList { .. }
+        | WSN::UnorderedList { .. }
+        | WSN::DefinitionList { .. }
+        | WSN::Blockquote { .. }
+        | WSN::Preformatted { .. }
+        | WSN::HorizontalDivider
+        | WSN::Redirect { .. } => true,
+        WSN::Tag { name, .. } => matches!(
+            name.to_lowercase().as_str(),
+            "div"
+                | "p"
+                | "table"
+                | "pre"
+                | "syntaxhighlight"
+                | "source"
+                | "references"
+                | "blockquote"
+                | "center"
+                | "ul"
+                | "ol"
+                | "dl"
+                | "hr"
+                | "h1"
+                | "h2"
+                | "h3"
+                | "h4"
+                | "h5"
+                | "h6"
+                | "figure"
+                | "gallery"
+        ),
+        WSN::Fragment { children } => children.iter().any(is_block),
+        _ => false,
+    }
+}
+
+/// The readable text of `nodes` without any markup, with headings, paragraphs, list
+/// items and table rows on lines of their own. Templates, images and footnotes are left
+/// out.
+pub fn plain_text(nodes: &[WSN]) -> String {
+    let mut text = String::new();
+    write_plain_text(nodes, &mut text);
+
+    // Collapse the blank lines left between blocks
+    let mut result = String::new();
+    let mut blank = false;
+    for line in text.lines().map(str::trim_end) {
+        if line.trim().is_empty() {
+            blank = !result.is_empty();
+            continue;
+        }
+        if blank {
+            result.push('\n');
+            blank = false;
+        }
+        result.push_str(line);
+        result.push('\n');
+    }
+    result
+}
+
+fn write_plain_text(nodes: &[WSN], text: &mut String) {
+    for node in nodes {
+        match node {
+            WSN::Text { text: t } => text.push_str(t),
+            WSN::Link { title, .. } if crate::media::file_name(title).is_some() => {}
+            WSN::Link { text: t, .. } => text.push_str(t),
+            WSN::ExtLink { link, text: t } => text.push_str(t.as_deref().unwrap_or(link)),
+            WSN::Newline => text.push('\n'),
+            WSN::ParagraphBreak | WSN::HorizontalDivider => text.push_str("\n\n"),
+            WSN::Tag { name, .. } if matches!(name.as_str(), "ref" | "references") => {}
+            WSN::Tag { name, .. } if name == "br" => text.push('\n'),
+            WSN::Heading { children, .. } => {
+                text.push_str("\n\n");
+                write_plain_text(children, text);
+                text.push_str("\n\n");
+            }
+            WSN::Table { captions, rows, .. } => {
+                text.push_str("\n\n");
+                for caption in captions {
+                    write_plain_text(&caption.content, text);
+                    text.push('\n');
+                }
+                for row in rows {
+                    for (i, cell) in row.cells.iter().enumerate() {
+                        if i > 0 {
+                            text.push_str(" | ");
+                        }
+                        let mut cell_text = String::new();
+                        write_plain_text(&cell.content, &mut cell_text);
+                        text.push_str(&cell_text.replace('\n', " "));
+                    }
+                    text.push('\n');
+                }
+                text.push('\n');
+            }
+            WSN::OrderedList { .. } | WSN::UnorderedList { .. } | WSN::DefinitionList { .. } => {
+                for item in children(node) {
+                    text.push('\n');
+                    write_plain_text(item, text);
+                }
+                text.push('\n');
+            }
+            WSN::Blockquote { .. } | WSN::Preformatted { .. } => {
+                text.push_str("\n\n");
+                for children in children(node) {
+                    write_plain_text(children, text);
+                }
+                text.push_str("\n\n");
+            }
+            node => {
+                for children in children(node) {
+                    write_plain_text(children, text);
+                }
+            }
+        }
+    }
+}
+
+/// Replace the numeric character references (`&#91;`, `&#x5B;`) in HTML `text` with
+/// the characters they stand for. Characters with a meaning in HTML stay escaped, and
+/// invalid references are left as they are.
+pub fn decode_numeric_references(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("&#") {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let decoded = after.split_once(';').and_then(|(number, remainder)| {
+            let code = match number.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => number.parse().ok()?,
+            };
+            Some((char::from_u32(code)?, remainder))
+        });
+
+        match decoded {
+            Some((c @ ('<' | '>' | '&' | '"'), remainder)) => {
+                result.push_str(&format!("&#{};", u32::from(c)));
+                rest = remainder;
+            }
+            Some((c, remainder)) => {
+                result.push(c);
+                rest = remainder;
+            }
+            None => {
+                result.push_str("&#");
+                rest = after;
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+/// The text of the rendered HTML `html`, without its tags and with its character
+/// references decoded
+pub fn html_text(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+        rest = rest[start..].split_once('>').map_or("", |(_, after)| after);
+    }
+    text.push_str(rest);
+
+    decode_numeric_references(&text)
+        .replace("&lt;", "<")
+        .replace("&#60;", "<")
+        .replace("&gt;", ">")
+        .replace("&#62;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#34;", "\"")
+        .replace("&amp;", "&")
+        .replace("&#38;", "&")
+}
+
+/// Look up the value of the attribute `name` in a tag's attribute string (e.g.
+/// `name="foo" responsive`). Attributes without a value yield an empty string.
+pub fn tag_attribute<'a>(attributes: &'a str, name: &str) -> Option<&'a str> {
+    tag_attributes(attributes)
+        .into_iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value)
+}
+
+/// Write attributes back out as an attribute string, quoting every value
+pub fn format_tag_attributes<'a>(
+    attributes: impl IntoIterator<Item = (&'a str, &'a str)>,
+) -> String {
+    attributes
+        .into_iter()
+        .map(|(key, value)| {
+            if value.contains('"') {
+                format!("{key}='{value}'")
+            } else {
+                format!("{key}=\"{value}\"")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Split a tag's attribute string into its keys and values, in order. Attributes
+/// without a value have an empty one.
+pub fn tag_attributes(attributes: &str) -> Vec<(&str, &str)> {
+    let mut result = vec![];
+    let mut rest = attributes.trim_start();
+    while !rest.is_empty() {
+        let key_end = rest
+            .find(|c: char| c == '=' || c.is_whitespace())
+            .unwrap_or(rest.len());
+        let key = &rest[..key_end];
+        rest = rest[key_end..].trim_start();
+
+        let mut value = "";
+        if let Some(after_equals) = rest.strip_prefix('=') {
+            let after_equals = after_equals.trim_start();
+            let (quoted_value, remainder) = match after_equals.chars().next() {
+                Some(quote @ ('"' | '\'')) => {
+                    let inner = &after_equals[1..];
+                    let end = inner.find(quote).unwrap_or(inner.len());
+                    (&inner[..end], inner.get(end + 1..).unwrap_or_default())
+                }
+                _ => {
+                    let end = after_equals
+                        .find(char::is_whitespace)
+                        .unwrap_or(after_equals.len());
+                    (&after_equals[..end], &after_equals[end..])
+                }
+            };
+            value = quoted_value;
+            rest = remainder.trim_start();
+        }
+
+        if !key.is_empty() {
+            result.push((key, value));
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_block() {
+        let text = |text: &str| WSN::Text {
+            text: text.to_string(),
+        };
+        let tag = |name: &str| WSN::Tag {
+            name: name.to_string(),
+            attributes: None,
+            children: vec![text("content")],
+        };
+        assert!(!is_block(&text("prose")));
+        assert!(!is_block(&tag("span")));
+        assert!(is_block(&tag("div")));
+        assert!(is_block(&WSN::Fragment {
+            children: vec![text("\n"), tag("DIV")],
+        }));
+        assert!(!is_block(&WSN::Fragment {
+            children: vec![text("a"), tag("kbd")],
+        }));
+    }
+
+    #[test]
+    fn test_plain_text() {
+        let nodes = wikitext_simplified::parse_and_simplify_wikitext(
+            "== Usage ==\nCreate a '''vehicle''' with [[Lua/Server/Vehicle|Vehicle.Create]].<ref>Server only</ref>\n\n* First\n* Second\n{{Template}}",
+            &wikitext_simplified::wikitext_util::wikipedia_pwt_configuration(),
+        )
+        .unwrap();
+        let text = plain_text(&nodes);
+        assert!(text.starts_with("Usage\n\n"), "{text:?}");
+        assert!(
+            text.contains("Create a vehicle with Vehicle.Create."),
+            "{text:?}"
+        );
+        assert!(text.contains("First\nSecond"), "{text:?}");
+        assert!(!text.contains("Server only"), "{text:?}");
+        assert!(!text.contains("Template"), "{text:?}");
+    }
+
+    #[test]
+    fn test_decode_numeric_references() {
+        assert_eq!(decode_numeric_references("&#91;1&#x5D;"), "[1]");
+        assert_eq!(decode_numeric_references("a &#60;b&#62;"), "a &#60;b&#62;");
+        assert_eq!(decode_numeric_references("&#nope; &#"), "&#nope; &#");
+    }
+
+    #[test]
+    fn test_html_text() {
+        assert_eq!(
+            html_text(r#"<span style="color:red">Removed</span> in 0.2"#),
+            "Removed in 0.2"
+        );
+        assert_eq!(html_text("a &amp;lt; &lt;b&#62; &#91;1]"), "a &lt; <b> [1]");
+    }
+
+    #[test]
+    fn test_tag_attribute() {
+        let attributes = r#"name="first note" group=notes responsive"#;
+        assert_eq!(tag_attribute(attributes, "name"), Some("first note"));
+        assert_eq!(tag_attribute(attributes, "group"), Some("notes"));
+        assert_eq!(tag_attribute(attributes, "responsive"), Some(""));
+        assert_eq!(tag_attribute(attributes, "missing"), None);
+        assert_eq!(tag_attribute("lang='lua'", "lang"), Some("lua"));
+    }
+}
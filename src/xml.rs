@@ -0,0 +1,10 @@
+//! Writing the XML files of the site (the feed, sitemap and MathML) by hand
+
+/// `text` with the characters that have a meaning in XML escaped, for use in text or an
+/// attribute value
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,74 @@
+// Searches the pages of the wiki from the box in the navigation bar. The index is only
+// fetched once the box is first used.
+(function () {
+  var input = document.getElementById("search");
+  var results = document.getElementById("search-results");
+  if (!input || !results) return;
+
+  var MAX_RESULTS = 10;
+  var index = null;
+  // The page URLs in the index are relative to it
+  var indexUrl = new URL(input.getAttribute("data-index"), document.baseURI);
+
+  function load() {
+    if (index) return index;
+    index = fetch(indexUrl)
+      .then(function (response) {
+        return response.json();
+      })
+      .then(function (entries) {
+        return Object.keys(entries).map(function (url) {
+          var entry = entries[url];
+          return {
+            url: new URL(url, indexUrl).href,
+            title: entry.title,
+            haystack: (entry.title + " " + entry.text).toLowerCase(),
+            titleLower: entry.title.toLowerCase(),
+          };
+        });
+      });
+    return index;
+  }
+
+  function render(matches) {
+    results.innerHTML = "";
+    matches.slice(0, MAX_RESULTS).forEach(function (entry) {
+      var item = document.createElement("li");
+      var link = document.createElement("a");
+      link.href = entry.url;
+      link.textContent = entry.title;
+      link.className = "block px-3 py-2 text-gray-900 hover:bg-gray-100";
+      item.appendChild(link);
+      results.appendChild(item);
+    });
+    results.hidden = matches.length === 0;
+  }
+
+  function search() {
+    var words = input.value.toLowerCase().split(/\s+/).filter(Boolean);
+    if (!words.length) {
+      render([]);
+      return;
+    }
+    load().then(function (entries) {
+      var matches = entries.filter(function (entry) {
+        return words.every(function (word) {
+          return entry.haystack.indexOf(word) !== -1;
+        });
+      });
+      // Pages with the words in their title come first
+      var inTitle = function (entry) {
+        return words.every(function (word) {
+          return entry.titleLower.indexOf(word) !== -1;
+        });
+      };
+      matches.sort(function (a, b) {
+        return inTitle(b) - inTitle(a);
+      });
+      render(matches);
+    });
+  }
+
+  input.addEventListener("focus", load);
+  input.addEventListener("input", search);
+})();
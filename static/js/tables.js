@@ -0,0 +1,29 @@
+// Hides the rows of long tables past the first few, with a button to show the rest.
+// Without scripts, every row is shown.
+(function () {
+  var tables = document.querySelectorAll("table[data-collapse-rows]");
+  Array.prototype.forEach.call(tables, function (table) {
+    var limit = parseInt(table.getAttribute("data-collapse-rows"), 10);
+    var body = table.tBodies[0];
+    if (!body || isNaN(limit)) return;
+
+    var hidden = Array.prototype.slice.call(body.rows, limit);
+    if (!hidden.length) return;
+    hidden.forEach(function (row) {
+      row.hidden = true;
+    });
+
+    var button = document.createElement("button");
+    button.type = "button";
+    button.className =
+      "mt-2 px-3 py-1 text-sm border border-gray-300 rounded hover:bg-gray-100";
+    button.textContent = "Show all " + body.rows.length + " rows";
+    button.addEventListener("click", function () {
+      hidden.forEach(function (row) {
+        row.hidden = false;
+      });
+      button.remove();
+    });
+    table.insertAdjacentElement("afterend", button);
+  });
+})();
@@ -0,0 +1,47 @@
+// Highlights the section currently being read in the "On this page" list
+(function () {
+  var nav = document.getElementById("on-this-page");
+  if (!nav) return;
+
+  var links = Array.prototype.slice.call(nav.querySelectorAll("a[href^='#']"));
+  var sections = links
+    .map(function (link) {
+      return document.getElementById(decodeURIComponent(link.hash.slice(1)));
+    })
+    .filter(Boolean);
+  var activeClasses = ["font-semibold", "text-blue-700"];
+
+  function update() {
+    // The active section is the last one whose heading has scrolled past the top
+    var active = sections[0];
+    sections.forEach(function (section) {
+      if (section.getBoundingClientRect().top <= 80) active = section;
+    });
+    links.forEach(function (link) {
+      var isActive = active && link.hash === "#" + encodeURIComponent(active.id);
+      activeClasses.forEach(function (c) {
+        link.classList.toggle(c, isActive);
+      });
+      if (isActive) {
+        link.setAttribute("aria-current", "true");
+      } else {
+        link.removeAttribute("aria-current");
+      }
+    });
+  }
+
+  var scheduled = false;
+  window.addEventListener(
+    "scroll",
+    function () {
+      if (scheduled) return;
+      scheduled = true;
+      window.requestAnimationFrame(function () {
+        scheduled = false;
+        update();
+      });
+    },
+    { passive: true }
+  );
+  update();
+})();